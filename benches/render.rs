@@ -0,0 +1,326 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use criterion::{criterion_group, criterion_main, Criterion};
+use crossterm::{QueueableCommand, cursor, style::{Print, PrintStyledContent}};
+use mrs_matrix::Frame;
+use mrs_matrix::animation::AnimationState;
+use mrs_matrix::raindrop::{Raindrop, RaindropConfig, RenderConfig};
+use mrs_matrix::raindrop::charsets::{Charset, AsciiAndSymbols};
+use mrs_matrix::raindrop::color_algorithms::{ColorAlgorithm, ColorContext, ColorScheme};
+use coolor::{Color, Hsl};
+
+const SEED: u64 = 0x6d72_735f_6d61_7472;
+
+const GREEN: ColorScheme = ColorScheme::Green;
+
+// the number of heap allocations made so far, counted by CountingAllocator
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// the system allocator, counting every allocation it makes
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8
+    {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout)
+    {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// returns one seeded raindrop per column, advanced far enough that the screen is in a steady state
+fn create_raindrops(charset: &Vec<char>, term_cols: u16, term_rows: u16) -> Vec<Raindrop<'_>>
+{
+    let mut raindrops: Vec<Raindrop> = (0..term_cols).map(|column_index| {
+        Raindrop::new_seeded(charset, 0.75, RaindropConfig::default(), term_rows, 
+            SEED + u64::from(column_index))
+    }).collect();
+
+    for _ in 0..100 {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(term_rows);
+        }
+    }
+
+    raindrops
+}
+
+// captures a frame in the default style, as the animation would on its first frame
+fn capture(raindrops: &[Raindrop], term_cols: u16, term_rows: u16) -> Frame
+{
+    Frame::capture(raindrops, term_cols, term_rows, &GREEN, &RenderConfig::default(), 0.0)
+}
+
+// compares the bytes written per frame by full redraws and diff-based redraws
+fn frame_output(c: &mut Criterion)
+{
+    const TERM_COLS: u16 = 80;
+    const TERM_ROWS: u16 = 24;
+    const MEASURED_FRAMES: usize = 100;
+
+    let charset = AsciiAndSymbols().get_charset();
+
+    //measure the average number of bytes emitted per frame with each approach
+    let mut raindrops = create_raindrops(&charset, TERM_COLS, TERM_ROWS);
+    let mut previous_frame = capture(&raindrops, TERM_COLS, TERM_ROWS);
+    let (mut full_bytes, mut diff_bytes) = (0, 0);
+    for _ in 0..MEASURED_FRAMES {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(TERM_ROWS);
+        }
+        let frame = capture(&raindrops, TERM_COLS, TERM_ROWS);
+        full_bytes += frame.render().len();
+        diff_bytes += frame.render_diff(&previous_frame).len();
+        previous_frame = frame;
+    }
+    println!("{}x{}: full redraw averages {} bytes/frame, diff averages {} bytes/frame",
+        TERM_COLS, TERM_ROWS, full_bytes / MEASURED_FRAMES, diff_bytes / MEASURED_FRAMES);
+
+    let mut group = c.benchmark_group("frame_output_80x24");
+
+    let mut raindrops = create_raindrops(&charset, TERM_COLS, TERM_ROWS);
+    group.bench_function("full_redraw", |b| b.iter(|| {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(TERM_ROWS);
+        }
+        capture(&raindrops, TERM_COLS, TERM_ROWS).render()
+    }));
+
+    let mut raindrops = create_raindrops(&charset, TERM_COLS, TERM_ROWS);
+    let mut previous_frame = capture(&raindrops, TERM_COLS, TERM_ROWS);
+    group.bench_function("diff", |b| b.iter(|| {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(TERM_ROWS);
+        }
+        let frame = capture(&raindrops, TERM_COLS, TERM_ROWS);
+        let output = frame.render_diff(&previous_frame);
+        previous_frame = frame;
+        output
+    }));
+
+    group.finish();
+}
+
+// colors followers as the green scheme does, but converts every color from HSL as it's drawn,
+// as the single-color schemes did before their colors were precomputed
+struct ConvertedGreen;
+
+impl ColorAlgorithm for ConvertedGreen {
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color
+    {
+        Color::Hsl(Hsl{h: 118.0, s: 1.0, l: (0.9 - follower_proportion).max(0.1)})
+    }
+}
+
+// compares converting each follower color from HSL against looking up precomputed colors
+fn follower_colors(c: &mut Criterion)
+{
+    const TERM_COLS: u16 = 200;
+    const TERM_ROWS: u16 = 50;
+
+    let charset = AsciiAndSymbols().get_charset();
+    let raindrops = create_raindrops(&charset, TERM_COLS, TERM_ROWS);
+    let render_config = RenderConfig::default();
+    let mut group = c.benchmark_group("follower_colors_200x50");
+
+    group.bench_function("converted", |b| b.iter(|| {
+        Frame::capture(&raindrops, TERM_COLS, TERM_ROWS, &ConvertedGreen, &render_config, 0.0)
+    }));
+
+    group.bench_function("precomputed", |b| b.iter(|| {
+        Frame::capture(&raindrops, TERM_COLS, TERM_ROWS, &GREEN, &render_config, 0.0)
+    }));
+
+    group.finish();
+}
+
+// compares capturing a full screen of rain against sparse rain, where most lanes are empty
+fn sparse_capture(c: &mut Criterion)
+{
+    const TERM_COLS: u16 = 200;
+    const TERM_ROWS: u16 = 50;
+
+    let charset = AsciiAndSymbols().get_charset();
+    let render_config = RenderConfig::default();
+    let mut group = c.benchmark_group("capture_200x50");
+
+    for density in [1.0, 0.1] {
+        let config = RaindropConfig{density, ..RaindropConfig::default()};
+        let mut raindrops: Vec<Raindrop> = (0..TERM_COLS).map(|column_index| {
+            Raindrop::new_seeded(&charset, 0.75, config, TERM_ROWS, SEED + u64::from(column_index))
+        }).collect();
+        for _ in 0..100 {
+            for raindrop in raindrops.iter_mut() {
+                raindrop.advance_animation(TERM_ROWS);
+            }
+        }
+
+        group.bench_function(format!("density_{}", density), |b| b.iter(|| {
+            Frame::capture(&raindrops, TERM_COLS, TERM_ROWS, &GREEN, &render_config, 0.0)
+        }));
+    }
+
+    group.finish();
+}
+
+// compares capturing every lane on one thread against capturing lanes in parallel
+#[cfg(feature = "parallel")]
+fn parallel_capture(c: &mut Criterion)
+{
+    const TERM_COLS: u16 = 400;
+    const TERM_ROWS: u16 = 100;
+
+    let charset = AsciiAndSymbols().get_charset();
+    let render_config = RenderConfig::default();
+    let lanes: Vec<Vec<Raindrop>> = create_raindrops(&charset, TERM_COLS, TERM_ROWS)
+        .into_iter()
+        .map(|raindrop| vec![raindrop])
+        .collect();
+    let mut group = c.benchmark_group("capture_400x100");
+
+    group.bench_function("single_threaded", |b| b.iter(|| {
+        Frame::capture_lanes(&lanes, TERM_COLS, TERM_ROWS, &GREEN, &render_config, 0.0)
+    }));
+
+    group.bench_function("parallel", |b| b.iter(|| {
+        Frame::capture_lanes_parallel(&lanes, TERM_COLS, TERM_ROWS, &GREEN, &render_config, 0.0)
+    }));
+
+    group.finish();
+}
+
+// returns a line buffered writer to the null device, which behaves like stdout without
+// the cost of a terminal actually drawing anything
+fn null_writer() -> LineWriter<File>
+{
+    let null_path = if cfg!(windows) {"NUL"} else {"/dev/null"};
+    LineWriter::new(File::create(null_path).expect("failed to open null device"))
+}
+
+// compares queueing a command per cell against writing each frame with a single write
+fn frame_write(c: &mut Criterion)
+{
+    const TERM_COLS: u16 = 200;
+    const TERM_ROWS: u16 = 50;
+
+    let charset = AsciiAndSymbols().get_charset();
+    let mut group = c.benchmark_group("frame_write_200x50");
+
+    let mut raindrops = create_raindrops(&charset, TERM_COLS, TERM_ROWS);
+    let mut out = null_writer();
+    group.bench_function("queue_per_cell", |b| b.iter(|| {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(TERM_ROWS);
+        }
+
+        //this is how frames were written before being batched
+        out.queue(cursor::MoveTo(0,0)).unwrap();
+        for row_index in 0..TERM_ROWS {
+            out.queue(cursor::MoveToRow(row_index + 1)).unwrap()
+            .queue(cursor::MoveToColumn(1)).unwrap();
+            for raindrop in raindrops.iter() {
+                match raindrop.get_styled_char_at_row(row_index, &GREEN, &RenderConfig::default(), 
+                    &ColorContext::default()) {
+                    None => out.queue(Print(" ")).unwrap(),
+                    Some(styled_char) => out.queue(PrintStyledContent(styled_char)).unwrap()
+                };
+            }
+        }
+        out.flush().unwrap();
+    }));
+
+    let mut raindrops = create_raindrops(&charset, TERM_COLS, TERM_ROWS);
+    let mut out = null_writer();
+    group.bench_function("batched", |b| b.iter(|| {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(TERM_ROWS);
+        }
+
+        let output = capture(&raindrops, TERM_COLS, TERM_ROWS).render();
+        out.write_all(output.as_bytes()).unwrap();
+        out.flush().unwrap();
+    }));
+
+    group.finish();
+}
+
+// the terminal sizes the animation is measured at, from a small terminal to a large one
+const ANIMATION_SIZES: [(u16, u16); 3] = [(80, 24), (200, 50), (400, 100)];
+
+// measures stepping the animation and rendering the frame it captures, as the loop does every 
+// frame, along with creating the raindrops it starts with, at a range of terminal sizes
+fn animation(c: &mut Criterion)
+{
+    let charset = AsciiAndSymbols().get_charset();
+    let create_state = |term_cols, term_rows| AnimationState::new(&charset, GREEN, 0.75, 
+        RaindropConfig::default(), Some(SEED), term_cols, term_rows);
+
+    let mut group = c.benchmark_group("step_and_render");
+    for (term_cols, term_rows) in ANIMATION_SIZES {
+        let mut state = create_state(term_cols, term_rows);
+        for _ in 0..100 {
+            state.step();
+        }
+        let mut output = String::new();
+        group.bench_function(format!("{}x{}", term_cols, term_rows), |b| b.iter(|| {
+            state.step();
+            state.frame().render_into(&mut output);
+        }));
+    }
+    group.finish();
+
+    //creating an AnimationState creates a raindrop for every lane, and captures the first frame
+    let mut group = c.benchmark_group("create_raindrops");
+    for (term_cols, term_rows) in ANIMATION_SIZES {
+        group.bench_function(format!("{}x{}", term_cols, term_rows), |b| b.iter(|| {
+            create_state(term_cols, term_rows)
+        }));
+    }
+    group.finish();
+}
+
+// measures respawning every raindrop, as happens constantly while the animation runs
+// (build with --features smallvec to compare against followers kept on the stack)
+fn respawn(c: &mut Criterion)
+{
+    const TERM_COLS: u16 = 200;
+    const TERM_ROWS: u16 = 50;
+    const MEASURED_RESPAWNS: usize = 100;
+
+    let charset = AsciiAndSymbols().get_charset();
+    let mut raindrops = create_raindrops(&charset, TERM_COLS, TERM_ROWS);
+
+    //count the average number of heap allocations made by respawning every raindrop
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..MEASURED_RESPAWNS {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.reinit_state(TERM_ROWS);
+        }
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+    println!("{}x{}: respawning every raindrop averages {} allocations", 
+        TERM_COLS, TERM_ROWS, allocations / MEASURED_RESPAWNS);
+
+    c.bench_function("respawn_200x50", |b| b.iter(|| {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.reinit_state(TERM_ROWS);
+        }
+    }));
+}
+
+#[cfg(not(feature = "parallel"))]
+criterion_group!(benches, frame_output, frame_write, follower_colors, sparse_capture, animation, 
+    respawn);
+#[cfg(feature = "parallel")]
+criterion_group!(benches, frame_output, frame_write, follower_colors, sparse_capture, animation, 
+    respawn, parallel_capture);
+criterion_main!(benches);
@@ -1,48 +1,1356 @@
 //! Functions relating directly to drawing animations on the screen
 //! 
-use std::io::{stdout, Write};
+use std::collections::VecDeque;
+use std::env;
+use std::io::{stdout, IsTerminal, Write};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Instant, Duration};
 use crossterm::{
     self,
-    event::{self, Event},
-    QueueableCommand, 
-    style::{Print, PrintStyledContent},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind},
+    Command,
+    style::{ContentStyle, Print, PrintStyledContent, StyledContent},
     terminal,
     cursor
 };
-use crate::raindrop::{Raindrop, color_algorithms::ColorAlgorithm};
+use crate::backend::{Backend, CrosstermBackend};
+use crate::decode::DecodeEffect;
+use crate::error::MatrixError;
+use crate::raindrop::{FollowerBold, Raindrop, RaindropConfig, RenderConfig};
+use crate::raindrop::charsets::CharSampler;
+use crate::raindrop::color_depth::{terminal_color_to_rgb, ColorDepth};
+use crate::raindrop::color_algorithms::{ColorAlgorithm, ColorContext, ColorScheme};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-/// Returns a `Vec<Raindrop>` with one `Raindrop` for each terminal column
+/// The amount the target framerate changes by when `+` or `-` is pressed
+const FRAMERATE_STEP: usize = 5;
+
+/// The range the target framerate is clamped to when changed with `+` or `-`
+const FRAMERATE_RANGE: std::ops::RangeInclusive<usize> = 1..=240;
+
+/// The amount the brightness changes by when `[` or `]` is pressed
+const BRIGHTNESS_STEP: f32 = 0.1;
+
+/// The number of most recent frames the FPS overlay averages over
+const FPS_SAMPLE_FRAMES: usize = 30;
+
+/// How long the screen takes to fade to black on exit, when fading out is turned on
+const FADE_OUT_DURATION: Duration = Duration::from_millis(500);
+
+/// The number of steps a ripple lasts, from the click that started it until it has faded away
+const RIPPLE_FRAMES: u32 = 12;
+
+/// The radius (in columns) a ripple spreads out to before it fades away
+const RIPPLE_RADIUS: f32 = 8.0;
+
+/// The size (in columns and rows) assumed for the terminal when its real size can't be 
+/// determined and the `COLUMNS` and `LINES` environment variables aren't set; 
+/// see [anim_loop_with_writer]
+pub const FALLBACK_TERMINAL_SIZE: (u16, u16) = (80, 24);
+
+/// Returns a new `Raindrop` for the lane at `lane_index`
+/// 
+/// If `seed` is provided, the `Raindrop` is seeded with a value derived from `seed` and 
+/// `lane_index`, so that each lane behaves differently but reproducibly.
+/// 
+/// Note that this function is intentionally private because it's unlikely to be generally useful
+fn create_raindrop(charset: &Vec<char>, advance_chance:f64, config: RaindropConfig, 
+    seed: Option<u64>, lane_index: u16, lane_length: u16) 
+-> Raindrop<'_>
+{
+    match seed {
+        None => Raindrop::new(
+            charset, advance_chance, config, lane_length),
+        //offset the seed by lane index so that each lane behaves differently
+        Some(seed) => Raindrop::new_seeded(
+            charset, advance_chance, config, lane_length, 
+            seed.wrapping_add(lane_index.into()))
+    }
+}
+
+/// Returns a `Vec<Raindrop>` with one `Raindrop` for each lane of the terminal
+/// 
+/// Lanes are terminal columns, or rows if `config.direction` is horizontal; 
+/// see [Direction](crate::raindrop::Direction).
 /// 
 /// `charset` should be a reference to a Vector of chars. This will be the set of 
 /// characters that the raindrops will be generated from.
 /// 
 /// `advance_chance` is the chance that a `Raindrop` will advance on any given frame.
 /// 
+/// `config` is the [RaindropConfig] that each `Raindrop` will be created with.
+/// 
+/// `seed` is an optional seed for random number generation. If provided, each `Raindrop`
+/// is seeded with a value derived from `seed` and its lane index, making the animation reproducible.
+/// 
 /// `terminal_width` should be the width of the terminal in columns
 /// 
 /// `terminal_height` should be the height of the terminal in rows
 /// 
 /// Note that this function is intentionally private because it's unlikely to be generally useful
-fn create_raindrops<T>(charset: &Vec<char>, color_algorithm: T, 
-    advance_chance:f64, terminal_width: u16, terminal_height: u16) 
--> Vec<Raindrop<T>>
+fn create_raindrops(charset: &Vec<char>, advance_chance:f64, config: RaindropConfig, 
+    seed: Option<u64>, terminal_width: u16, terminal_height: u16) 
+-> Vec<Raindrop<'_>>
+{
+    let (lane_count, lane_length) = config.direction.lane_dimensions(terminal_width, terminal_height);
+
+    (0..lane_count)
+        .map(|lane_index| create_raindrop(
+            charset, advance_chance, config, seed, lane_index, lane_length))
+        .collect()
+}
+
+/// Fits a `Vec<Raindrop>` (with one `Raindrop` per lane) to a resized terminal
+/// 
+/// Lanes are terminal columns, or rows if `config.direction` is horizontal; 
+/// see [Direction](crate::raindrop::Direction).
+/// 
+/// Rather than recreating every `Raindrop`, the existing ones keep their positions:
+/// - If there are fewer lanes, the `Raindrop`s for the removed lanes are dropped.
+/// - If there are more lanes, new `Raindrop`s are created for the added lanes.
+/// - Any `Raindrop` that has fallen entirely past the end of a shortened lane is 
+///   reinitialized with [reinit_state](crate::raindrop::Raindrop::reinit_state). The rest keep 
+///   falling as before, with anything past the edge of the terminal simply clipped.
+/// 
+/// `charset`, `advance_chance`, `config`, and `seed` are used to create the `Raindrop`s 
+/// for any added lanes, and should match those used to create the existing ones.
+/// 
+/// `terminal_width` and `terminal_height` should be the new size of the terminal.
+pub fn resize_raindrops<'a>(raindrops: &mut Vec<Raindrop<'a>>, charset: &'a Vec<char>, 
+    advance_chance: f64, config: RaindropConfig, seed: Option<u64>, 
+    terminal_width: u16, terminal_height: u16)
+{
+    let (lane_count, lane_length) = config.direction.lane_dimensions(terminal_width, terminal_height);
+
+    //drop any lanes that no longer fit, then add drops for any new lanes
+    let previous_lane_count = raindrops.len() as u16;
+    raindrops.truncate(lane_count.into());
+    raindrops.extend((previous_lane_count..lane_count)
+        .map(|lane_index| create_raindrop(
+            charset, advance_chance, config, seed, lane_index, lane_length)));
+
+    for raindrop in raindrops.iter_mut() {
+        restart_if_past_end(raindrop, lane_length);
+    }
+}
+
+/// Restarts a `Raindrop` that was left entirely past the end of a shortened lane
+/// 
+/// Note that this function is intentionally private because it's unlikely to be generally useful
+fn restart_if_past_end(raindrop: &mut Raindrop, lane_length: u16)
+{
+    if raindrop.row_index() >= lane_length.into() && !raindrop.is_visible(lane_length) {
+        raindrop.reinit_state(lane_length);
+    }
+}
+
+/// Captures the cells of a single lane, passing the index of each cell (counted row by row) 
+/// and its contents to `set_cell`
+/// 
+/// Lanes past the edge of the terminal, and empty lanes, are skipped, as are any cells 
+/// outside of the stretch of the lane its raindrops cover; callers should start from an 
+/// empty frame, so that those cells are left empty.
+/// 
+/// Note that this function is intentionally private because it's unlikely to be generally useful
+#[allow(clippy::too_many_arguments)]
+fn capture_lane<T, F>(lane: &[Raindrop], lane_index: usize, term_cols: u16, term_rows: u16, 
+    color_algorithm: &T, render_config: &RenderConfig, phase: f32, mut set_cell: F)
+where T: ColorAlgorithm, F: FnMut(usize, Option<StyledContent<char>>)
+{
+    let direction = match lane.first() {
+        Some(raindrop) => raindrop.direction(),
+        None => return
+    };
+    let (lane_count, lane_length) = direction.lane_dimensions(term_cols, term_rows);
+    if lane_index >= lane_count.into() {
+        return;
+    }
+    let lane_index = lane_index as u16;
+
+    //only the positions between the furthest tail and the furthest leader can hold any chars,
+    //so when the rain is sparse, most lanes are skipped without querying their raindrops at all
+    let first_position = lane.iter().map(Raindrop::tail_row_index).min().unwrap_or(0).max(0);
+    let last_position = lane.iter().map(Raindrop::row_index).max().unwrap_or(-1)
+        .min(i32::from(lane_length) - 1);
+    if first_position > last_position {
+        return;
+    }
+
+    //raindrops count positions from the edge they enter from
+    for position in first_position as u16..=last_position as u16 {
+        let position_on_screen = if direction.is_reversed() {
+            lane_length - 1 - position
+        } else {
+            position
+        };
+        //in the wind, each cell drifts sideways by more the further along the lane it is,
+        //wrapping around to the other side of the screen
+        let drifted_lane_index = (i32::from(lane_index) 
+            + render_config.wind_offset(phase, position_on_screen))
+            .rem_euclid(i32::from(lane_count)) as u16;
+        let (column_index, row_index) = if direction.is_horizontal() {
+            (position_on_screen, drifted_lane_index)
+        } else {
+            (drifted_lane_index, position_on_screen)
+        };
+
+        let color_context = ColorContext{phase, row_index, term_rows, column_index, term_cols};
+
+        //where raindrops overlap, draw the one that has travelled the least on top
+        let cell = lane.iter()
+            .filter_map(|raindrop| {
+                let raindrop_row_index = raindrop.row_index();
+                raindrop.get_styled_char_at_row(position, color_algorithm, render_config, 
+                    &color_context)
+                    .map(|styled_char| (raindrop_row_index, styled_char))
+            })
+            .min_by_key(|(raindrop_row_index, _)| *raindrop_row_index)
+            .map(|(_, styled_char)| styled_char);
+        set_cell(usize::from(row_index) * usize::from(term_cols) + usize::from(column_index), cell);
+    }
+}
+
+/// A grid of the styled characters making up a single frame of animation
+/// 
+/// A `Frame` can be rendered in full with [render](crate::animation::Frame::render), or compared
+/// with the previously drawn `Frame` using [render_diff](crate::animation::Frame::render_diff) so
+/// that only the cells that changed are redrawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    term_cols: u16,
+    term_rows: u16,
+
+    // cells are stored row by row; None represents an empty (space) cell
+    cells: Vec<Option<StyledContent<char>>>
+}
+
+impl Frame {
+
+    /// Returns a new `Frame` holding the current state of `raindrops`
+    /// 
+    /// `raindrops` should be a slice of `Raindrop`s, one per lane (terminal column, or row for 
+    /// horizontal directions). If there are more `Raindrop`s than lanes, the extra `Raindrop`s 
+    /// are not captured; if there are fewer, the remaining lanes are left empty.
+    /// 
+    /// `term_cols` should be the width of the terminal in columns.
+    /// 
+    /// `term_rows` should be the height of the terminal in rows.
+    /// 
+    /// `color_algorithm` should be a reference to a type implementing [ColorAlgorithm]; it is
+    /// used to color the follower characters.
+    /// 
+    /// `render_config` should be a reference to a [RenderConfig], which controls how 
+    /// the leader characters are styled.
+    /// 
+    /// `phase` should be the number of frames the animation has advanced by; it is passed
+    /// to the `color_algorithm` so colors can change over time.
+    /// 
+    /// Each `Raindrop` is drawn according to its [Direction](crate::raindrop::Direction), 
+    /// which decides whether its lane is a column or a row and which edge of the terminal 
+    /// it enters from.
+    pub fn capture<T>(raindrops: &[Raindrop], term_cols: u16, term_rows: u16, 
+        color_algorithm: &T, render_config: &RenderConfig, phase: f32) -> Self
+    where T: ColorAlgorithm
+    {
+        Self::capture_from(raindrops.iter().map(std::slice::from_ref), 
+            term_cols, term_rows, color_algorithm, render_config, phase)
+    }
+
+    /// Returns a new `Frame` holding the current state of `lanes`, each of which may hold 
+    /// any number of `Raindrop`s
+    /// 
+    /// Where `Raindrop`s in the same lane overlap, the one that has travelled the least 
+    /// (the one with the lowest [row_index](crate::raindrop::Raindrop::row_index)) is drawn on top.
+    /// 
+    /// This is otherwise the same as [capture](crate::animation::Frame::capture).
+    pub fn capture_lanes<T>(lanes: &[Vec<Raindrop>], term_cols: u16, term_rows: u16, 
+        color_algorithm: &T, render_config: &RenderConfig, phase: f32) -> Self
+    where T: ColorAlgorithm
+    {
+        Self::capture_from(lanes.iter().map(Vec::as_slice), 
+            term_cols, term_rows, color_algorithm, render_config, phase)
+    }
+
+    /// Returns a new `Frame` holding the current state of `lanes`, capturing each lane 
+    /// on its own thread
+    /// 
+    /// The lanes are captured in parallel into a buffer of cells, which is then copied into
+    /// the `Frame` on the calling thread. This is otherwise the same as 
+    /// [capture_lanes](crate::animation::Frame::capture_lanes), and captures the same `Frame`.
+    /// 
+    /// This is only available with the `parallel` feature.
+    #[cfg(feature = "parallel")]
+    pub fn capture_lanes_parallel<T>(lanes: &[Vec<Raindrop>], term_cols: u16, term_rows: u16, 
+        color_algorithm: &T, render_config: &RenderConfig, phase: f32) -> Self
+    where T: ColorAlgorithm
+    {
+        let lane_cells: Vec<Vec<(usize, Option<StyledContent<char>>)>> = lanes.par_iter()
+            .enumerate()
+            .map(|(lane_index, lane)| {
+                let mut cells = Vec::new();
+                capture_lane(lane, lane_index, term_cols, term_rows, color_algorithm, render_config, 
+                    phase, |cell_index, cell| cells.push((cell_index, cell)));
+                cells
+            })
+            .collect();
+
+        let mut frame = Self::empty(term_cols, term_rows);
+        for (cell_index, cell) in lane_cells.into_iter().flatten() {
+            frame.cells[cell_index] = cell;
+        }
+        frame
+    }
+
+    // shared implementation of capture and capture_lanes, taking the raindrops in each lane
+    fn capture_from<'r, 'a: 'r, L, T>(lanes: L, term_cols: u16, term_rows: u16, 
+        color_algorithm: &T, render_config: &RenderConfig, phase: f32) -> Self
+    where L: Iterator<Item = &'r [Raindrop<'a>]>, T: ColorAlgorithm
+    {
+        let mut frame = Self::empty(term_cols, term_rows);
+        for (lane_index, lane) in lanes.enumerate() {
+            capture_lane(lane, lane_index, term_cols, term_rows, color_algorithm, render_config, 
+                phase, |cell_index, cell| frame.cells[cell_index] = cell);
+        }
+        frame
+    }
+
+    // returns a frame of the given size with every cell empty
+    fn empty(term_cols: u16, term_rows: u16) -> Self
+    {
+        Self {
+            term_cols,
+            term_rows,
+            cells: vec![None; usize::from(term_cols) * usize::from(term_rows)]
+        }
+    }
+
+    /// Returns the size of this `Frame` as a number of columns and rows
+    pub fn size(&self) -> (u16, u16)
+    {
+        (self.term_cols, self.term_rows)
+    }
+
+    /// Returns the styled character at the given column and row, or `None` if that cell is empty
+    /// or outside of this `Frame`
+    pub fn get(&self, column_index: u16, row_index: u16) -> Option<StyledContent<char>>
+    {
+        if column_index >= self.term_cols || row_index >= self.term_rows {
+            return None;
+        }
+        self.cells[self.cell_index(column_index, row_index)]
+    }
+
+    /// Returns this `Frame` as a `String` of characters and ANSI escape codes
+    /// 
+    /// Printing the returned `String` to a terminal draws the frame over the whole screen,
+    /// starting at the top left corner.
+    pub fn render(&self) -> String
+    {
+        let mut output = String::new();
+        self.render_into(&mut output);
+        output
+    }
+
+    /// Renders this `Frame` into `output`, as [render](crate::animation::Frame::render) does
+    /// 
+    /// `output` is cleared first, but keeps its capacity, so a `String` reused for every 
+    /// frame only allocates while it grows to fit the largest frame.
+    pub fn render_into(&self, output: &mut String)
+    {
+        output.clear();
+        self.write_full(output).expect("writing to a String should never fail");
+    }
+
+    /// Returns this `Frame` as lines of characters and ANSI escape codes, one line per row
+    /// 
+    /// Unlike [render](crate::animation::Frame::render), no cursor movement is included, so 
+    /// the frame is drawn wherever the cursor happens to be when it's printed. This suits 
+    /// printing a frame inline, such as in a shell prompt. Each line, including the last, 
+    /// ends with a newline.
+    pub fn render_lines(&self) -> String
+    {
+        let mut output = String::new();
+        self.write_lines(&mut output).expect("writing to a String should never fail");
+        output
+    }
+
+    /// Returns a `String` of characters and ANSI escape codes that turns `previous` into this `Frame`
+    /// 
+    /// Only cells that differ between the two frames are written, each preceded by a cursor move
+    /// where needed. When the rain is sparse, this is far smaller than the output of 
+    /// [render](crate::animation::Frame::render).
+    /// 
+    /// If `previous` has different dimensions than this `Frame`, the whole frame is rendered instead.
+    pub fn render_diff(&self, previous: &Frame) -> String
+    {
+        let mut output = String::new();
+        self.render_diff_into(previous, &mut output);
+        output
+    }
+
+    /// Renders the changes from `previous` to this `Frame` into `output`, as 
+    /// [render_diff](crate::animation::Frame::render_diff) does
+    /// 
+    /// Like [render_into](crate::animation::Frame::render_into), `output` is cleared first 
+    /// but keeps its capacity.
+    pub fn render_diff_into(&self, previous: &Frame, output: &mut String)
+    {
+        if self.term_cols != previous.term_cols || self.term_rows != previous.term_rows {
+            return self.render_into(output);
+        }
+
+        output.clear();
+        self.write_diff(output, previous).expect("writing to a String should never fail");
+    }
+
+    /// Returns a copy of this `Frame` with every colored cell dimmed towards black
+    /// 
+    /// `brightness` is the proportion of each color channel kept, from `0.0` (black) to `1.0` 
+    /// (unchanged); values outside of that range are clamped to it. The dimmed colors are mapped 
+    /// onto `color_depth`, so they can be displayed by the same terminal as the original colors.
+    /// 
+    /// Cells without a color are left as they are, since there's no way to dim them, 
+    /// except at a brightness of `0.0`, where every cell is left empty.
+    pub fn dimmed(&self, brightness: f32, color_depth: ColorDepth) -> Frame
+    {
+        let brightness = brightness.clamp(0.0, 1.0);
+        let dim_cell = |cell: Option<StyledContent<char>>| {
+            if brightness == 0.0 {
+                return None;
+            }
+            let cell = cell?;
+            if brightness == 1.0 {
+                return Some(cell);
+            }
+            let dim_channel = |channel: u8| (f32::from(channel) * brightness).round() as u8;
+            Some(map_cell_color(cell, color_depth, 
+                |rgb| coolor::Rgb::new(dim_channel(rgb.r), dim_channel(rgb.g), dim_channel(rgb.b))))
+        };
+
+        Frame {
+            term_cols: self.term_cols,
+            term_rows: self.term_rows,
+            cells: self.cells.iter().copied().map(dim_cell).collect()
+        }
+    }
+
+    // lightens the colored cells around a ripple towards white, the most at its center; 
+    // the ripple spreads out and fades as it ages
+    fn draw_ripple(&mut self, ripple: &Ripple, color_depth: ColorDepth)
+    {
+        let progress = ripple.age as f32 / RIPPLE_FRAMES as f32;
+        let radius = 1.0 + (RIPPLE_RADIUS - 1.0) * progress;
+        let fade = 1.0 - progress;
+
+        //cells are about twice as tall as they are wide, so rows are counted double 
+        //to keep the ripple round
+        let column_reach = radius as i32;
+        let row_reach = (radius / 2.0) as i32;
+        for row_offset in -row_reach..=row_reach {
+            for column_offset in -column_reach..=column_reach {
+                let column_index = i32::from(ripple.column) + column_offset;
+                let row_index = i32::from(ripple.row) + row_offset;
+                if !(0..i32::from(self.term_cols)).contains(&column_index) 
+                    || !(0..i32::from(self.term_rows)).contains(&row_index) 
+                {
+                    continue;
+                }
+
+                let distance = (column_offset.pow(2) as f32 + (2 * row_offset).pow(2) as f32).sqrt();
+                if distance > radius {
+                    continue;
+                }
+                let strength = fade * (1.0 - distance / (radius + 1.0));
+                let lighten_channel = |channel: u8| {
+                    (f32::from(channel) + (255.0 - f32::from(channel)) * strength).round() as u8
+                };
+
+                let cell_index = self.cell_index(column_index as u16, row_index as u16);
+                self.cells[cell_index] = self.cells[cell_index].map(|cell| map_cell_color(cell, color_depth, 
+                    |rgb| coolor::Rgb::new(lighten_channel(rgb.r), lighten_channel(rgb.g), lighten_channel(rgb.b))));
+            }
+        }
+    }
+
+    // draws message over the middle row, centered (or cut off at the right edge if it's too wide),
+    // in the same style as the leaders; spaces in message are drawn as empty cells
+    fn draw_message(&mut self, message: &str, render_config: &RenderConfig)
+    {
+        let cells: Vec<_> = message.chars()
+            .map(|c| if c == ' ' {None} else {Some(render_config.style_leader(c))})
+            .collect();
+        self.draw_centered(cells.into_iter());
+    }
+
+    // draws cells over the middle row, centered (or cut off at the right edge if there are 
+    // too many to fit), in front of whatever was there before
+    pub(crate) fn draw_centered<I>(&mut self, cells: I)
+    where I: ExactSizeIterator<Item = Option<StyledContent<char>>>
+    {
+        if self.term_rows == 0 {
+            return;
+        }
+        let drawn_length = cells.len().min(usize::from(self.term_cols));
+        let first_column = (usize::from(self.term_cols) - drawn_length) / 2;
+        let row_start = self.cell_index(0, self.term_rows / 2);
+
+        for (column_index, cell) in (first_column..).zip(cells.take(drawn_length)) {
+            self.cells[row_start + column_index] = cell;
+        }
+    }
+
+    // returns the index within cells for the given column and row
+    fn cell_index(&self, column_index: u16, row_index: u16) -> usize
+    {
+        usize::from(row_index) * usize::from(self.term_cols) + usize::from(column_index)
+    }
+
+    // writes a single cell, using a space for empty cells
+    fn write_cell(output: &mut String, cell: Option<StyledContent<char>>) -> fmt::Result
+    {
+        match cell {
+            None => Print(" ").write_ansi(output),
+            Some(styled_char) => PrintStyledContent(styled_char).write_ansi(output)
+        }
+    }
+
+    // does the work of render, returning a fmt::Result to allow use of the ? operator
+    fn write_full(&self, output: &mut String) -> fmt::Result
+    {
+        //reset cursor position
+        cursor::MoveTo(0,0).write_ansi(output)?;
+
+        //iterate through all rows
+        for row_index in 0..self.term_rows {
+
+            //strangely, these commands seem to be 1 based, unlike MoveTo
+            cursor::MoveToRow(row_index + 1).write_ansi(output)?;
+            cursor::MoveToColumn(1).write_ansi(output)?;
+
+            //print every cell in the row; empty cells are printed as spaces
+            for column_index in 0..self.term_cols {
+                Self::write_cell(output, self.cells[self.cell_index(column_index, row_index)])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // does the work of render_lines, returning a fmt::Result to allow use of the ? operator
+    fn write_lines(&self, output: &mut String) -> fmt::Result
+    {
+        for row_index in 0..self.term_rows {
+            for column_index in 0..self.term_cols {
+                Self::write_cell(output, self.cells[self.cell_index(column_index, row_index)])?;
+            }
+            output.push('\n');
+        }
+
+        Ok(())
+    }
+
+    // does the work of render_diff, returning a fmt::Result to allow use of the ? operator
+    fn write_diff(&self, output: &mut String, previous: &Frame) -> fmt::Result
+    {
+        // the position the cursor will be at after the last write, if any
+        let mut cursor_position: Option<(u16, u16)> = None;
+
+        for row_index in 0..self.term_rows {
+            for column_index in 0..self.term_cols {
+                let cell_index = self.cell_index(column_index, row_index);
+                let cell = self.cells[cell_index];
+
+                if cell == previous.cells[cell_index] {
+                    continue;
+                }
+
+                //printing a char moves the cursor one column to the right, so a move
+                //is only needed if this cell doesn't directly follow the last one written
+                if cursor_position != Some((column_index, row_index)) {
+                    cursor::MoveTo(column_index, row_index).write_ansi(output)?;
+                }
+                Self::write_cell(output, cell)?;
+                cursor_position = Some((column_index + 1, row_index));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns `cell` with its color changed by `map`, mapped onto `color_depth`
+/// 
+/// Cells without a color are returned as they are, since there's no color to change.
+fn map_cell_color<F>(cell: StyledContent<char>, color_depth: ColorDepth, map: F) -> StyledContent<char>
+where F: FnOnce(coolor::Rgb) -> coolor::Rgb
+{
+    let style = cell.style();
+    match style.foreground_color.and_then(terminal_color_to_rgb) {
+        None => cell,
+        Some(rgb) => {
+            let mapped_color = color_depth.downsample(coolor::Color::Rgb(map(rgb)));
+            let mapped_style = ContentStyle{foreground_color: Some(mapped_color.into()), ..*style};
+            StyledContent::new(mapped_style, *cell.content())
+        }
+    }
+}
+
+/// Returns a single frame of animation as a `String` of characters and ANSI escape codes
+/// 
+/// Printing the returned `String` to a terminal draws the frame over the whole screen,
+/// starting at the top left corner. Nothing is written anywhere by this function, so 
+/// it can be used without entering raw mode or the alternate screen.
+/// 
+/// This is shorthand for capturing a [Frame] at a phase of 0.0 and rendering it; 
+/// see [Frame::capture] for a description of the arguments.
+pub fn render_frame<T>(raindrops: &[Raindrop], term_cols: u16, term_rows: u16, 
+    color_algorithm: &T, render_config: &RenderConfig) -> String
+where T: ColorAlgorithm
+{
+    Frame::capture(raindrops, term_cols, term_rows, color_algorithm, render_config, 0.0).render()
+}
+
+/// Returns a single frame of animation as lines of characters and ANSI escape codes, 
+/// one line per row
+/// 
+/// Unlike [render_frame], no cursor movement is included, only styled characters and newlines, 
+/// so the returned `String` can be printed with `println!` or written to a log, and drawn 
+/// as is by printing it (or `cat`ing the log) to a terminal. Every styled character resets its 
+/// style after itself, so no style carries over from one line to the next.
+/// 
+/// This is shorthand for capturing a [Frame] at a phase of 0.0 and calling 
+/// [render_lines](Frame::render_lines); see [Frame::capture] for a description of the arguments.
+pub fn render_frame_ansi<T>(raindrops: &[Raindrop], term_cols: u16, term_rows: u16, 
+    color_algorithm: &T, render_config: &RenderConfig) -> String
 where T: ColorAlgorithm
 {
-    let mut raindrop_vec: Vec<Raindrop<T>> = Vec::with_capacity(terminal_width.into());
+    Frame::capture(raindrops, term_cols, term_rows, color_algorithm, render_config, 0.0).render_lines()
+}
+
+/// The state of an animation, which can be stepped through and rendered one frame at a time
+/// 
+/// This holds everything needed to draw the animation (the raindrops, the terminal dimensions,
+/// and the color algorithm) without doing any terminal I/O, so an animation can be driven 
+/// manually; for example, in tests. [anim_loop] is built on top of this.
+/// 
+/// The current frame is captured when the `AnimationState` is created and on every call to 
+/// [step](crate::animation::AnimationState::step), so rendering it doesn't change anything.
+/// 
+/// # Examples
+/// ```
+/// use mrs_matrix::animation::AnimationState;
+/// use mrs_matrix::raindrop::RaindropConfig;
+/// use mrs_matrix::raindrop::charsets::{Charset, PrintableAscii};
+/// use mrs_matrix::raindrop::color_algorithms::ColorScheme;
+/// 
+/// let charset = PrintableAscii().get_charset();
+/// let mut state = AnimationState::new(&charset, ColorScheme::Green, 0.75, 
+///     RaindropConfig::default(), Some(1), 80, 24);
+/// 
+/// for _ in 0..10 {
+///     state.step();
+/// }
+/// 
+/// //print the tenth frame after the first
+/// print!("{}", state.render());
+/// ```
+pub struct AnimationState<'a, T: ColorAlgorithm> {
+    // the raindrops in each lane, oldest first
+    lanes: Vec<Vec<Raindrop<'a>>>,
+    term_cols: u16,
+    term_rows: u16,
+    color_algorithm: T,
+    render_config: RenderConfig,
+
+    // the number of times the animation has been stepped, passed to the color algorithm as its phase
+    frames_stepped: u64,
+
+    // used to create raindrops for any lanes added by a resize;
+    // this is borrowed rather than regenerated, so every lane shares the one charset
+    charset: &'a Vec<char>,
+    // the charsets each raindrop picks one of whenever it respawns, if set_charsets gave any
+    charsets: &'a [CharSampler],
+    advance_chance: f64,
+    raindrop_config: RaindropConfig,
+    seed: Option<u64>,
+
+    // text drawn over the middle of every frame, in front of the rain
+    message: Option<String>,
+
+    // text settling into place over the middle of every frame, in front of the rain and message
+    decode_effect: Option<DecodeEffect>,
+
+    // ripples lightening the rain around where they were started, oldest first
+    ripples: Vec<Ripple>,
+
+    // raindrops that finished falling in a shared lane, kept to be reused by the next one started
+    spare_raindrops: Vec<Raindrop<'a>>,
+
+    // the current frame, captured from raindrops
+    frame: Frame
+}
 
-    for _ in 0..terminal_width {
-        let new_raindrop = Raindrop::new(
-            charset, color_algorithm, advance_chance, terminal_height);
-        raindrop_vec.push(new_raindrop);
+impl<'a, T: ColorAlgorithm> AnimationState<'a, T> {
+
+    /// Returns a new `AnimationState` with one `Raindrop` for each lane of the terminal
+    /// 
+    /// As the animation runs, up to `raindrop_config.drops_per_lane` raindrops may share a lane.
+    /// 
+    /// The default [RenderConfig] is used; to change it, use 
+    /// [set_render_config](crate::animation::AnimationState::set_render_config).
+    /// 
+    /// `term_cols` and `term_rows` should be the width and height of the terminal.
+    /// 
+    /// See [anim_loop] for a description of the remaining arguments.
+    /// 
+    /// # Panics
+    /// 
+    /// This function panics if `charset` is empty (i.e. has a length of zero).
+    /// 
+    /// This function panics if `advance_chance` is outside the range `[0.0, 1.0]`
+    /// 
+    /// This function panics if `raindrop_config.follower_min_length` is zero.
+    pub fn new(charset: &'a Vec<char>, color_algorithm: T, advance_chance: f64, 
+        raindrop_config: RaindropConfig, seed: Option<u64>, term_cols: u16, term_rows: u16) -> Self
+    {
+        assert!(!charset.is_empty(), "cannot create AnimationState with empty character set");
+
+        let lanes: Vec<Vec<Raindrop>> = 
+            create_raindrops(charset, advance_chance, raindrop_config, seed, term_cols, term_rows)
+            .into_iter()
+            .map(|raindrop| vec![raindrop])
+            .collect();
+        let render_config = RenderConfig::default();
+        let frame = Frame::capture_lanes(&lanes, term_cols, term_rows, 
+            &color_algorithm, &render_config, 0.0);
+
+        Self {
+            lanes,
+            term_cols,
+            term_rows,
+            color_algorithm,
+            render_config,
+            frames_stepped: 0,
+            charset,
+            charsets: &[],
+            advance_chance,
+            raindrop_config,
+            seed,
+            message: None,
+            decode_effect: None,
+            ripples: Vec::new(),
+            spare_raindrops: Vec::new(),
+            frame
+        }
+    }
+
+    /// Advances the animation by one frame
+    /// 
+    /// If a lane has room for more raindrops, a new one is started once the tail of 
+    /// the newest raindrop in the lane has fully entered the terminal.
+    pub fn step(&mut self)
+    {
+        let (_, lane_length) = 
+            self.raindrop_config.direction.lane_dimensions(self.term_cols, self.term_rows);
+        let drops_per_lane = usize::from(self.raindrop_config.drops_per_lane.max(1));
+
+        for lane in self.lanes.iter_mut() {
+            //when sharing a lane, raindrops that have finished are removed rather than 
+            //starting over, leaving the newest one in place so the lane is never empty;
+            //they're kept aside to be reused, rather than dropped
+            if lane.len() > 1 {
+                let newest = lane.pop().expect("lane has more than one raindrop");
+                let mut raindrop_index = 0;
+                while raindrop_index < lane.len() {
+                    let raindrop = &lane[raindrop_index];
+                    if raindrop.row_index() < 0 || raindrop.is_visible(lane_length) {
+                        raindrop_index += 1;
+                    } else {
+                        self.spare_raindrops.push(lane.remove(raindrop_index));
+                    }
+                }
+                lane.push(newest);
+            }
+
+            for raindrop in lane.iter_mut() {
+                raindrop.advance_animation(lane_length);
+            }
+
+            if lane.len() < drops_per_lane {
+                let newest = lane.last_mut().expect("lanes always hold at least one raindrop");
+                if newest.tail_row_index() > 0 {
+                    let new_raindrop = match self.spare_raindrops.pop() {
+                        Some(mut recycled) => {
+                            newest.spawn_sibling_into(&mut recycled, lane_length);
+                            recycled
+                        },
+                        None => newest.spawn_sibling(lane_length)
+                    };
+                    lane.push(new_raindrop);
+                }
+            }
+        }
+        if let Some(decode_effect) = &mut self.decode_effect {
+            decode_effect.step(self.charset);
+        }
+        self.ripples.retain_mut(|ripple| {
+            ripple.age += 1;
+            ripple.age < RIPPLE_FRAMES
+        });
+
+        self.frames_stepped += 1;
+        self.capture();
     }
 
-    raindrop_vec
+    /// Returns the current frame as a `String` of characters and ANSI escape codes
+    /// 
+    /// See [Frame::render] for details.
+    pub fn render(&self) -> String
+    {
+        self.frame.render()
+    }
+
+    /// Returns an iterator over the rendered frames of the animation, starting with the current one
+    /// 
+    /// Each call to `next` renders the current frame (see [render](crate::animation::AnimationState::render)),
+    /// then steps the animation. The iterator never ends on its own, so it should be limited with an 
+    /// adapter such as [take](Iterator::take).
+    /// 
+    /// # Examples
+    /// ```
+    /// use mrs_matrix::animation::AnimationState;
+    /// use mrs_matrix::raindrop::RaindropConfig;
+    /// use mrs_matrix::raindrop::charsets::{Charset, PrintableAscii};
+    /// use mrs_matrix::raindrop::color_algorithms::ColorScheme;
+    /// 
+    /// let charset = PrintableAscii().get_charset();
+    /// let mut state = AnimationState::new(&charset, ColorScheme::Green, 0.75, 
+    ///     RaindropConfig::default(), Some(1), 80, 24);
+    /// 
+    /// let frames: Vec<String> = state.frames().take(100).collect();
+    /// assert_eq!(frames.len(), 100);
+    /// ```
+    pub fn frames(&mut self) -> Frames<'_, 'a, T>
+    {
+        Frames{state: self}
+    }
+
+    /// Returns a reference to the current [Frame]
+    /// 
+    /// This can be compared against a previously drawn `Frame` with [Frame::render_diff].
+    pub fn frame(&self) -> &Frame
+    {
+        &self.frame
+    }
+
+    /// Fits the animation to a resized terminal
+    /// 
+    /// Existing raindrops are kept where possible, in the same way as [resize_raindrops].
+    /// Raindrops for any added lanes borrow the same charset as the rest, so resizing never 
+    /// rebuilds it. The current frame is captured again at the new size.
+    pub fn resize(&mut self, term_cols: u16, term_rows: u16)
+    {
+        self.term_cols = term_cols;
+        self.term_rows = term_rows;
+
+        let (lane_count, lane_length) = 
+            self.raindrop_config.direction.lane_dimensions(term_cols, term_rows);
+
+        //drop any lanes that no longer fit, then add a single drop for each new lane
+        let previous_lane_count = self.lanes.len() as u16;
+        self.lanes.truncate(lane_count.into());
+        self.lanes.extend((previous_lane_count..lane_count)
+            .map(|lane_index| {
+                let mut raindrop = create_raindrop(self.charset, self.advance_chance, 
+                    self.raindrop_config, self.seed, lane_index, lane_length);
+                if !self.charsets.is_empty() {
+                    raindrop.set_charsets(self.charsets);
+                }
+                vec![raindrop]
+            }));
+
+        for raindrop in self.lanes.iter_mut().flatten() {
+            restart_if_past_end(raindrop, lane_length);
+        }
+
+        self.capture();
+    }
+
+    /// Gives each raindrop several charsets to pick from, for a mix of scripts on screen
+    /// 
+    /// Each raindrop picks one of `charsets` at random straight away, and another whenever it 
+    /// respawns, drawing every char of a single fall from the same charset; see 
+    /// [Raindrop::set_charsets]. The charset the `AnimationState` was created with is still 
+    /// used to scramble any [DecodeEffect]. The current frame is captured again with the new chars.
+    /// 
+    ///# Panics
+    /// 
+    /// This function panics if `charsets` is empty, or any of its charsets are
+    pub fn set_charsets(&mut self, charsets: &'a [CharSampler])
+    {
+        self.charsets = charsets;
+        for raindrop in self.lanes.iter_mut().flatten() {
+            raindrop.set_charsets(charsets);
+        }
+        self.capture();
+    }
+
+    /// Replaces the color algorithm
+    /// 
+    /// The raindrops are left as they are, but the current frame is captured again 
+    /// in the new colors.
+    pub fn set_color_algorithm(&mut self, color_algorithm: T)
+    {
+        self.color_algorithm = color_algorithm;
+        self.capture();
+    }
+
+    /// Replaces the [RenderConfig]
+    /// 
+    /// The raindrops are left as they are, but the current frame is captured again 
+    /// with the new styling.
+    pub fn set_render_config(&mut self, render_config: RenderConfig)
+    {
+        self.render_config = render_config;
+        self.capture();
+    }
+
+    /// Replaces the message drawn over the animation, or removes it if `message` is `None`
+    /// 
+    /// The message is drawn in the middle row of every frame, centered, in the same style as 
+    /// the leaders. The rain falls behind it, so raindrops never draw over the message's cells; 
+    /// spaces in the message are drawn as empty cells. A message wider than the terminal is 
+    /// cut off at its right edge. The current frame is captured again with the new message.
+    pub fn set_message(&mut self, message: Option<String>)
+    {
+        self.message = message;
+        self.capture();
+    }
+
+    /// Replaces the [DecodeEffect] drawn over the animation, or removes it if `decode_effect` 
+    /// is `None`
+    /// 
+    /// The effect is stepped along with the animation, its unlocked characters flickering 
+    /// through the animation's charset. It's drawn in the middle row of every frame, centered 
+    /// (or cut off at its right edge if it's wider than the terminal), in front of both the 
+    /// rain and any message. The current frame is captured again with the new effect.
+    pub fn set_decode_effect(&mut self, decode_effect: Option<DecodeEffect>)
+    {
+        self.decode_effect = decode_effect;
+        self.capture();
+    }
+
+    /// Starts a ripple at the given column and row, such as where the screen was clicked
+    /// 
+    /// The ripple lightens the colors of the chars around it, spreading out and fading away 
+    /// over the next 12 steps. Any number of ripples can be drawn at once. Empty cells and 
+    /// chars without a color are left as they are, as is any message or [DecodeEffect]. 
+    /// The current frame is captured again with the new ripple.
+    pub fn add_ripple(&mut self, column_index: u16, row_index: u16)
+    {
+        self.ripples.push(Ripple{column: column_index, row: row_index, age: 0});
+        self.capture();
+    }
+
+    // captures the current state of the raindrops as the current frame
+    fn capture(&mut self)
+    {
+        //the phase loses precision after many days of animation, but keeps counting up
+        #[cfg(not(feature = "parallel"))]
+        let frame = Frame::capture_lanes(&self.lanes, self.term_cols, self.term_rows, 
+            &self.color_algorithm, &self.render_config, self.frames_stepped as f32);
+        #[cfg(feature = "parallel")]
+        let frame = Frame::capture_lanes_parallel(&self.lanes, self.term_cols, self.term_rows, 
+            &self.color_algorithm, &self.render_config, self.frames_stepped as f32);
+        self.frame = frame;
+
+        for ripple in self.ripples.iter() {
+            self.frame.draw_ripple(ripple, self.render_config.color_depth);
+        }
+        if let Some(message) = &self.message {
+            self.frame.draw_message(message, &self.render_config);
+        }
+        if let Some(decode_effect) = &self.decode_effect {
+            self.frame.draw_centered(decode_effect.cells(&self.render_config));
+        }
+    }
+}
+
+/// A ripple started by a click, which lightens the rain around it for [RIPPLE_FRAMES] steps
+#[derive(Clone, Copy, Debug)]
+struct Ripple {
+    column: u16,
+    row: u16,
+
+    // the number of steps since the ripple started
+    age: u32
+}
+
+/// An endless iterator over the rendered frames of an [AnimationState]
+/// 
+/// This is created by [AnimationState::frames]; see its documentation for more.
+pub struct Frames<'s, 'a, T: ColorAlgorithm> {
+    state: &'s mut AnimationState<'a, T>
+}
+
+impl<T: ColorAlgorithm> Iterator for Frames<'_, '_, T> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String>
+    {
+        let rendered = self.state.render();
+        self.state.step();
+        Some(rendered)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>)
+    {
+        (usize::MAX, None)
+    }
+}
+
+impl<T: ColorAlgorithm> std::iter::FusedIterator for Frames<'_, '_, T> {}
+
+/// The color algorithm used by the animation loop, which may be switched for a preset 
+/// [ColorScheme] while running
+/// 
+/// This is only public so that the [AnimationState] of a running loop can be passed to an 
+/// `on_frame` callback (see [LoopOptions]); it's unlikely to be useful otherwise.
+pub enum LoopColors<T: ColorAlgorithm> {
+    /// The color algorithm the loop was started with
+    Configured(T),
+
+    /// A preset color scheme switched to with the number keys
+    Scheme(ColorScheme)
+}
+
+/// A callback run once for every frame drawn by [anim_loop_with_writer], with the 
+/// [AnimationState] the frame was drawn from and the index of the frame
+pub type OnFrame<'f, T> = &'f mut dyn FnMut(&AnimationState<LoopColors<T>>, u64);
+
+impl<T: ColorAlgorithm> ColorAlgorithm for LoopColors<T> {
+    fn gen_color(&self, follower_proportion: f32, hue_seed: f32, context: &ColorContext) 
+    -> coolor::Color {
+        match self {
+            LoopColors::Configured(color_algorithm) => 
+                color_algorithm.gen_color(follower_proportion, hue_seed, context),
+            LoopColors::Scheme(scheme) => scheme.gen_color(follower_proportion, hue_seed, context)
+        }
+    }
+}
+
+/// The optional settings of [anim_loop_with_writer] and [anim_loop_with_backend]
+/// 
+/// `interactive` should be `true` if the loop draws to a terminal the user is looking at.
+/// In that case, raw mode and the alternate screen are entered, the cursor is hidden, 
+/// and the loop returns when `q`, `Q`, `Esc`, or `Ctrl-C` is pressed. Space pauses and resumes
+/// the animation, `+` and `-` adjust the target framerate, `1` to `6` switch color schemes, 
+/// `[` and `]` adjust the brightness, `f` toggles the framerate overlay, clicking starts a ripple
+/// (see [AnimationState::add_ripple]), and other keys and mouse events are ignored. 
+/// Terminal resizes are also handled.
+/// The terminal is restored by a [TerminalGuard] on return, including if the loop panics.
+/// 
+/// In either case, the loop also returns if the process receives SIGINT or SIGTERM (on Unix),
+/// going through the same cleanup as any other return.
+/// 
+/// If `interactive` is `false` (the default), all of that terminal setup is skipped and no input 
+/// is read, so the loop runs until `duration` has passed, `frames` frames have been drawn, or the 
+/// process is ended. This is appropriate for writers that aren't a tty.
+/// 
+/// `render_config` is a [RenderConfig], which controls how leader characters are styled.
+/// Styling that the environment says isn't supported is turned off: color if `NO_COLOR` is set,
+/// and both color and bold if `TERM` is `dumb`.
+/// 
+/// `seed`, `duration`, and `frames` are `None` by default, for a fresh animation every time 
+/// that runs until it's stopped; see [anim_loop] for their other values.
+/// 
+/// `size` should be `None` to draw frames at the size of the terminal, following it as it's 
+/// resized, or `Some((columns, rows))` to always draw them at that size, ignoring resizes.
+/// If `size` is `None` and the size of the terminal can't be determined (as when there is 
+/// no terminal at all, such as in CI), frames are drawn at the size given by the `COLUMNS` 
+/// and `LINES` environment variables, or [FALLBACK_TERMINAL_SIZE] for either one that isn't set.
+/// 
+/// `message` is text drawn over the middle of the animation, in front of the rain, 
+/// or `None` for no message; see [AnimationState::set_message].
+/// 
+/// `decode_effect` is a [DecodeEffect] drawn over the middle of the animation, in front of the
+/// rain and message, or `None` for no effect; see [AnimationState::set_decode_effect].
+/// 
+/// `fall_speed` is how many times the animation is stepped per frame drawn, on average; see 
+/// [FallAccumulator]. At `1.0` (the default) it's stepped once per frame; lower speeds let 
+/// rain fall slowly while still being drawn at a high framerate.
+/// 
+/// `start_paused` should be `true` to draw the first frame and then wait, paused, until Space 
+/// is pressed. The loop's `duration` only starts counting down once the animation is first 
+/// resumed, though every frame drawn while paused still counts towards its `frames`. As no input is read 
+/// when `interactive` is `false`, `start_paused` is ignored in that case.
+/// 
+/// `fade_out` should be `true` to fade the last frame drawn to black over half a second when 
+/// the loop returns, rather than leaving it on screen until the terminal is restored. 
+/// The fade is drawn at the target framerate, before the [TerminalGuard] restores the terminal.
+/// As the screen of a writer that isn't a terminal isn't cleared up afterwards, `fade_out` is 
+/// ignored when `interactive` is `false`.
+/// 
+/// `charsets` should be empty for every raindrop to draw from the loop's `charset`, or hold 
+/// [CharSampler]s for each raindrop to pick one of at random whenever it respawns, drawing 
+/// chars as often as the sampler says; see [AnimationState::set_charsets]. `charset` is then 
+/// only used to scramble the `decode_effect`.
+/// 
+/// `on_frame` should be `None`, or `Some(callback)` to call `callback` once for every frame 
+/// drawn, including those drawn while paused. It's passed the [AnimationState] the frame was 
+/// drawn from and the index of the frame, counting from zero, and is called after the frame is 
+/// written but before the animation is stepped. This suits overlays and instrumentation, such as 
+/// counting frames or triggering events at certain times.
+/// 
+/// Apart from `interactive`, which [anim_loop] turns on if `stdout` is a terminal, and the 
+/// `seed`, `duration`, and `frames` passed to it, the default `LoopOptions` draw the animation 
+/// just as [anim_loop] does.
+pub struct LoopOptions<'f, T: ColorAlgorithm> {
+    pub interactive: bool,
+    pub render_config: RenderConfig,
+    pub seed: Option<u64>,
+    pub duration: Option<Duration>,
+    pub frames: Option<u64>,
+    pub size: Option<(u16, u16)>,
+    pub message: Option<String>,
+    pub decode_effect: Option<DecodeEffect>,
+    pub fall_speed: f64,
+    pub start_paused: bool,
+    pub fade_out: bool,
+    pub charsets: Vec<CharSampler>,
+    pub on_frame: Option<OnFrame<'f, T>>
+}
+
+impl<T: ColorAlgorithm> Default for LoopOptions<'_, T> {
+    fn default() -> Self
+    {
+        Self {
+            interactive: false,
+            render_config: RenderConfig::default(),
+            seed: None,
+            duration: None,
+            frames: None,
+            size: None,
+            message: None,
+            decode_effect: None,
+            fall_speed: 1.0,
+            start_paused: false,
+            fade_out: false,
+            charsets: Vec::new(),
+            on_frame: None
+        }
+    }
+}
+
+/// A flag that is set when the process is asked to terminate by a signal
+/// 
+/// On Unix, this covers SIGINT and SIGTERM; the signal handlers are unregistered
+/// when the flag is dropped. On other platforms, the flag is never set.
+/// 
+/// Note that this is intentionally private because it's unlikely to be generally useful
+struct TerminationFlag {
+    flag: Arc<AtomicBool>,
+
+    #[cfg(unix)]
+    signal_ids: Vec<signal_hook::SigId>
+}
+
+impl TerminationFlag {
+
+    // registers signal handlers that set the returned flag
+    #[cfg(unix)]
+    fn register() -> std::io::Result<Self>
+    {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut signal_ids = Vec::with_capacity(2);
+        for signal in [SIGINT, SIGTERM] {
+            signal_ids.push(signal_hook::flag::register(signal, Arc::clone(&flag))?);
+        }
+
+        Ok(Self { flag, signal_ids })
+    }
+
+    // there are no signals to handle on this platform; return a flag that is never set
+    #[cfg(not(unix))]
+    fn register() -> std::io::Result<Self>
+    {
+        Ok(Self { flag: Arc::new(AtomicBool::new(false)) })
+    }
+
+    // returns true if a termination signal has been received
+    fn is_set(&self) -> bool
+    {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for TerminationFlag {
+    fn drop(&mut self)
+    {
+        for signal_id in self.signal_ids.drain(..) {
+            signal_hook::low_level::unregister(signal_id);
+        }
+    }
+}
+
+/// Prepares a terminal for animation, and restores it when dropped
+/// 
+/// Creating a `TerminalGuard` enters the alternate screen and hides the cursor, and 
+/// optionally enables raw mode. When the `TerminalGuard` is dropped, all of that is undone.
+/// Because this happens on drop, the terminal is restored whether the animation 
+/// returns normally, returns early with an error, or panics.
+/// 
+/// All drawing should be done through the `TerminalGuard` itself, which dereferences to the 
+/// wrapped [Backend]. If the backend implements [Write] (as [CrosstermBackend] does), 
+/// so does the `TerminalGuard`, passing writes along to it.
+/// 
+/// # Examples
+/// ```no_run
+/// use std::io::{stdout, Write};
+/// use mrs_matrix::MatrixError;
+/// use mrs_matrix::animation::TerminalGuard;
+/// use mrs_matrix::backend::CrosstermBackend;
+/// 
+/// pub fn main() -> Result<(), MatrixError>
+/// {
+///     let mut guard = TerminalGuard::new(CrosstermBackend::new(stdout()), true)?;
+///     guard.write_all(b"drawn on the alternate screen")?;
+///     guard.flush()?;
+///     // the terminal is restored when guard goes out of scope
+///     Ok(())
+/// }
+/// ```
+pub struct TerminalGuard<B: Backend> {
+    backend: B,
+
+    // whether raw mode was enabled by this guard and so should be disabled on restore
+    raw_mode: bool,
+
+    // whether mouse capture was enabled by this guard and so should be disabled on restore
+    mouse_capture: bool
+}
+
+impl<B: Backend> TerminalGuard<B> {
+
+    /// Returns a new `TerminalGuard` wrapping `backend`, after entering the alternate screen 
+    /// and hiding the cursor
+    /// 
+    /// If `raw_mode` is `true`, raw mode is also enabled, allowing keypresses to be processed
+    /// one by one. Raw mode requires an actual terminal, so it should be `false` if the program
+    /// isn't attached to one.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns [MatrixError::Terminal] if raw mode can't be enabled, or the terminal can't be 
+    /// set up through `backend`
+    pub fn new(mut backend: B, raw_mode: bool) -> Result<Self, MatrixError>
+    {
+        if raw_mode {
+            //enable raw mode to process keypress by keypress
+            backend.enable_raw_mode().map_err(MatrixError::Terminal)?;
+        }
+
+        //enter alternate screen, and hide the cursor
+        backend.enter_alternate_screen()
+            .and_then(|_| backend.hide_cursor())
+            .and_then(|_| backend.flush())
+            .map_err(MatrixError::Terminal)?;
+
+        Ok(Self { backend, raw_mode, mouse_capture: false })
+    }
+
+    /// Returns this `TerminalGuard` after enabling mouse capture, so that clicks and other
+    /// mouse events can be read like keypresses
+    /// 
+    /// Mouse capture is disabled again along with everything else when the `TerminalGuard` 
+    /// is dropped.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns [MatrixError::Terminal] if mouse capture can't be enabled through the backend
+    pub fn with_mouse_capture(mut self) -> Result<Self, MatrixError>
+    {
+        self.backend.enable_mouse_capture()
+            .and_then(|_| self.backend.flush())
+            .map_err(MatrixError::Terminal)?;
+        self.mouse_capture = true;
+        Ok(self)
+    }
+
+    // undoes everything done by new
+    // this is the one place where terminal state is restored
+    fn restore(&mut self) -> std::io::Result<()>
+    {
+        if self.raw_mode {
+            //disable raw mode
+            self.backend.disable_raw_mode()?;
+        }
+        if self.mouse_capture {
+            self.backend.disable_mouse_capture()?;
+        }
+
+        //be sure to leave the alternate screen and show the cursor again
+        self.backend.leave_alternate_screen()?;
+        self.backend.show_cursor()?;
+        self.backend.flush()
+    }
+}
+
+impl<B: Backend> Deref for TerminalGuard<B> {
+    type Target = B;
+
+    fn deref(&self) -> &B
+    {
+        &self.backend
+    }
+}
+
+impl<B: Backend> DerefMut for TerminalGuard<B> {
+    fn deref_mut(&mut self) -> &mut B
+    {
+        &mut self.backend
+    }
+}
+
+impl<B: Backend + Write> Write for TerminalGuard<B> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>
+    {
+        self.backend.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()>
+    {
+        Write::flush(&mut self.backend)
+    }
+}
+
+impl<B: Backend> Drop for TerminalGuard<B> {
+    fn drop(&mut self)
+    {
+        //there is nowhere to report an error from here (and we may already be panicking), 
+        //so a failure to restore the terminal is ignored
+        let _ = self.restore();
+    }
 }
 
 /// The main loop that renders the screen
 /// 
-/// Returns after receiving any keypress
+/// Returns when `q`, `Q`, `Esc`, or `Ctrl-C` is pressed. Pressing space pauses or resumes
+/// the animation, and `+` or `-` raises or lowers the target framerate by 5 (within 1 to 240).
+/// The number keys `1` to `6` switch to one of the preset [ColorScheme]s in place of 
+/// `color_algorithm`, `[` or `]` lowers or raises the brightness by 0.1 (within 0.0 to 1.0), 
+/// and `f` shows or hides the measured framerate in the top right corner; other keys are ignored.
+/// 
+/// This is a thin wrapper around [anim_loop_with_writer] that draws to `stdout` 
+/// as an interactive terminal, with the default [RenderConfig]. If `stdout` isn't a terminal 
+/// (for example, because it's piped into another program), it's drawn to non-interactively.
 /// 
 /// `charset` should be a `Vec<char>`. This will be the set of characters that will be
 /// displayed within the animation.
@@ -51,118 +1359,580 @@ where T: ColorAlgorithm
 /// [LightnessDescending](crate::raindrop::color_algorithms::LightnessDescending).
 /// 
 /// `advance_chance` should be the chance (from 0.0 to 1.0) that any one `Raindrop` will advance
-/// its movement on any given frame. This value must be within the range `[0.0, 1.0)`.
+/// its movement on any given frame. This value must be within the range `[0.0, 1.0]`; a value of 1.0
+/// moves every `Raindrop` in lockstep, while lower values stagger them.
 /// 
 /// `target_framerate` should be the number of frames per second to target.
 /// 
-/// # Panics
+/// `raindrop_config` should be a [RaindropConfig], which controls the length of raindrop followers.
+/// 
+/// `seed` should be `None` for a fresh animation every time, or `Some(seed)` for a reproducible one;
+/// with the same seed, terminal size, and framerate, the same frames will be drawn.
 /// 
-/// This function panics if `charset` is empty (i.e. has a length of zero).
+/// `duration` should be `None` to run until a quit key is pressed, or `Some(duration)` to also
+/// return once `duration` has passed since the loop started.
 /// 
-/// This function panics if `target_framerate` is zero.
+/// `frames` should be `None` to run until a quit key is pressed, or `Some(frames)` to also
+/// return after exactly `frames` frames have been drawn. If both `duration` and `frames` 
+/// are set, the loop returns as soon as either limit is reached.
 /// 
-/// This function panics if `advance_chance` is outside the range `[0.0, 1.0)`
+///# Errors
+/// 
+/// Returns [MatrixError::Config] without drawing anything if `charset` is empty (i.e. has a 
+/// length of zero), `target_framerate` is zero, `advance_chance` is outside the range `[0.0, 1.0]`, 
+/// or `raindrop_config.follower_min_length` is zero.
+/// 
+/// Returns [MatrixError::Terminal] if the terminal can't be set up or read from, and 
+/// [MatrixError::Io] if drawing a frame fails.
 /// 
 /// # Examples
-/// ```
+/// ```no_run
+/// use mrs_matrix::MatrixError;
 /// use mrs_matrix::animation::anim_loop;
+/// use mrs_matrix::raindrop::RaindropConfig;
 /// use mrs_matrix::raindrop::charsets::{Charset, PrintableAscii};
 /// use mrs_matrix::raindrop::color_algorithms::LightnessDescending;
 /// 
-/// pub fn main() -> crossterm::Result<()>
+/// pub fn main() -> Result<(), MatrixError>
 /// {
 ///     let charset = PrintableAscii().get_charset();
-///     let color_algorithm = LightnessDescending{
-///         hue: 118.0,
-///         saturation: 0.82
-///     };
+///     let color_algorithm = LightnessDescending::new(118.0, 0.82)
+///         .expect("hue and saturation are in range");
 ///     let advance_chance = 0.75;
 ///     let target_framerate = 25;
-///     anim_loop(charset, color_algorithm, advance_chance, target_framerate)
+///     let raindrop_config = RaindropConfig::default();
+///     let seed = None;
+///     let duration = None;
+///     let frames = None;
+///     anim_loop(charset, color_algorithm, advance_chance, target_framerate, raindrop_config, 
+///         seed, duration, frames)
 /// }
 /// ```
+#[allow(clippy::too_many_arguments)]
 pub fn anim_loop<T: ColorAlgorithm>(charset: Vec<char>, color_algorithm: T,
-     advance_chance:f64, target_framerate: usize) -> crossterm::Result<()>
+     advance_chance:f64, target_framerate: usize, raindrop_config: RaindropConfig, seed: Option<u64>,
+     duration: Option<Duration>, frames: Option<u64>) 
+-> Result<(), MatrixError>
 {
-    assert!(charset.len() > 0, "cannot run anim_loop with empty character set");
-    assert!(target_framerate > 0, 
-        "cannot run anim_loop at target framerate of zero");
+    //hold the lock on stdout for the whole animation, rather than taking it for every write
+    let out = stdout().lock();
+    let interactive = out.is_terminal();
+    anim_loop_with_writer(out, charset, color_algorithm, advance_chance, target_framerate,
+        raindrop_config, LoopOptions{interactive, seed, duration, frames, ..LoopOptions::default()})
+}
 
-    let mut out = stdout();
+/// The main loop that renders the screen, using a color algorithm chosen at runtime
+/// 
+/// This is the same as [anim_loop], but takes `color_algorithm` as a trait object. 
+/// This saves callers from needing a separate call to [anim_loop] for each type 
+/// of [ColorAlgorithm] they might use, at the cost of dynamic dispatch when coloring characters.
+/// 
+/// See [anim_loop] for a description of the arguments.
+/// 
+///# Errors
+/// 
+/// Returns an error under the same conditions as [anim_loop].
+#[allow(clippy::too_many_arguments)]
+pub fn anim_loop_dyn(charset: Vec<char>, color_algorithm: Box<dyn ColorAlgorithm>,
+     advance_chance:f64, target_framerate: usize, raindrop_config: RaindropConfig, seed: Option<u64>,
+     duration: Option<Duration>, frames: Option<u64>) 
+-> Result<(), MatrixError>
+{
+    anim_loop(charset, color_algorithm, advance_chance, target_framerate, raindrop_config, 
+        seed, duration, frames)
+}
 
-    let (mut term_cols, mut term_rows) = terminal::size()?;
+/// The main loop that renders the screen, drawing to any [Write] implementor
+/// 
+/// `out` is where frames are written to. This can be `stdout`, a file, 
+/// a `Vec<u8>`, or anything else implementing [Write].
+/// 
+/// `options` holds the rest of the settings, such as whether `out` is a terminal the user is 
+/// looking at, and when the loop should return; see [LoopOptions].
+/// 
+/// See [anim_loop] for a description of the remaining arguments.
+/// 
+///# Errors
+/// 
+/// Returns an error under the same conditions as [anim_loop], or [MatrixError::Config] if any 
+/// of the `charsets` of `options` are empty or its `fall_speed` isn't a positive, finite number.
+pub fn anim_loop_with_writer<W, T>(out: W, charset: Vec<char>, color_algorithm: T,
+     advance_chance:f64, target_framerate: usize, raindrop_config: RaindropConfig, 
+     options: LoopOptions<T>) 
+-> Result<(), MatrixError>
+where W: Write, T: ColorAlgorithm
+{
+    anim_loop_with_backend(CrosstermBackend::new(out), charset, color_algorithm, advance_chance, 
+        target_framerate, raindrop_config, options)
+}
 
-    //enable raw mode to process keypress by keypress
-    terminal::enable_raw_mode()?;
+/// The main loop that renders the screen, drawing through any [Backend]
+/// 
+/// This is the same as [anim_loop_with_writer], which draws through a [CrosstermBackend], 
+/// but lets the terminal be swapped out; for example, for a [TestBackend](crate::backend::TestBackend) 
+/// that records what's drawn. When the `interactive` option is `true`, the terminal is set up 
+/// through `backend`, but input is still read from the terminal the process is attached to, 
+/// so `interactive` should be `false` for any backend that doesn't draw to that terminal.
+/// 
+/// See [anim_loop_with_writer] for a description of the arguments.
+/// 
+///# Errors
+/// 
+/// Returns an error under the same conditions as [anim_loop_with_writer].
+pub fn anim_loop_with_backend<B, T>(mut backend: B, charset: Vec<char>, color_algorithm: T,
+     advance_chance:f64, mut target_framerate: usize, raindrop_config: RaindropConfig, 
+     options: LoopOptions<T>) 
+-> Result<(), MatrixError>
+where B: Backend, T: ColorAlgorithm
+{
+    let LoopOptions{interactive, render_config, seed, duration, frames, size, message, decode_effect, 
+        fall_speed, start_paused, fade_out, charsets, mut on_frame} = options;
+    check_loop_config(&charset, &charsets, advance_chance, target_framerate, raindrop_config, 
+        fall_speed)?;
+
+    let (term_cols, term_rows) = size.unwrap_or_else(terminal_size);
+
+    //decides how many times to step the animation each frame, so rain can fall at any speed
+    let mut fall_accumulator = FallAccumulator::new(fall_speed);
+
+    //watch for termination signals so they can end the loop as cleanly as a keypress
+    let termination_flag = TerminationFlag::register()?;
+
+    //when interactive, prepare the terminal and route all output through a guard 
+    //that restores the terminal when it goes out of scope, even if the loop panics
+    let mut terminal_guard;
+    let out: &mut dyn Backend = if interactive {
+        terminal_guard = TerminalGuard::new(backend, true)?.with_mouse_capture()?;
+        &mut *terminal_guard
+    } else {
+        &mut backend
+    };
+
+    //schedules each frame at a fixed interval from the last, so frames stay locked to the clock
+    let mut frame_clock = FrameClock::new(Instant::now(), frame_duration(target_framerate));
+
+    let mut animation_state = AnimationState::new(&charset, LoopColors::Configured(color_algorithm), 
+        advance_chance, raindrop_config, seed, term_cols, term_rows);
+    let mut render_config = supported_render_config(render_config);
+    animation_state.set_render_config(render_config);
+    if !charsets.is_empty() {
+        animation_state.set_charsets(&charsets);
+    }
+    animation_state.set_message(message);
+    animation_state.set_decode_effect(decode_effect);
 
-    //enter alternate screen, and hide the cursor
-    out.queue(terminal::EnterAlternateScreen)?
-    .queue(cursor::Hide)?;
+    //the last frame drawn, used to only redraw cells that have changed
+    //this is None when the whole screen needs to be redrawn
+    let mut previous_frame: Option<Frame> = None;
 
-    //calculate target frame duration by dividing one second by the number of frames that should be in one second
-    let target_frame_duration = Duration::from_secs_f64(1.0/(target_framerate as f64));
+    //while paused, the animation is not stepped, freezing it in place
+    //without input, there would be no way to resume, so only interactive loops start paused
+    let mut paused = start_paused && interactive;
 
-    let mut raindrop_vector = 
-        create_raindrops(&charset, color_algorithm, advance_chance, 
-            term_cols, term_rows);
+    //when the animation was first unpaused, used to stop the loop once duration has passed
+    let mut loop_start_instant: Option<Instant> = None;
+
+    //the number of frames drawn so far, used to stop the loop once frames have been drawn
+    let mut frames_drawn: u64 = 0;
+
+    //the measured framerate is drawn over the rain while show_fps is set
+    let mut show_fps = false;
+    let mut frame_rate_meter = FrameRateMeter::new();
+    let mut overlay_cols = term_cols;
 
     let mut start_instant: Instant;
-    loop {
+    'animation: loop {
         start_instant = Instant::now();
 
-        //reset cursor position
-        out.queue(cursor::MoveTo(0,0))?;
+        //stop loop if a termination signal came in since the last frame
+        if termination_flag.is_set() {
+            break;
+        }
 
-        //iterate through all rows
-        for row_index in 0..term_rows {
+        //stop loop if it has been running for the requested duration, 
+        //counting from when it first started moving
+        if !paused {
+            loop_start_instant.get_or_insert(start_instant);
+        }
+        if duration.is_some_and(|duration| loop_start_instant
+            .is_some_and(|loop_start_instant| loop_start_instant.elapsed() >= duration)) {
+            break;
+        }
 
-            //strangely, these commands seem to be 1 based, unlike MoveTo
-            out.queue(cursor::MoveToRow(row_index + 1))?
-            .queue(cursor::MoveToColumn(1))?;
-
-            //iterate through all columns by iterating through raindrop_vector, printing styled chars where applicable
-            //note that spaces are printed for columns on this row without a printable char
-            for raindrop in raindrop_vector.iter_mut() {
-                match raindrop.get_styled_char_at_row(row_index) {
-                    None => out.queue(Print(" "))?,
-                    Some(styled_char) => out.queue(PrintStyledContent(styled_char))?
-                };
-            }
+        //stop loop if the requested number of frames have been drawn
+        if frames.is_some_and(|frames| frames_drawn >= frames) {
+            break;
         }
+        frames_drawn += 1;
+        frame_rate_meter.record(start_instant);
 
-        //flush buffer to 'draw'
+        //draw whatever has changed since the last frame, then flush buffer to 'draw'
+        out.draw_frame(animation_state.frame(), previous_frame.as_ref())?;
+        if show_fps {
+            //the overlay is drawn over the frame without changing it, and redrawn every frame
+            draw_fps_overlay(out, frame_rate_meter.fps(), overlay_cols)?;
+        }
         out.flush()?;
+        previous_frame = Some(animation_state.frame().clone());
+
+        //let the caller see the frame just drawn before anything changes
+        if let Some(on_frame) = on_frame.as_mut() {
+            on_frame(&animation_state, frames_drawn - 1);
+        }
+
+        //advance the animation by however many steps the fall speed calls for, unless paused
+        if !paused {
+            for _ in 0..fall_accumulator.steps_for_frame() {
+                animation_state.step();
+            }
+        }
 
-        //call advance_animation on all the raindrops
-        for raindrop in raindrop_vector.iter_mut() {
-            raindrop.advance_animation(term_rows);
+        //the instant the next frame is due; this is now if the frame took longer than planned
+        let next_frame_instant = frame_clock.schedule_next_frame(Instant::now());
+
+        if !interactive {
+            //with no input to wait on, simply sleep for the rest of the frame
+            std::thread::sleep(next_frame_instant.saturating_duration_since(Instant::now()));
+            continue;
         }
     
-        //wait for enough time to hit target_frame_duration, or no time if frame duration exceeds target
-        if event::poll(target_frame_duration.saturating_sub(Instant::now() - start_instant))? {
-            match event::read()? {
-                //upon recieving a resize event set new column amount
-                Event::Resize(new_cols, new_rows) => {
-                    term_cols = new_cols;
-                    term_rows = new_rows;
-
-                    raindrop_vector = 
-                        create_raindrops(&charset, color_algorithm,
-                            advance_chance, term_cols, term_rows);
+        //dragging the edge of a window sends a burst of resize events, so only the last 
+        //size received before the next frame is applied, rather than resizing for each one
+        let mut new_size: Option<(u16, u16)> = None;
+
+        //wait for the rest of the frame, handling any events that come in along the way
+        while event::poll(next_frame_instant.saturating_duration_since(Instant::now()))
+            .map_err(MatrixError::Terminal)? 
+        {
+            match event::read().map_err(MatrixError::Terminal)? {
+                //upon recieving a resize event set new column amount, 
+                //unless the size was fixed by the caller
+                Event::Resize(new_cols, new_rows) if size.is_none() => {
+                    new_size = Some((new_cols, new_rows));
+                },
+                //stop loop upon recieving one of the quit keys
+                Event::Key(KeyEvent{code: KeyCode::Char('q' | 'Q') | KeyCode::Esc, ..}) => break 'animation,
+                //in raw mode, Ctrl-C is received as a key event rather than a signal
+                Event::Key(KeyEvent{code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL}) => break 'animation,
+                //toggle pause upon recieving a space
+                Event::Key(KeyEvent{code: KeyCode::Char(' '), ..}) => paused = !paused,
+                //raise or lower the framerate, taking effect from the next frame
+                Event::Key(KeyEvent{code: KeyCode::Char(adjust @ ('+' | '-')), ..}) => {
+                    target_framerate = if adjust == '+' {
+                        target_framerate.saturating_add(FRAMERATE_STEP)
+                    } else {
+                        target_framerate.saturating_sub(FRAMERATE_STEP)
+                    }.clamp(*FRAMERATE_RANGE.start(), *FRAMERATE_RANGE.end());
+                    frame_clock.set_frame_duration(frame_duration(target_framerate));
+                },
+                //switch to a preset color scheme; raindrops are left as they are, 
+                //and only change color from the next frame drawn
+                Event::Key(KeyEvent{code: KeyCode::Char(digit @ '1'..='6'), ..}) => {
+                    let scheme_index = digit as usize - '1' as usize;
+                    animation_state.set_color_algorithm(LoopColors::Scheme(ColorScheme::ALL[scheme_index]));
+                },
+                //dim or brighten every char, taking effect from the current frame
+                Event::Key(KeyEvent{code: KeyCode::Char(adjust @ ('[' | ']')), ..}) => {
+                    let step = if adjust == ']' {BRIGHTNESS_STEP} else {-BRIGHTNESS_STEP};
+                    render_config.brightness = (render_config.brightness + step).clamp(0.0, 1.0);
+                    animation_state.set_render_config(render_config);
                 },
-                //stop loop upon recieving a mouse or key event
-                _ => break
+                //show or hide the framerate overlay; once hidden, the whole screen is 
+                //redrawn to cover it up
+                Event::Key(KeyEvent{code: KeyCode::Char('f' | 'F'), ..}) => {
+                    show_fps = !show_fps;
+                    if !show_fps {
+                        previous_frame = None;
+                    }
+                },
+                //start a ripple where the screen was clicked
+                Event::Mouse(MouseEvent{kind: MouseEventKind::Down(_), column, row, ..}) => {
+                    animation_state.add_ripple(column, row);
+                },
+                //ignore any other key or mouse event
+                _ => ()
             }
         }
+
+        if let Some((new_cols, new_rows)) = new_size {
+            //keep the existing raindrops where possible, rather than starting over
+            animation_state.resize(new_cols, new_rows);
+            overlay_cols = new_cols;
+
+            //clear anything left over from the old size (which the terminal may have
+            //moved around when resizing), then redraw everything on the next frame
+            out.clear()?;
+            previous_frame = None;
+        }
     }
 
-    //disable raw mode
-    terminal::disable_raw_mode()?;
+    //fade out while the terminal guard (if any) is still in place, 
+    //starting from whatever is on screen now
+    if fade_out && interactive {
+        //the framerate overlay is drawn over the frame, so covering it up takes a full redraw
+        let on_screen = previous_frame.as_ref().filter(|_| !show_fps);
+        let last_frame = previous_frame.as_ref().unwrap_or_else(|| animation_state.frame());
+        fade_to_black(out, last_frame, on_screen, target_framerate, render_config.color_depth)?;
+    }
 
-    //be sure to leave the alternate screen and show the cursor again
-    out.queue(terminal::LeaveAlternateScreen)?
-    .queue(cursor::Show)?;
+    //the terminal guard (if any) restores the terminal as it is dropped here
     out.flush()?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Returns a [MatrixError::Config] describing the first of the settings that an animation 
+/// can't be run with, if there are any
+pub(crate) fn check_loop_config(charset: &[char], charsets: &[CharSampler], advance_chance: f64, 
+    target_framerate: usize, raindrop_config: RaindropConfig, fall_speed: f64) -> Result<(), MatrixError>
+{
+    let problem = if charset.is_empty() {
+        "cannot run animation with empty character set"
+    } else if charsets.iter().any(|charset| charset.is_empty()) {
+        "cannot run animation with an empty character set to pick from"
+    } else if target_framerate == 0 {
+        "cannot run animation at target framerate of zero"
+    } else if !(0.0..=1.0).contains(&advance_chance) {
+        "cannot run animation with advance chance outside of range [0.0, 1.0]"
+    } else if raindrop_config.follower_min_length == 0 {
+        "cannot run animation with follower min length of zero"
+    } else if !(fall_speed.is_finite() && fall_speed > 0.0) {
+        "cannot run animation with fall speed that isn't a positive, finite number"
+    } else {
+        return Ok(());
+    };
+    Err(MatrixError::Config(problem.to_string()))
+}
+
+/// Draws `frame` dimming to black over [FADE_OUT_DURATION], at `framerate` frames per second
+/// 
+/// `on_screen` should be the frame already on screen, so only the cells that change are 
+/// redrawn, or `None` to redraw the whole screen to start with.
+fn fade_to_black(out: &mut dyn Backend, frame: &Frame, on_screen: Option<&Frame>, framerate: usize,
+    color_depth: ColorDepth) -> std::io::Result<()>
+{
+    let fade_frames = (FADE_OUT_DURATION.as_secs_f64() * framerate as f64).ceil().max(1.0) as u32;
+    let mut frame_clock = FrameClock::new(Instant::now(), frame_duration(framerate));
+    let mut previous_frame = on_screen.cloned();
+
+    for fade_frame in 1..=fade_frames {
+        let dimmed_frame = frame.dimmed(1.0 - fade_frame as f32 / fade_frames as f32, color_depth);
+        out.draw_frame(&dimmed_frame, previous_frame.as_ref())?;
+        out.flush()?;
+        previous_frame = Some(dimmed_frame);
+
+        let next_frame_instant = frame_clock.schedule_next_frame(Instant::now());
+        std::thread::sleep(next_frame_instant.saturating_duration_since(Instant::now()));
+    }
+
+    Ok(())
+}
+
+/// Schedules the frames of an animation loop so they stay locked to the wall clock
+/// 
+/// Waiting a full frame duration after each frame's work is done lets the time spent working
+/// (and any rounding in the wait) add up, so the animation runs slower than its target framerate.
+/// Instead, each frame is scheduled a fixed frame duration after the one before it, and the loop
+/// waits until that instant. 
+/// 
+/// If a frame runs late by more than a whole frame duration, the schedule skips ahead 
+/// rather than rushing through frames to catch up.
+/// 
+/// # Examples
+/// ```
+/// use std::time::{Duration, Instant};
+/// use mrs_matrix::animation::FrameClock;
+/// 
+/// let mut frame_clock = FrameClock::new(Instant::now(), Duration::from_millis(40));
+/// for _ in 0..3 {
+///     //draw a frame here, then wait for the next one
+///     let next_frame_instant = frame_clock.schedule_next_frame(Instant::now());
+///     std::thread::sleep(next_frame_instant.saturating_duration_since(Instant::now()));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FrameClock {
+    next_frame_instant: Instant,
+    frame_duration: Duration
+}
+
+impl FrameClock {
+    /// Returns a new `FrameClock` for a loop whose first frame starts at `start_instant`
+    /// and whose frames each last `frame_duration`
+    pub fn new(start_instant: Instant, frame_duration: Duration) -> Self
+    {
+        Self {
+            next_frame_instant: start_instant + frame_duration,
+            frame_duration
+        }
+    }
+
+    /// Changes the duration of each frame, taking effect after the frame already scheduled
+    pub fn set_frame_duration(&mut self, frame_duration: Duration)
+    {
+        self.frame_duration = frame_duration;
+    }
+
+    /// Returns the instant the next frame should start at, given that the current frame's work
+    /// finished at `now`, and schedules the frame after it
+    /// 
+    /// The returned instant is `now` if the current frame ran more than a frame duration late.
+    pub fn schedule_next_frame(&mut self, now: Instant) -> Instant
+    {
+        if now.saturating_duration_since(self.next_frame_instant) > self.frame_duration {
+            //too far behind to catch up; start the next frame now and schedule from there
+            self.next_frame_instant = now;
+        }
+
+        let next_frame_instant = self.next_frame_instant;
+        self.next_frame_instant += self.frame_duration;
+        next_frame_instant
+    }
+}
+
+/// Decides how many times to step an animation on each frame to make the rain fall at a 
+/// given speed, independent of the framerate
+/// 
+/// The animation is stepped however many times it takes to keep the total number of steps 
+/// at the fall speed times the number of frames so far, rounded down, so any fraction of a 
+/// step is carried over to the next frame.
+/// At a fall speed of `1.0`, every frame takes exactly one step; at `0.5`, every other frame 
+/// takes a step; and at `2.0`, every frame takes two.
+/// 
+/// # Examples
+/// ```
+/// use mrs_matrix::animation::FallAccumulator;
+/// 
+/// let mut fall_accumulator = FallAccumulator::new(0.5);
+/// let steps: Vec<u32> = (0..4).map(|_| fall_accumulator.steps_for_frame()).collect();
+/// assert_eq!(steps, [0, 1, 0, 1]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FallAccumulator {
+    fall_speed: f64,
+
+    // the number of frames so far, from which the total number of steps is worked out 
+    // (rather than summing the fall speed every frame, which would let rounding errors build up)
+    frames: u64,
+
+    // the total number of steps taken so far
+    steps: u64
+}
+
+impl FallAccumulator {
+    /// Returns a new `FallAccumulator` that takes `fall_speed` steps per frame, on average
+    /// 
+    /// # Panics
+    /// 
+    /// This function panics if `fall_speed` isn't a positive, finite number.
+    pub fn new(fall_speed: f64) -> Self
+    {
+        assert!(fall_speed.is_finite() && fall_speed > 0.0, 
+            "cannot create FallAccumulator with fall speed that isn't positive and finite");
+        Self {
+            fall_speed,
+            frames: 0,
+            steps: 0
+        }
+    }
+
+    /// Returns the number of times to step the animation on the next frame
+    pub fn steps_for_frame(&mut self) -> u32
+    {
+        self.frames = self.frames.saturating_add(1);
+        let total_steps = (self.frames as f64 * self.fall_speed).floor() as u64;
+        let steps = total_steps.saturating_sub(self.steps);
+        self.steps = total_steps;
+        steps as u32
+    }
+}
+
+/// Measures the actual framerate of the animation loop, averaged over recent frames
+struct FrameRateMeter {
+    // the start of each recent frame, oldest first
+    frame_instants: VecDeque<Instant>
+}
+
+impl FrameRateMeter {
+    fn new() -> Self
+    {
+        Self {
+            frame_instants: VecDeque::with_capacity(FPS_SAMPLE_FRAMES + 1)
+        }
+    }
+
+    /// Records that a frame started at `instant`, forgetting frames older than the sample
+    fn record(&mut self, instant: Instant)
+    {
+        self.frame_instants.push_back(instant);
+        if self.frame_instants.len() > FPS_SAMPLE_FRAMES + 1 {
+            self.frame_instants.pop_front();
+        }
+    }
+
+    /// Returns the average framerate over the recorded frames, or `None` until there are enough
+    fn fps(&self) -> Option<f64>
+    {
+        let (oldest, newest) = (self.frame_instants.front()?, self.frame_instants.back()?);
+        let elapsed = newest.duration_since(*oldest).as_secs_f64();
+        if elapsed == 0.0 {
+            return None;
+        }
+        Some((self.frame_instants.len() - 1) as f64 / elapsed)
+    }
+}
+
+/// Draws the framerate overlay, right-aligned in the top row of a terminal `term_cols` wide
+fn draw_fps_overlay(out: &mut dyn Backend, fps: Option<f64>, term_cols: u16) -> std::io::Result<()>
+{
+    let label = match fps {
+        Some(fps) => format!("{:>6.1} fps", fps),
+        None => "   --- fps".to_string()
+    };
+    let label_cols = u16::try_from(label.len()).unwrap_or(u16::MAX);
+
+    out.move_to(term_cols.saturating_sub(label_cols), 0)?;
+    out.print(&label)
+}
+
+/// Returns the size of the terminal in columns and rows
+/// 
+/// If the size can't be queried, it's read from the `COLUMNS` and `LINES` environment 
+/// variables instead, falling back to [FALLBACK_TERMINAL_SIZE] for either one that's missing.
+pub fn terminal_size() -> (u16, u16)
+{
+    terminal::size().unwrap_or_else(|_| {
+        let (fallback_cols, fallback_rows) = FALLBACK_TERMINAL_SIZE;
+        let from_env = |name: &str, fallback: u16| {
+            env::var(name).ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(fallback)
+        };
+        (from_env("COLUMNS", fallback_cols), from_env("LINES", fallback_rows))
+    })
+}
+
+/// Returns `render_config` with any styling the environment says isn't supported turned off
+/// 
+/// Following <https://no-color.org>, a `NO_COLOR` variable that is set to anything other than 
+/// an empty string turns off color. A `TERM` of `dumb` turns off both color and bold,
+/// for followers as well as leaders.
+pub(crate) fn supported_render_config(mut render_config: RenderConfig) -> RenderConfig
+{
+    if env::var_os("NO_COLOR").is_some_and(|no_color| !no_color.is_empty()) {
+        render_config.use_color = false;
+    }
+    if env::var_os("TERM").is_some_and(|term| term == "dumb") {
+        render_config.use_color = false;
+        render_config.bold_leader = false;
+        render_config.follower_bold = FollowerBold::None;
+    }
+    render_config
+}
+
+/// Returns the duration of a single frame at `framerate` frames per second
+fn frame_duration(framerate: usize) -> Duration
+{
+    //divide one second by the number of frames that should be in one second
+    Duration::from_secs_f64(1.0/(framerate as f64))
+}
@@ -0,0 +1,429 @@
+//! The Backend trait, which everything drawn to the terminal goes through, and its implementations
+
+use std::io::{self, Write};
+use crossterm::{
+    cursor,
+    event,
+    style::{Print, PrintStyledContent, StyledContent},
+    terminal,
+    QueueableCommand
+};
+use crate::animation::Frame;
+
+/// A terminal that an animation can be drawn to
+///
+/// Every change the animation makes to the terminal goes through a `Backend`, which keeps the
+/// rest of the library independent of any one terminal crate. [CrosstermBackend] draws to a
+/// real terminal (or any [Write] implementor) with crossterm, and [TestBackend] records what
+/// would have been drawn, so it can be checked in tests.
+///
+/// Only [draw_frame](Backend::draw_frame) has a default implementation, which draws the cells
+/// of a frame one by one with [move_to](Backend::move_to) and [print_styled](Backend::print_styled).
+/// Backends that can draw a whole frame more efficiently may override it.
+pub trait Backend {
+    /// Switches to the alternate screen, leaving the contents of the main screen untouched
+    fn enter_alternate_screen(&mut self) -> io::Result<()>;
+
+    /// Switches back to the main screen from the alternate screen
+    fn leave_alternate_screen(&mut self) -> io::Result<()>;
+
+    /// Enables raw mode, so that keypresses can be read one by one as they happen
+    fn enable_raw_mode(&mut self) -> io::Result<()>;
+
+    /// Disables raw mode
+    fn disable_raw_mode(&mut self) -> io::Result<()>;
+
+    /// Starts reporting clicks and other mouse events as input
+    fn enable_mouse_capture(&mut self) -> io::Result<()>;
+
+    /// Stops reporting mouse events as input
+    fn disable_mouse_capture(&mut self) -> io::Result<()>;
+
+    /// Hides the cursor
+    fn hide_cursor(&mut self) -> io::Result<()>;
+
+    /// Shows the cursor
+    fn show_cursor(&mut self) -> io::Result<()>;
+
+    /// Moves the cursor to the given column and row, counting from zero at the top left corner
+    fn move_to(&mut self, column: u16, row: u16) -> io::Result<()>;
+
+    /// Prints a styled char at the cursor, moving the cursor one column to the right
+    fn print_styled(&mut self, content: StyledContent<char>) -> io::Result<()>;
+
+    /// Prints unstyled text at the cursor, moving the cursor to the end of it
+    fn print(&mut self, text: &str) -> io::Result<()>;
+
+    /// Clears the whole screen
+    fn clear(&mut self) -> io::Result<()>;
+
+    /// Makes sure everything drawn so far has reached the terminal
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Draws `frame`, starting from `previous` if that's what's already on screen
+    ///
+    /// If `previous` is `None`, or has different dimensions than `frame`, every cell is drawn;
+    /// otherwise, only the cells that differ between the two frames are drawn. Empty cells
+    /// are drawn as spaces.
+    fn draw_frame(&mut self, frame: &Frame, previous: Option<&Frame>) -> io::Result<()>
+    {
+        let (term_cols, term_rows) = frame.size();
+        let previous = previous.filter(|previous| previous.size() == frame.size());
+
+        for row_index in 0..term_rows {
+            for column_index in 0..term_cols {
+                let cell = frame.get(column_index, row_index);
+                if previous.is_some_and(|previous| previous.get(column_index, row_index) == cell) {
+                    continue;
+                }
+
+                self.move_to(column_index, row_index)?;
+                match cell {
+                    Some(styled_char) => self.print_styled(styled_char)?,
+                    None => self.print(" ")?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A [Backend] that draws to any [Write] implementor, such as `stdout`, with crossterm
+///
+/// Each frame is rendered with [Frame::render_into] or [Frame::render_diff_into] and sent to `out`
+/// in a single write, rather than cell by cell. Every frame is rendered into the same buffer, so
+/// once it has grown to fit the largest frame, drawing doesn't allocate. Whatever `out` is, raw
+/// mode is enabled and disabled for the terminal the process is attached to.
+///
+/// Anything written to a `CrosstermBackend` as a [Write] implementor is passed along to `out`.
+pub struct CrosstermBackend<W: Write> {
+    out: W,
+
+    // each frame is rendered into this before being written, keeping its capacity between frames
+    buffer: String
+}
+
+impl<W: Write> CrosstermBackend<W> {
+
+    /// Returns a new `CrosstermBackend` drawing to `out`
+    pub fn new(out: W) -> Self
+    {
+        Self { out, buffer: String::new() }
+    }
+
+    /// Returns the writer this `CrosstermBackend` draws to
+    pub fn into_inner(self) -> W
+    {
+        self.out
+    }
+}
+
+impl<W: Write> Backend for CrosstermBackend<W> {
+    fn enter_alternate_screen(&mut self) -> io::Result<()>
+    {
+        self.out.queue(terminal::EnterAlternateScreen).map(|_| ())
+    }
+
+    fn leave_alternate_screen(&mut self) -> io::Result<()>
+    {
+        self.out.queue(terminal::LeaveAlternateScreen).map(|_| ())
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()>
+    {
+        terminal::enable_raw_mode()
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()>
+    {
+        terminal::disable_raw_mode()
+    }
+
+    fn enable_mouse_capture(&mut self) -> io::Result<()>
+    {
+        self.out.queue(event::EnableMouseCapture).map(|_| ())
+    }
+
+    fn disable_mouse_capture(&mut self) -> io::Result<()>
+    {
+        self.out.queue(event::DisableMouseCapture).map(|_| ())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()>
+    {
+        self.out.queue(cursor::Hide).map(|_| ())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()>
+    {
+        self.out.queue(cursor::Show).map(|_| ())
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> io::Result<()>
+    {
+        self.out.queue(cursor::MoveTo(column, row)).map(|_| ())
+    }
+
+    fn print_styled(&mut self, content: StyledContent<char>) -> io::Result<()>
+    {
+        self.out.queue(PrintStyledContent(content)).map(|_| ())
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()>
+    {
+        self.out.queue(Print(text)).map(|_| ())
+    }
+
+    fn clear(&mut self) -> io::Result<()>
+    {
+        self.out.queue(terminal::Clear(terminal::ClearType::All)).map(|_| ())
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.out.flush()
+    }
+
+    fn draw_frame(&mut self, frame: &Frame, previous: Option<&Frame>) -> io::Result<()>
+    {
+        //build up the whole frame as a single string, then write it all at once
+        match previous {
+            None => frame.render_into(&mut self.buffer),
+            Some(previous) => frame.render_diff_into(previous, &mut self.buffer)
+        }
+        self.out.write_all(self.buffer.as_bytes())
+    }
+}
+
+impl<W: Write> Write for CrosstermBackend<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.out.flush()
+    }
+}
+
+/// A single call made to a [TestBackend]
+///
+/// Each variant matches the [Backend] method of the same name, holding any arguments it was given.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DrawCall {
+    /// [enter_alternate_screen](Backend::enter_alternate_screen)
+    EnterAlternateScreen,
+    /// [leave_alternate_screen](Backend::leave_alternate_screen)
+    LeaveAlternateScreen,
+    /// [enable_raw_mode](Backend::enable_raw_mode)
+    EnableRawMode,
+    /// [disable_raw_mode](Backend::disable_raw_mode)
+    DisableRawMode,
+    /// [enable_mouse_capture](Backend::enable_mouse_capture)
+    EnableMouseCapture,
+    /// [disable_mouse_capture](Backend::disable_mouse_capture)
+    DisableMouseCapture,
+    /// [hide_cursor](Backend::hide_cursor)
+    HideCursor,
+    /// [show_cursor](Backend::show_cursor)
+    ShowCursor,
+    /// [move_to](Backend::move_to), with the column and row moved to
+    MoveTo(u16, u16),
+    /// [print_styled](Backend::print_styled), with the styled char printed
+    PrintStyled(StyledContent<char>),
+    /// [print](Backend::print), with the text printed
+    Print(String),
+    /// [clear](Backend::clear)
+    Clear,
+    /// [flush](Backend::flush)
+    Flush
+}
+
+/// A [Backend] that records every call made to it, rather than drawing anything
+///
+/// This allows what an animation draws to be checked in tests, without a terminal.
+/// Frames are drawn cell by cell, with the default implementation of
+/// [draw_frame](Backend::draw_frame).
+///
+/// # Examples
+/// ```
+/// use mrs_matrix::backend::{Backend, DrawCall, TestBackend};
+///
+/// let mut backend = TestBackend::new();
+/// backend.move_to(3, 1).unwrap();
+/// backend.print("hi").unwrap();
+/// assert_eq!(backend.calls(), [DrawCall::MoveTo(3, 1), DrawCall::Print("hi".to_string())]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TestBackend {
+    calls: Vec<DrawCall>
+}
+
+impl TestBackend {
+
+    /// Returns a new `TestBackend` that hasn't had any calls made to it
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Returns every call made to this `TestBackend` so far, oldest first
+    pub fn calls(&self) -> &[DrawCall]
+    {
+        &self.calls
+    }
+}
+
+impl Backend for TestBackend {
+    fn enter_alternate_screen(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::EnterAlternateScreen);
+        Ok(())
+    }
+
+    fn leave_alternate_screen(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::LeaveAlternateScreen);
+        Ok(())
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::EnableRawMode);
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::DisableRawMode);
+        Ok(())
+    }
+
+    fn enable_mouse_capture(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::EnableMouseCapture);
+        Ok(())
+    }
+
+    fn disable_mouse_capture(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::DisableMouseCapture);
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::HideCursor);
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::ShowCursor);
+        Ok(())
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::MoveTo(column, row));
+        Ok(())
+    }
+
+    fn print_styled(&mut self, content: StyledContent<char>) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::PrintStyled(content));
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::Print(text.to_string()));
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::Clear);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.calls.push(DrawCall::Flush);
+        Ok(())
+    }
+}
+
+impl<B: Backend + ?Sized> Backend for &mut B {
+    fn enter_alternate_screen(&mut self) -> io::Result<()>
+    {
+        (**self).enter_alternate_screen()
+    }
+
+    fn leave_alternate_screen(&mut self) -> io::Result<()>
+    {
+        (**self).leave_alternate_screen()
+    }
+
+    fn enable_raw_mode(&mut self) -> io::Result<()>
+    {
+        (**self).enable_raw_mode()
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()>
+    {
+        (**self).disable_raw_mode()
+    }
+
+    fn enable_mouse_capture(&mut self) -> io::Result<()>
+    {
+        (**self).enable_mouse_capture()
+    }
+
+    fn disable_mouse_capture(&mut self) -> io::Result<()>
+    {
+        (**self).disable_mouse_capture()
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()>
+    {
+        (**self).hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()>
+    {
+        (**self).show_cursor()
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> io::Result<()>
+    {
+        (**self).move_to(column, row)
+    }
+
+    fn print_styled(&mut self, content: StyledContent<char>) -> io::Result<()>
+    {
+        (**self).print_styled(content)
+    }
+
+    fn print(&mut self, text: &str) -> io::Result<()>
+    {
+        (**self).print(text)
+    }
+
+    fn clear(&mut self) -> io::Result<()>
+    {
+        (**self).clear()
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        (**self).flush()
+    }
+
+    fn draw_frame(&mut self, frame: &Frame, previous: Option<&Frame>) -> io::Result<()>
+    {
+        (**self).draw_frame(frame, previous)
+    }
+}
@@ -0,0 +1,409 @@
+//! A builder for configuring and running the animation
+
+use std::io::{stdout, IsTerminal, Write};
+use std::time::Duration;
+use crate::decode::DecodeEffect;
+use crate::error::MatrixError;
+use crate::animation::{anim_loop_with_writer, check_loop_config, supported_render_config, terminal_size, 
+    AnimationState, LoopOptions};
+use crate::raindrop::{RaindropConfig, RenderConfig};
+use crate::raindrop::charsets::{AsciiAndSymbols, CharSampler, Charset};
+use crate::raindrop::color_algorithms::{ColorAlgorithm, ColorScheme};
+#[cfg(feature = "gif")]
+use crate::animation::FallAccumulator;
+#[cfg(feature = "gif")]
+use crate::gif::GifWriter;
+
+// the most rows above the terminal a raindrop can start from, which a one-shot frame 
+// gives the rain time to fall from
+const ONESHOT_LEAD_IN_FRAMES: u32 = 64;
+
+/// Configures an animation with chainable setters, then runs it
+///
+/// Any setting that isn't set keeps the same default as the `mrs-matrix` binary:
+/// the [AsciiAndSymbols] charset, the green [ColorScheme], an advance chance of 0.75,
+/// a target framerate of 25, the default [RaindropConfig] and [RenderConfig], no seed, 
+/// no duration or frame limit, the size of the terminal, no message, a fall speed of 1.0, 
+/// no starting pause, and no fade out.
+///
+/// See [anim_loop](crate::animation::anim_loop) for a description of each setting.
+///
+/// # Examples
+/// ```no_run
+/// use mrs_matrix::{MatrixBuilder, MatrixError};
+/// use mrs_matrix::raindrop::color_algorithms::HueVariation;
+///
+/// pub fn main() -> Result<(), MatrixError>
+/// {
+///     MatrixBuilder::new()
+///         .charset("01".chars().collect())
+///         .color(HueVariation::new(1.0, 0.5)?)
+///         .framerate(30)
+///         .seed(42)
+///         .run()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MatrixBuilder<T: ColorAlgorithm = ColorScheme> {
+    charset: Vec<char>,
+    charsets: Vec<CharSampler>,
+    color_algorithm: T,
+    advance_chance: f64,
+    target_framerate: usize,
+    raindrop_config: RaindropConfig,
+    render_config: RenderConfig,
+    seed: Option<u64>,
+    duration: Option<Duration>,
+    frames: Option<u64>,
+    size: Option<(u16, u16)>,
+    message: Option<String>,
+    fall_speed: f64,
+    start_paused: bool,
+    fade_out: bool,
+
+    // the target text and lock rate of the decode effect, which is created when the animation 
+    // starts so that it can be seeded
+    decode: Option<(String, f64)>
+}
+
+impl MatrixBuilder {
+
+    /// Returns a new `MatrixBuilder` with every setting at its default
+    pub fn new() -> Self
+    {
+        Self {
+            charset: AsciiAndSymbols().get_charset(),
+            charsets: Vec::new(),
+            color_algorithm: ColorScheme::Green,
+            advance_chance: 0.75,
+            target_framerate: 25,
+            raindrop_config: RaindropConfig::default(),
+            render_config: RenderConfig::default(),
+            seed: None,
+            duration: None,
+            frames: None,
+            size: None,
+            message: None,
+            fall_speed: 1.0,
+            start_paused: false,
+            fade_out: false,
+            decode: None
+        }
+    }
+}
+
+impl Default for MatrixBuilder {
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl<T: ColorAlgorithm> MatrixBuilder<T> {
+
+    /// Sets the set of characters that will be displayed within the animation
+    pub fn charset(mut self, charset: Vec<char>) -> Self
+    {
+        self.charset = charset;
+        self
+    }
+
+    /// Sets several charsets for each raindrop to pick one of whenever it respawns, 
+    /// for a mix of scripts on screen
+    /// 
+    /// Every char of a single fall comes from the same charset, picked as often as its 
+    /// [CharSampler] says, so a single [Weighted](crate::raindrop::charsets::Weighted) charset's 
+    /// sampler can be given to weight the rain. When this is empty (as it is by default), every 
+    /// raindrop draws from the [charset](MatrixBuilder::charset); otherwise, that charset is only 
+    /// used to scramble the [decode](MatrixBuilder::decode) effect.
+    pub fn charsets(mut self, charsets: Vec<CharSampler>) -> Self
+    {
+        self.charsets = charsets;
+        self
+    }
+
+    /// Sets the algorithm used to color follower characters
+    ///
+    /// This can be any type implementing [ColorAlgorithm], including a `Box<dyn ColorAlgorithm>`.
+    pub fn color<U: ColorAlgorithm>(self, color_algorithm: U) -> MatrixBuilder<U>
+    {
+        MatrixBuilder {
+            charset: self.charset,
+            charsets: self.charsets,
+            color_algorithm,
+            advance_chance: self.advance_chance,
+            target_framerate: self.target_framerate,
+            raindrop_config: self.raindrop_config,
+            render_config: self.render_config,
+            seed: self.seed,
+            duration: self.duration,
+            frames: self.frames,
+            size: self.size,
+            message: self.message,
+            fall_speed: self.fall_speed,
+            start_paused: self.start_paused,
+            fade_out: self.fade_out,
+            decode: self.decode
+        }
+    }
+
+    /// Sets the chance (from 0.0 to 1.0) that any one `Raindrop` will advance on any given frame
+    pub fn advance_chance(mut self, advance_chance: f64) -> Self
+    {
+        self.advance_chance = advance_chance;
+        self
+    }
+
+    /// Sets the number of frames per second to target
+    pub fn framerate(mut self, target_framerate: usize) -> Self
+    {
+        self.target_framerate = target_framerate;
+        self
+    }
+
+    /// Sets the [RaindropConfig], which controls the length of raindrop followers
+    pub fn raindrop_config(mut self, raindrop_config: RaindropConfig) -> Self
+    {
+        self.raindrop_config = raindrop_config;
+        self
+    }
+
+    /// Sets the [RenderConfig], which controls how leader characters are styled
+    pub fn render_config(mut self, render_config: RenderConfig) -> Self
+    {
+        self.render_config = render_config;
+        self
+    }
+
+    /// Seeds the random number generator, making the animation reproducible
+    pub fn seed(mut self, seed: u64) -> Self
+    {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets how long the animation runs for before returning
+    pub fn duration(mut self, duration: Duration) -> Self
+    {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Sets how many frames are drawn before returning
+    pub fn frames(mut self, frames: u64) -> Self
+    {
+        self.frames = Some(frames);
+        self
+    }
+
+    /// Sets how many times the animation is stepped per frame, on average, to make the rain fall
+    /// faster or slower without changing the framerate; see [FallAccumulator]
+    /// 
+    /// # Panics
+    /// 
+    /// This function panics if `fall_speed` isn't a positive, finite number.
+    pub fn fall_speed(mut self, fall_speed: f64) -> Self
+    {
+        assert!(fall_speed.is_finite() && fall_speed > 0.0, 
+            "cannot set fall speed that isn't positive and finite");
+        self.fall_speed = fall_speed;
+        self
+    }
+
+    /// Sets whether the animation starts out paused, showing its first frame until it's resumed
+    /// 
+    /// This only has an effect when the animation is run interactively; see 
+    /// [LoopOptions] for how it interacts with the duration.
+    pub fn start_paused(mut self, start_paused: bool) -> Self
+    {
+        self.start_paused = start_paused;
+        self
+    }
+
+    /// Sets whether the screen fades to black when the animation ends, rather than being 
+    /// cleared straight away
+    /// 
+    /// Like [start_paused](MatrixBuilder::start_paused), this only has an effect when the 
+    /// animation is run interactively.
+    pub fn fade_out(mut self, fade_out: bool) -> Self
+    {
+        self.fade_out = fade_out;
+        self
+    }
+
+    /// Sets a fixed size (in columns and rows) to draw at, instead of the size of the terminal
+    /// 
+    /// With a fixed size, resizing the terminal doesn't change the size of the animation.
+    pub fn size(mut self, term_cols: u16, term_rows: u16) -> Self
+    {
+        self.size = Some((term_cols, term_rows));
+        self
+    }
+
+    /// Sets a message to draw over the middle of the animation, in front of the rain
+    /// 
+    /// See [AnimationState::set_message] for how the message is drawn.
+    pub fn message(mut self, message: impl Into<String>) -> Self
+    {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Sets text for a [DecodeEffect] to settle into over the middle of the animation, 
+    /// with each character having a `lock_rate` chance of locking in on each frame
+    /// 
+    /// With a seed, the effect settles the same way every time. See 
+    /// [AnimationState::set_decode_effect] for how the effect is drawn.
+    /// 
+    /// # Panics
+    /// 
+    /// This function panics if `lock_rate` is outside the range `[0.0, 1.0]`.
+    pub fn decode(mut self, target: impl Into<String>, lock_rate: f64) -> Self
+    {
+        assert!((0.0..=1.0).contains(&lock_rate),
+            "cannot decode with lock rate outside of range [0.0, 1.0]");
+        self.decode = Some((target.into(), lock_rate));
+        self
+    }
+
+    /// Returns a single frame of the animation as lines of characters and ANSI escape codes,
+    /// without drawing anything or touching the terminal
+    /// 
+    /// The animation is first stepped for long enough that the rain has filled the whole 
+    /// terminal, rather than returning the empty frame the animation starts with. With a seed,
+    /// the same frame is returned every time. See [Frame::render_lines](crate::Frame::render_lines)
+    /// for the format of the returned `String`.
+    /// 
+    /// The frame is drawn at the size set with [size](MatrixBuilder::size), or otherwise at the 
+    /// size of the terminal, falling back as [anim_loop_with_writer] does if it can't be found.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns [MatrixError::Config] under the same conditions as 
+    /// [anim_loop](crate::animation::anim_loop), such as an empty charset.
+    pub fn render_oneshot(&self) -> Result<String, MatrixError>
+    {
+        check_loop_config(&self.charset, &self.charsets, self.advance_chance, self.target_framerate, 
+            self.raindrop_config, self.fall_speed)?;
+
+        let (term_cols, term_rows) = self.size.unwrap_or_else(terminal_size);
+        let mut animation_state = AnimationState::new(&self.charset, &self.color_algorithm, 
+            self.advance_chance, self.raindrop_config, self.seed, term_cols, term_rows);
+        animation_state.set_render_config(supported_render_config(self.render_config));
+        if !self.charsets.is_empty() {
+            animation_state.set_charsets(&self.charsets);
+        }
+        animation_state.set_message(self.message.clone());
+        animation_state.set_decode_effect(self.decode_effect());
+
+        let (_, lane_length) = self.raindrop_config.direction.lane_dimensions(term_cols, term_rows);
+        for _ in 0..(u32::from(lane_length) + ONESHOT_LEAD_IN_FRAMES) {
+            animation_state.step();
+        }
+        Ok(animation_state.frame().render_lines())
+    }
+
+    /// Records the animation as a looping animated GIF written to `out`, without drawing anything
+    /// or touching the terminal
+    /// 
+    /// The GIF has the number of frames set with [frames](MatrixBuilder::frames), or otherwise 
+    /// as many frames as the [duration](MatrixBuilder::duration) lasts at the target framerate.
+    /// Like the animation, it starts from an empty frame. Frames are drawn as fast as possible, 
+    /// but each is shown for a frame's duration at the target framerate. With a seed, the same 
+    /// GIF is written every time. See [GifWriter] for how frames are drawn.
+    /// 
+    /// The GIF is drawn at the size set with [size](MatrixBuilder::size), or otherwise at the 
+    /// size of the terminal, falling back as [anim_loop_with_writer] does if it can't be found.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns [MatrixError::Config] if neither a number of frames nor a duration is set, or 
+    /// under the same conditions as [anim_loop](crate::animation::anim_loop). Returns 
+    /// [MatrixError::Io] for any error returned by [GifWriter].
+    #[cfg(feature = "gif")]
+    pub fn render_gif<W: Write>(&self, out: W) -> Result<(), MatrixError>
+    {
+        let frames = self.frames
+            .or_else(|| self.duration.map(|duration| {
+                (duration.as_secs_f64() * self.target_framerate as f64).round() as u64
+            }))
+            .ok_or_else(|| MatrixError::Config(
+                "a GIF needs a number of frames or a duration".to_string()))?;
+        check_loop_config(&self.charset, &self.charsets, self.advance_chance, self.target_framerate, 
+            self.raindrop_config, self.fall_speed)?;
+
+        let (term_cols, term_rows) = self.size.unwrap_or_else(terminal_size);
+        let mut animation_state = AnimationState::new(&self.charset, &self.color_algorithm, 
+            self.advance_chance, self.raindrop_config, self.seed, term_cols, term_rows);
+        animation_state.set_render_config(self.render_config);
+        if !self.charsets.is_empty() {
+            animation_state.set_charsets(&self.charsets);
+        }
+        animation_state.set_message(self.message.clone());
+        animation_state.set_decode_effect(self.decode_effect());
+
+        let frame_interval = Duration::from_secs_f64(1.0 / self.target_framerate as f64);
+        let mut gif = GifWriter::new(out, term_cols, term_rows, frame_interval)?;
+        let mut fall_accumulator = FallAccumulator::new(self.fall_speed);
+        for _ in 0..frames {
+            gif.write_frame(animation_state.frame())?;
+            for _ in 0..fall_accumulator.steps_for_frame() {
+                animation_state.step();
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the animation in the terminal, as [anim_loop](crate::animation::anim_loop) does
+    /// 
+    /// If `stdout` isn't a terminal, the animation is drawn to it non-interactively; see 
+    /// [anim_loop_with_writer] for what that means.
+    ///
+    ///# Errors
+    ///
+    /// Returns an error under the same conditions as [anim_loop](crate::animation::anim_loop).
+    pub fn run(self) -> Result<(), MatrixError>
+    {
+        //hold the lock on stdout for the whole animation, rather than taking it for every write
+        let out = stdout().lock();
+        let interactive = out.is_terminal();
+        self.run_with_writer(out, interactive)
+    }
+
+    /// Runs the animation, drawing to `out`, as [anim_loop_with_writer] does
+    ///
+    /// See [LoopOptions] for a description of `interactive`.
+    ///
+    ///# Errors
+    ///
+    /// Returns an error under the same conditions as [anim_loop](crate::animation::anim_loop).
+    pub fn run_with_writer<W: Write>(self, out: W, interactive: bool) -> Result<(), MatrixError>
+    {
+        let options = LoopOptions {
+            interactive,
+            render_config: self.render_config,
+            seed: self.seed,
+            duration: self.duration,
+            frames: self.frames,
+            decode_effect: self.decode_effect(),
+            size: self.size,
+            message: self.message,
+            fall_speed: self.fall_speed,
+            start_paused: self.start_paused,
+            fade_out: self.fade_out,
+            charsets: self.charsets,
+            on_frame: None
+        };
+        anim_loop_with_writer(out, self.charset, self.color_algorithm, self.advance_chance,
+            self.target_framerate, self.raindrop_config, options)
+    }
+
+    // returns a new decode effect for the decode setting, seeded if the animation is
+    fn decode_effect(&self) -> Option<DecodeEffect>
+    {
+        self.decode.as_ref().map(|(target, lock_rate)| match self.seed {
+            None => DecodeEffect::new(target, *lock_rate),
+            Some(seed) => DecodeEffect::new_seeded(target, *lock_rate, seed)
+        })
+    }
+}
@@ -0,0 +1,131 @@
+//! Recording animations as [asciinema](https://asciinema.org) cast files
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Records everything written to it as an asciinema v2 cast file
+///
+/// Creating a `CastWriter` writes the cast header. After that, everything written is held
+/// until [flush](Write::flush) is called, at which point it's written out as a single output
+/// event. As the animation loop flushes once per frame, each frame becomes one event.
+///
+/// Events are timed by counting flushes rather than by the clock: the first flush is at 0
+/// seconds, and each one after is `frame_interval` later than the last. A flush with nothing
+/// written (such as a frame in which nothing changed) writes no event, but still takes up its
+/// time. This keeps recordings of seeded animations byte-for-byte reproducible, but means the
+/// writer should be flushed exactly once per frame. Anything not yet flushed is written out
+/// when the `CastWriter` is dropped.
+///
+/// # Examples
+/// ```no_run
+/// use std::fs::File;
+/// use std::time::Duration;
+/// use mrs_matrix::{MatrixBuilder, MatrixError};
+/// use mrs_matrix::cast::CastWriter;
+///
+/// pub fn main() -> Result<(), MatrixError>
+/// {
+///     let file = File::create("rain.cast")?;
+///     let cast = CastWriter::new(file, 80, 24, Duration::from_secs_f64(1.0 / 25.0))?;
+///     MatrixBuilder::new()
+///         .size(80, 24)
+///         .seed(42)
+///         .frames(250)
+///         .run_with_writer(cast, false)
+/// }
+/// ```
+pub struct CastWriter<W: Write> {
+    out: W,
+
+    // the time between one event and the next
+    frame_interval: Duration,
+
+    // the number of flushes so far, used to time the next event
+    flushes: u32,
+
+    // output written since the last flush, which becomes the next event
+    pending: Vec<u8>
+}
+
+impl<W: Write> CastWriter<W> {
+
+    /// Returns a new `CastWriter` wrapping `out`, after writing the header of a cast
+    /// `width` columns wide and `height` rows high
+    ///
+    /// `frame_interval` is the time between one flush's event and the next; this should
+    /// be the duration of a single frame.
+    pub fn new(mut out: W, width: u16, height: u16, frame_interval: Duration) -> io::Result<Self>
+    {
+        writeln!(out, "{{\"version\": 2, \"width\": {}, \"height\": {}}}", width, height)?;
+
+        Ok(Self {
+            out,
+            frame_interval,
+            flushes: 0,
+            pending: Vec::new()
+        })
+    }
+
+    // writes anything written since the last flush as a new event
+    fn write_event(&mut self) -> io::Result<()>
+    {
+        if !self.pending.is_empty() {
+            let time = self.frame_interval.as_secs_f64() * f64::from(self.flushes);
+            let data = json_string(&String::from_utf8_lossy(&self.pending));
+            writeln!(self.out, "[{:.6}, \"o\", {}]", time, data)?;
+            self.pending.clear();
+        }
+
+        self.flushes = self.flushes.saturating_add(1);
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for CastWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.write_event()?;
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Drop for CastWriter<W> {
+    fn drop(&mut self)
+    {
+        //there is nowhere to report an error from here, so a failure to write is ignored
+        if !self.pending.is_empty() {
+            let _ = self.flush();
+        }
+    }
+}
+
+// returns s as a quoted JSON string, escaping anything JSON doesn't allow as is
+fn json_string(s: &str) -> String
+{
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            //control characters (such as the escape that starts every ANSI escape code)
+            //must be written as unicode escapes
+            c if c < ' ' => {
+                write!(quoted, "\\u{:04x}", c as u32).expect("writing to a String should never fail");
+            },
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,118 @@
+//! An effect where scrambled characters settle into a message, one at a time
+
+use crossterm::style::StyledContent;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use crate::raindrop::RenderConfig;
+
+/// Text that starts out scrambled and settles into place, one character at a time
+///
+/// Each character of the target text starts out unlocked, flickering through random characters
+/// of the animation's charset. On every step, each unlocked character has a `lock_rate` chance
+/// of locking in, after which it shows the target character and never changes again.
+/// Spaces in the target are locked from the start.
+///
+/// The effect is drawn over the middle row of the animation, centered, in front of the rain;
+/// see [AnimationState::set_decode_effect](crate::AnimationState::set_decode_effect).
+/// Locked characters are styled like the leaders of the raindrops, and unlocked characters
+/// in the leader color but never bold. Unlocked characters are drawn as empty cells until
+/// the effect is first stepped.
+#[derive(Clone, Debug)]
+pub struct DecodeEffect {
+    // the text the effect settles into
+    target: Vec<char>,
+
+    // the character shown in each unlocked cell, or None for locked cells and
+    // unlocked cells that haven't been stepped yet
+    flicker: Vec<Option<char>>,
+
+    // whether each cell has locked in to its target character
+    locked: Vec<bool>,
+
+    lock_rate: f64,
+
+    // this is seeded either from system entropy or from a user provided seed
+    local_rng: StdRng
+}
+
+impl DecodeEffect {
+
+    /// Returns a new `DecodeEffect` that settles into `target`, with each unlocked character
+    /// having a `lock_rate` chance of locking in on each step
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `lock_rate` is outside the range `[0.0, 1.0]`.
+    pub fn new(target: &str, lock_rate: f64) -> Self
+    {
+        Self::with_rng(target, lock_rate, StdRng::from_entropy())
+    }
+
+    /// Returns a new `DecodeEffect` whose randomness is fully determined by `seed`
+    ///
+    /// This is the same as [new](DecodeEffect::new), except that two `DecodeEffect`s created
+    /// with the same arguments and the same `seed` will flicker and lock in identically.
+    ///
+    /// # Panics
+    ///
+    /// This function panics under the same conditions as [new](DecodeEffect::new).
+    pub fn new_seeded(target: &str, lock_rate: f64, seed: u64) -> Self
+    {
+        Self::with_rng(target, lock_rate, StdRng::seed_from_u64(seed))
+    }
+
+    // shared implementation of new and new_seeded
+    fn with_rng(target: &str, lock_rate: f64, local_rng: StdRng) -> Self
+    {
+        assert!((0.0..=1.0).contains(&lock_rate),
+            "cannot create DecodeEffect with lock rate outside of range [0.0, 1.0]");
+
+        let target: Vec<char> = target.chars().collect();
+        let locked = target.iter().map(|c| *c == ' ').collect();
+        Self {
+            flicker: vec![None; target.len()],
+            target,
+            locked,
+            lock_rate,
+            local_rng
+        }
+    }
+
+    /// Advances the effect by one frame
+    ///
+    /// Each unlocked character either locks in, with a chance of the lock rate, or changes to
+    /// a random character of `charset`. If `charset` is empty, unlocked characters are left empty.
+    pub fn step(&mut self, charset: &[char])
+    {
+        for (locked, flicker) in self.locked.iter_mut().zip(self.flicker.iter_mut()) {
+            if *locked {
+                continue;
+            }
+            if self.local_rng.gen_bool(self.lock_rate) {
+                *locked = true;
+                *flicker = None;
+            } else {
+                *flicker = charset.choose(&mut self.local_rng).copied();
+            }
+        }
+    }
+
+    /// Returns `true` once every character has locked in to the target text
+    pub fn is_settled(&self) -> bool
+    {
+        self.locked.iter().all(|locked| *locked)
+    }
+
+    // returns the styled characters the effect currently shows, one per character of the target
+    // text, with None for empty cells
+    pub(crate) fn cells<'b>(&'b self, render_config: &'b RenderConfig)
+    -> impl ExactSizeIterator<Item = Option<StyledContent<char>>> + 'b
+    {
+        let flickering_config = RenderConfig{bold_leader: false, ..*render_config};
+        self.target.iter().zip(&self.locked).zip(&self.flicker)
+            .map(move |((target, locked), flicker)| match (*locked, *target, *flicker) {
+                (true, ' ', _) => None,
+                (true, target, _) => Some(render_config.style_leader(target)),
+                (false, _, flicker) => flicker.map(|c| flickering_config.style_leader(c))
+            })
+    }
+}
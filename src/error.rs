@@ -0,0 +1,61 @@
+//! The error type returned by the animation's entry points
+
+use std::fmt;
+use std::io;
+use crate::raindrop::color_algorithms::ColorError;
+
+/// An error returned when an animation can't be started, or stops because something went wrong
+///
+/// This keeps callers from depending on the terminal backend's own error type. Any
+/// [io::Error] (which is also what the terminal backend returns) converts into the `Io`
+/// variant, and any [ColorError] into the `InvalidColor` variant, so both can be passed
+/// along with `?`.
+#[derive(Debug)]
+pub enum MatrixError {
+    /// Writing the animation (or reading input) failed
+    Io(io::Error),
+    /// The terminal couldn't be set up for the animation, or read from while it ran
+    Terminal(io::Error),
+    /// A setting was invalid, such as an empty charset or a framerate of zero
+    Config(String),
+    /// A color algorithm was given a color component outside of its range
+    InvalidColor(ColorError)
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            MatrixError::Io(error) => write!(f, "failed to write animation: {}", error),
+            MatrixError::Terminal(error) => write!(f, "failed to control terminal: {}", error),
+            MatrixError::Config(message) => write!(f, "invalid configuration: {}", message),
+            MatrixError::InvalidColor(error) => write!(f, "invalid color: {}", error)
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+    {
+        match self {
+            MatrixError::Io(error) | MatrixError::Terminal(error) => Some(error),
+            MatrixError::Config(_) => None,
+            MatrixError::InvalidColor(error) => Some(error)
+        }
+    }
+}
+
+// crossterm::ErrorKind is an alias of io::Error, so this also converts any terminal backend error
+impl From<io::Error> for MatrixError {
+    fn from(error: io::Error) -> Self
+    {
+        MatrixError::Io(error)
+    }
+}
+
+impl From<ColorError> for MatrixError {
+    fn from(error: ColorError) -> Self
+    {
+        MatrixError::InvalidColor(error)
+    }
+}
@@ -0,0 +1,85 @@
+//! Exporting frames of the animation for display outside of a terminal
+
+use std::fmt::Write;
+use crossterm::style::Attribute;
+use crate::Frame;
+use crate::raindrop::color_depth::terminal_color_to_rgb;
+
+/// Returns `frame` as an HTML `<pre>` block, with each character in a `<span>` styled to match
+/// how it's drawn in the terminal
+///
+/// Each character's color is written as a `#rrggbb` hex color, with palette colors (such as
+/// the default bright white of the leaders) converted to their xterm default values. Bold
+/// characters are given `font-weight:bold`, and characters drawn without color inherit the
+/// color of the page. Empty cells are left as spaces, and the block has a black background
+/// so the rain looks as it does in the terminal. Characters that are special in HTML are
+/// escaped, so the block can be pasted directly into a page.
+///
+/// To export the current frame of an [AnimationState](crate::AnimationState), pass it
+/// [frame](crate::AnimationState::frame).
+///
+/// # Examples
+/// ```
+/// use mrs_matrix::AnimationState;
+/// use mrs_matrix::export::render_frame_html;
+/// use mrs_matrix::raindrop::RaindropConfig;
+/// use mrs_matrix::raindrop::color_algorithms::ColorScheme;
+///
+/// let charset = "01".chars().collect();
+/// let mut animation_state = AnimationState::new(&charset, ColorScheme::Green, 0.75,
+///     RaindropConfig::default(), Some(42), 80, 24);
+/// for _ in 0..100 {
+///     animation_state.step();
+/// }
+/// let html = render_frame_html(animation_state.frame());
+/// assert!(html.starts_with("<pre"));
+/// ```
+pub fn render_frame_html(frame: &Frame) -> String
+{
+    let (term_cols, term_rows) = frame.size();
+    let mut html = String::from("<pre style=\"background-color:#000000\">\n");
+    for row_index in 0..term_rows {
+        for column_index in 0..term_cols {
+            let styled_char = match frame.get(column_index, row_index) {
+                Some(styled_char) => styled_char,
+                None => {
+                    html.push(' ');
+                    continue;
+                }
+            };
+
+            let style = styled_char.style();
+            let mut css = Vec::new();
+            if let Some(rgb) = style.foreground_color.and_then(terminal_color_to_rgb) {
+                css.push(format!("color:#{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b));
+            }
+            if style.attributes.has(Attribute::Bold) {
+                css.push(String::from("font-weight:bold"));
+            }
+
+            html.push_str("<span");
+            if !css.is_empty() {
+                write!(html, " style=\"{}\"", css.join(";")).expect("writing to a String should never fail");
+            }
+            html.push('>');
+            push_escaped(&mut html, *styled_char.content());
+            html.push_str("</span>");
+        }
+        html.push('\n');
+    }
+    html.push_str("</pre>\n");
+    html
+}
+
+// appends c to html, escaped if it has a special meaning in HTML
+fn push_escaped(html: &mut String, c: char)
+{
+    match c {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '"' => html.push_str("&quot;"),
+        '\'' => html.push_str("&#39;"),
+        c => html.push(c)
+    }
+}
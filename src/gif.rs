@@ -0,0 +1,317 @@
+//! Recording animations as animated GIFs
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::Duration;
+use coolor::{Color as CoolorColor, Rgb};
+use ::gif::{Encoder, EncodingError, Repeat};
+use crate::Frame;
+use crate::raindrop::color_depth::{ansi_to_rgb, terminal_color_to_rgb, ColorDepth};
+
+/// The width of a single terminal cell in a GIF, in pixels
+pub const CELL_WIDTH: u16 = 8;
+
+/// The height of a single terminal cell in a GIF, in pixels
+pub const CELL_HEIGHT: u16 = 12;
+
+// the number of blank pixel rows above each 8x8 glyph, centering it in its cell
+const GLYPH_TOP: usize = 2;
+
+// the palette index of the background, which is black in every palette
+const BACKGROUND_INDEX: u8 = 0;
+
+// the color of characters drawn without color, which is the default foreground of xterm
+const DEFAULT_FOREGROUND: Rgb = Rgb{r: 229, g: 229, b: 229};
+
+// 8x8 glyphs for the printable ASCII characters from ' ' to '~', one byte per row from the top,
+// with the least significant bit as the leftmost pixel (from the public domain font8x8 font)
+const FONT: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], //' '
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], //'!'
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], //'"'
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], //'#'
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], //'$'
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], //'%'
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], //'&'
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], //'''
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], //'('
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], //')'
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], //'*'
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], //'+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], //','
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], //'-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], //'.'
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], //'/'
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], //'0'
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], //'1'
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], //'2'
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], //'3'
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], //'4'
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], //'5'
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], //'6'
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], //'7'
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], //'8'
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], //'9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], //':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], //';'
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], //'<'
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], //'='
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], //'>'
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], //'?'
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], //'@'
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], //'A'
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], //'B'
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], //'C'
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], //'D'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], //'E'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], //'F'
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], //'G'
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], //'H'
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //'I'
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], //'J'
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], //'K'
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], //'L'
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], //'M'
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], //'N'
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], //'O'
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], //'P'
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], //'Q'
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], //'R'
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], //'S'
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //'T'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], //'U'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], //'V'
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], //'W'
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], //'X'
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], //'Y'
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], //'Z'
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], //'['
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], //'\'
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], //']'
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], //'^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], //'_'
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], //'`'
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], //'a'
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], //'b'
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], //'c'
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], //'d'
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], //'e'
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], //'f'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], //'g'
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], //'h'
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //'i'
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], //'j'
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], //'k'
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], //'l'
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], //'m'
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], //'n'
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], //'o'
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], //'p'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], //'q'
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], //'r'
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], //'s'
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], //'t'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], //'u'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], //'v'
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], //'w'
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], //'x'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], //'y'
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], //'z'
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], //'{'
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], //'|'
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], //'}'
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]  //'~'
+];
+
+/// Records [Frame]s as the frames of a looping animated GIF
+///
+/// Each terminal cell becomes [CELL_WIDTH] by [CELL_HEIGHT] pixels, with its character drawn
+/// in its foreground color over a black background. Printable ASCII characters are drawn with
+/// a built-in 8x8 bitmap font; as the font has no other characters, any other character
+/// (such as those of the [Katakana](crate::raindrop::charsets::Katakana) charset) is drawn as
+/// a mirrored ASCII character chosen by its code point, much like the rain in the film.
+///
+/// Each frame is written with its own palette of the exact colors in it. A GIF palette holds
+/// at most 256 colors, so a frame with more colors than that (such as one using a rainbow of
+/// colors) is instead drawn with the nearest colors of the xterm 256-color palette.
+///
+/// Frame delays are derived from `frame_interval` rather than the clock, so recordings of
+/// seeded animations are byte-for-byte reproducible. The GIF is finished when the
+/// `GifWriter` is dropped.
+///
+/// # Examples
+/// ```no_run
+/// use std::fs::File;
+/// use std::time::Duration;
+/// use mrs_matrix::AnimationState;
+/// use mrs_matrix::gif::GifWriter;
+/// use mrs_matrix::raindrop::RaindropConfig;
+/// use mrs_matrix::raindrop::color_algorithms::ColorScheme;
+///
+/// pub fn main() -> std::io::Result<()>
+/// {
+///     let charset = "01".chars().collect();
+///     let mut animation_state = AnimationState::new(&charset, ColorScheme::Green, 0.75,
+///         RaindropConfig::default(), Some(42), 80, 24);
+///     let file = File::create("rain.gif")?;
+///     let mut gif = GifWriter::new(file, 80, 24, Duration::from_secs_f64(1.0 / 25.0))?;
+///     for _ in 0..100 {
+///         animation_state.step();
+///         gif.write_frame(animation_state.frame())?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct GifWriter<W: Write> {
+    encoder: Encoder<W>,
+    term_cols: u16,
+    term_rows: u16,
+    frame_interval: Duration,
+
+    // the number of frames written so far, used to time the next one
+    frames_written: u32,
+
+    // the pixels of the frame being drawn, as palette indices; kept to reuse its allocation
+    pixels: Vec<u8>
+}
+
+impl<W: Write> GifWriter<W> {
+
+    /// Returns a new `GifWriter` wrapping `out`, after writing the header of a GIF big enough
+    /// to show `term_cols` columns and `term_rows` rows of cells
+    ///
+    /// `frame_interval` should be the duration of a single frame.
+    ///
+    /// Returns an error of kind [InvalidInput](io::ErrorKind::InvalidInput) if the GIF would be
+    /// wider or taller than the 65535 pixels a GIF can be, or an error writing to `out`.
+    pub fn new(out: W, term_cols: u16, term_rows: u16, frame_interval: Duration) -> io::Result<Self>
+    {
+        let (width, height) = term_cols.checked_mul(CELL_WIDTH)
+            .zip(term_rows.checked_mul(CELL_HEIGHT))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!(
+                "{}x{} cells is too large for a GIF", term_cols, term_rows)))?;
+
+        let mut encoder = Encoder::new(out, width, height, &xterm_palette())
+            .map_err(encoding_error)?;
+        encoder.set_repeat(Repeat::Infinite).map_err(encoding_error)?;
+
+        Ok(Self {
+            encoder,
+            term_cols,
+            term_rows,
+            frame_interval,
+            frames_written: 0,
+            pixels: Vec::with_capacity(usize::from(width) * usize::from(height))
+        })
+    }
+
+    /// Draws `frame` and writes it as the next frame of the GIF
+    ///
+    /// Only the cells within the size given to [new](GifWriter::new) are drawn.
+    pub fn write_frame(&mut self, frame: &Frame) -> io::Result<()>
+    {
+        //find the color of every cell, then give each distinct color an index in the palette
+        let mut cells = Vec::new();
+        let mut palette_indices: HashMap<(u8, u8, u8), u8> = HashMap::new();
+        let mut palette = vec![0, 0, 0];
+        let mut too_many_colors = false;
+        for row_index in 0..self.term_rows {
+            for column_index in 0..self.term_cols {
+                let Some(styled_char) = frame.get(column_index, row_index) else {
+                    continue;
+                };
+                let rgb = styled_char.style().foreground_color
+                    .and_then(terminal_color_to_rgb)
+                    .unwrap_or(DEFAULT_FOREGROUND);
+                let key = (rgb.r, rgb.g, rgb.b);
+                if !too_many_colors && !palette_indices.contains_key(&key) {
+                    match u8::try_from(palette_indices.len() + 1) {
+                        Ok(index) => {
+                            palette_indices.insert(key, index);
+                            palette.extend_from_slice(&[rgb.r, rgb.g, rgb.b]);
+                        },
+                        Err(_) => too_many_colors = true
+                    }
+                }
+                cells.push((column_index, row_index, *styled_char.content(), rgb));
+            }
+        }
+
+        //fall back to the global xterm palette if the frame's colors don't all fit in a palette
+        let palette_index = |rgb: Rgb| if too_many_colors {
+            match ColorDepth::Ansi256.downsample(CoolorColor::Rgb(rgb)) {
+                CoolorColor::Ansi(ansi) => ansi.code,
+                _ => unreachable!("colors are downsampled to palette colors")
+            }
+        } else {
+            palette_indices[&(rgb.r, rgb.g, rgb.b)]
+        };
+
+        let (width, height) = (self.term_cols * CELL_WIDTH, self.term_rows * CELL_HEIGHT);
+        self.pixels.clear();
+        self.pixels.resize(usize::from(width) * usize::from(height), BACKGROUND_INDEX);
+        for (column_index, row_index, c, rgb) in cells {
+            let color_index = palette_index(rgb);
+            let left = usize::from(column_index * CELL_WIDTH);
+            let top = usize::from(row_index * CELL_HEIGHT) + GLYPH_TOP;
+            for (glyph_row, bits) in glyph(c).into_iter().enumerate() {
+                let row_start = (top + glyph_row) * usize::from(width) + left;
+                for glyph_column in 0..8 {
+                    if bits & (1 << glyph_column) != 0 {
+                        self.pixels[row_start + glyph_column] = color_index;
+                    }
+                }
+            }
+        }
+
+        let gif_frame = ::gif::Frame {
+            width,
+            height,
+            delay: self.next_delay(),
+            palette: if too_many_colors {None} else {Some(palette)},
+            buffer: Cow::Borrowed(&self.pixels),
+            ..::gif::Frame::default()
+        };
+        self.encoder.write_frame(&gif_frame).map_err(encoding_error)?;
+        self.frames_written = self.frames_written.saturating_add(1);
+        Ok(())
+    }
+
+    // returns the delay of the next frame in hundredths of a second (the unit of GIF delays);
+    // each delay is rounded so that the total delay stays as close as possible to the
+    // total time the frames should take, rather than accumulating rounding errors
+    fn next_delay(&self) -> u16
+    {
+        let centiseconds = |frames: u32| {
+            (self.frame_interval.as_secs_f64() * f64::from(frames) * 100.0).round()
+        };
+        let delay = centiseconds(self.frames_written + 1) - centiseconds(self.frames_written);
+        delay.clamp(0.0, f64::from(u16::MAX)) as u16
+    }
+}
+
+// returns the 8x8 glyph of c, with characters the font doesn't have drawn as a mirrored
+// printable, non-space ASCII character chosen by code point
+fn glyph(c: char) -> [u8; 8]
+{
+    match c {
+        ' '..='~' => FONT[c as usize - 0x20],
+        _ => FONT[c as usize % (FONT.len() - 1) + 1].map(u8::reverse_bits)
+    }
+}
+
+// returns the xterm 256-color palette as the rgb bytes of each color in turn
+fn xterm_palette() -> Vec<u8>
+{
+    (0..=255).map(ansi_to_rgb).flat_map(|rgb| [rgb.r, rgb.g, rgb.b]).collect()
+}
+
+// returns an error encoding a GIF as an io::Error
+fn encoding_error(error: EncodingError) -> io::Error
+{
+    match error {
+        EncodingError::Io(error) => error,
+        error => io::Error::other(error)
+    }
+}
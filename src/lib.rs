@@ -3,6 +3,24 @@
 //! This library was created for use in the standalone `mrs_matrix` binary 
 //! project, but can be freely used for other purposes as desired.
 pub mod animation;
-pub use animation::anim_loop;
+pub use animation::{AnimationState, anim_loop, anim_loop_dyn, anim_loop_with_writer, render_frame, render_frame_ansi, Frame, LoopOptions};
 
-pub mod raindrop;
\ No newline at end of file
+pub mod raindrop;
+
+pub mod backend;
+
+pub mod error;
+pub use error::MatrixError;
+
+pub mod builder;
+pub use builder::MatrixBuilder;
+
+pub mod cast;
+
+pub mod decode;
+
+#[cfg(feature = "gif")]
+pub mod gif;
+
+#[cfg(feature = "html")]
+pub mod export;
@@ -1,13 +1,63 @@
-use mrs_matrix::anim_loop;
+use mrs_matrix::{MatrixBuilder, MatrixError};
+use mrs_matrix::animation::terminal_size;
+use mrs_matrix::cast::CastWriter;
 use mrs_matrix::raindrop::charsets::Charset;
-use mrs_matrix::raindrop::{charsets, color_algorithms};
-use clap::{ArgEnum, ArgGroup, Parser};
+use mrs_matrix::raindrop::color_algorithms::{ColorAlgorithm, ColorScheme};
+use mrs_matrix::raindrop::color_depth::ColorDepth;
+use mrs_matrix::raindrop::{charsets, color_algorithms, Direction, FollowerBold, RaindropConfig, RenderConfig};
+use clap::{ArgAction, ArgEnum, CommandFactory, ErrorKind, Parser};
+use crossterm::terminal;
+use coolor::{Color, Hsl, Rgb};
+use std::fs::File;
+#[cfg(feature = "gif")]
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::Duration;
+#[cfg(feature = "config")]
+use std::{ffi::OsString, fs, path::Path};
+#[cfg(feature = "config")]
+use clap::{Arg, FromArgMatches, ValueSource};
+#[cfg(feature = "config")]
+use serde::Deserialize;
+
+// the colors --color can be set to by name, as in cmatrix; only their hue and saturation are used,
+// except by the solid color mode
+const NAMED_COLORS: [(&str, Hsl); 7] = [
+    ("green", Hsl{h: 120.0, s: 1.0, l: 0.5}),
+    ("red", Hsl{h: 0.0, s: 1.0, l: 0.5}),
+    ("blue", Hsl{h: 240.0, s: 1.0, l: 0.5}),
+    ("white", Hsl{h: 0.0, s: 0.0, l: 1.0}),
+    ("yellow", Hsl{h: 60.0, s: 1.0, l: 0.5}),
+    ("cyan", Hsl{h: 180.0, s: 1.0, l: 0.5}),
+    ("magenta", Hsl{h: 300.0, s: 1.0, l: 0.5})
+];
+
+// the framerate of an --update delay of 0, which cmatrix runs without waiting between frames at all
+const UPDATE_ZERO_FRAMERATE: usize = 200;
 
 #[derive(Debug, Clone, Copy, ArgEnum)]
 enum CharsetType {
     Alphanumeric,
+    #[clap(alias = "ascii")]
     PrintableAscii,
-    AsciiAndSymbols
+    AsciiAndSymbols,
+    Katakana,
+    Matrix,
+    Braille
+}
+
+impl CharsetType {
+    fn charset(self) -> Box<dyn Charset>
+    {
+        match self {
+            CharsetType::Alphanumeric => Box::new(charsets::Alphanumeric()),
+            CharsetType::PrintableAscii => Box::new(charsets::PrintableAscii()),
+            CharsetType::AsciiAndSymbols => Box::new(charsets::AsciiAndSymbols()),
+            CharsetType::Katakana => Box::new(charsets::Katakana()),
+            CharsetType::Matrix => Box::new(charsets::Weighted::matrix()),
+            CharsetType::Braille => Box::new(charsets::Braille())
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, ArgEnum)]
@@ -17,109 +67,648 @@ enum ColorMode {
     Purple,
     Red,
     Yellow,
-    Rainbow
+    Rainbow,
+    RandomPerDrop,
+    Solid,
+    RotatingRainbow
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum DirectionMode {
+    Down,
+    Up,
+    Left,
+    Right
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum ColorDepthMode {
+    Truecolor,
+    #[clap(name = "256")]
+    Ansi256,
+    #[clap(name = "16")]
+    Ansi16
 }
 
 #[derive(Debug, Parser)]
 #[clap(version, about, long_about = None)]
-#[clap(group(
-    ArgGroup::new("charsetgroup")
-    .args(&["charset", "custom-charset"]),
-))]
+#[clap(args_override_self = true)]
 struct Args {
    
     /// Defines how characters will be colored.
     #[clap(short, long, arg_enum, value_parser, default_value_t = ColorMode::Green)]
     color_mode: ColorMode,
 
-    /// Defines the character set that will be drawn from.
-    #[clap(long, arg_enum, value_parser, default_value_t = CharsetType::AsciiAndSymbols)]
-    charset: CharsetType,
+    /// Defines the character set that will be drawn from; several can be combined, separated by commas.
+    #[clap(long, arg_enum, value_parser, value_delimiter = ',', default_value = "ascii-and-symbols")]
+    charset: Vec<CharsetType>,
 
     /// Run in synchronized scrolling mode
-    #[clap(short, long)]
+    // like every flag, this can be given a value (as --sync-scrolling=false), so that the command line 
+    // can turn off a flag that a config file turns on
+    #[clap(short, long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
     sync_scrolling: bool,
 
     /// Sets the target framerate
     #[clap(short, long, value_parser=framerate_in_range, default_value_t = 25)]
     framerate: usize,
 
+    /// Sets the framerate from a cmatrix update delay (0 to 10), in hundredths of a second per frame
+    #[clap(short, long, value_parser=clap::value_parser!(u8).range(0..=10), conflicts_with = "framerate")]
+    update: Option<u8>,
+
     /// Custom character set passed as a string
-    #[clap(long)]
-    custom_charset: Option<String>
+    #[clap(long, conflicts_with_all = &["charset", "charsets"])]
+    custom_charset: Option<String>,
+
+    /// Character sets for each raindrop to pick one of whenever it starts falling, separated by commas
+    #[clap(long, arg_enum, value_parser, value_delimiter = ',', conflicts_with = "charset")]
+    charsets: Vec<CharsetType>,
+
+    /// Color (as #rrggbb) whose hue fades along each trail; overrides color mode, except solid, where it colors every follower char
+    #[clap(long, value_parser=parse_hex_color)]
+    color_hex: Option<Hsl>,
+
+    /// Color (as r,g,b from 0 to 255) to use like --color-hex
+    #[clap(long, value_parser=parse_rgb_color, conflicts_with="color-hex")]
+    color_rgb: Option<Hsl>,
+
+    /// Color (by name, as in cmatrix: green, red, blue, white, yellow, cyan, or magenta) to use like --color-hex
+    #[clap(short = 'C', long, value_parser=parse_named_color, conflicts_with_all = &["color-hex", "color-rgb"])]
+    color: Option<Hsl>,
+
+    /// Color (as #rrggbb) of follower chars closest to the leader; overrides color mode
+    #[clap(long, value_parser=parse_hex_color, requires="color-hex-to")]
+    color_hex_from: Option<Hsl>,
+
+    /// Color (as #rrggbb) of follower chars furthest from the leader; overrides color mode
+    #[clap(long, value_parser=parse_hex_color, requires="color-hex-from")]
+    color_hex_to: Option<Hsl>,
+
+    /// Comma separated colors (as #rrggbb) of bands from the leader to the tail; overrides color mode
+    #[clap(long, value_parser=parse_hex_color, value_delimiter=',', conflicts_with="color-hex-from")]
+    palette: Option<Vec<Hsl>>,
+
+    /// Color (as #rrggbb) of the leading char of each raindrop [default: white]
+    #[clap(long, value_parser=parse_hex_color)]
+    leader_hex: Option<Hsl>,
+
+    /// Curves how quickly trails fade with single-color modes and --color-hex; above 1.0, they stay bright for longer
+    #[clap(long, value_parser=falloff_in_range, default_value_t = 1.0)]
+    falloff: f32,
+
+    /// Sets the shortest length a raindrop's trail can be
+    #[clap(long, value_parser=min_length_in_range, default_value_t = RaindropConfig::default().follower_min_length)]
+    min_length: u16,
+
+    /// Sets the longest length a raindrop's trail can be, as an offset from the terminal height
+    #[clap(long, value_parser, default_value_t = RaindropConfig::default().follower_max_length_offset)]
+    max_length_offset: u16,
+
+    /// Defines the direction raindrops fall in
+    #[clap(long, arg_enum, value_parser, default_value_t = DirectionMode::Down)]
+    direction: DirectionMode,
+
+    /// Gives each raindrop its own speed, rather than all moving at the same rate
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    speed_variation: bool,
+
+    /// Scales how fast the rain falls without changing the framerate (e.g. 0.5 moves it every other frame)
+    #[clap(long, value_parser=fall_speed_in_range, default_value_t = 1.0)]
+    fall_speed: f64,
+
+    /// Sets the chance (from 0.0 to 1.0) that a raindrop's trail flickers on any given frame
+    #[clap(long, value_parser=glitch_rate_in_range, default_value_t = RaindropConfig::default().glitch_rate)]
+    glitch: f32,
+
+    /// Sets roughly the proportion (from 0.0 to 1.0) of columns that hold a raindrop at any one time
+    #[clap(long, value_parser=density_in_range, default_value_t = RaindropConfig::default().density)]
+    density: f32,
+
+    /// Sets the most raindrops that can share a column at once
+    #[clap(long, value_parser=clap::value_parser!(u8).range(1..), default_value_t = RaindropConfig::default().drops_per_lane)]
+    drops_per_column: u8,
+
+    /// Sets the number of frames each raindrop's leading char is shown for before it changes
+    #[clap(long, value_parser=clap::value_parser!(u32).range(1..), default_value_t = RaindropConfig::default().leader_refresh_frames)]
+    leader_refresh: u32,
+
+    /// Keeps the leading char of each raindrop the same for the whole of its fall
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    static_leader: bool,
+
+    /// Draws the leading char of each raindrop without bold
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    no_bold: bool,
+
+    /// Draws about half of the chars bold, picked afresh every frame, like cmatrix -b
+    #[clap(short = 'b', long, conflicts_with_all = &["all-bold", "no-bold"], action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    random_bold: bool,
+
+    /// Draws every char bold, like cmatrix -B
+    #[clap(short = 'B', long, conflicts_with = "no-bold", action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    all_bold: bool,
+
+    /// Draws every char without color, as when the NO_COLOR environment variable is set
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    no_color: bool,
+
+    /// Scales the lightness of every char, from 0.0 to 1.0; leaders never fade out entirely
+    #[clap(long, value_parser=brightness_in_range, default_value_t = RenderConfig::default().brightness)]
+    brightness: f32,
+
+    /// Darkens chars (from 0.0 to 1.0) the further they are from the center of the screen
+    #[clap(long, value_parser=vignette_in_range, default_value_t = RenderConfig::default().vignette)]
+    vignette: f32,
+
+    /// Dims every other row, like the scanlines of a CRT
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    scanlines: bool,
+
+    /// Lightens this many follower chars right behind each leader, as if the leader were glowing
+    #[clap(long, value_parser, default_value_t = RenderConfig::default().glow)]
+    glow: u8,
+
+    /// Leans the rain sideways by this many columns per row (from -1.0 to 1.0), gusting slowly
+    #[clap(long, value_parser=wind_in_range, default_value_t = RenderConfig::default().wind, allow_hyphen_values = true)]
+    wind: f32,
+
+    /// Number of colors the terminal supports [default: detected from COLORTERM and TERM]
+    #[clap(long, arg_enum, value_parser)]
+    color_depth: Option<ColorDepthMode>,
+
+    /// Draws this text over the middle of the screen, in front of the rain
+    #[clap(long, value_parser)]
+    message: Option<String>,
+
+    /// Draws scrambled chars over the middle of the screen that settle into this text one at a time
+    #[clap(long, value_parser, conflicts_with = "message")]
+    decode: Option<String>,
+
+    /// Sets the chance (from 0.0 to 1.0) that each scrambled char of --decode settles on any given frame
+    #[clap(long, value_parser=decode_rate_in_range, default_value_t = 0.05, requires = "decode")]
+    decode_rate: f64,
+
+    /// Draws the first frame and waits for Space before starting; --duration counts from then
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    start_paused: bool,
+
+    /// Fades the screen to black on exit, rather than clearing it straight away
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    fade_out: bool,
+
+    /// Seeds the random number generator, making the animation reproducible
+    #[clap(long, value_parser)]
+    seed: Option<u64>,
 
+    /// Exits after running for this many seconds
+    #[clap(long, value_parser=parse_duration)]
+    duration: Option<Duration>,
+
+    /// Exits after drawing this many frames
+    #[clap(long, value_parser=clap::value_parser!(u64).range(1..))]
+    frames: Option<u64>,
+
+    /// Prints a single frame to stdout and exits, without taking over the terminal
+    #[clap(long, action = ArgAction::Set, value_parser, default_value_t = false, default_missing_value = "true", min_values = 0, require_equals = true, value_name = "BOOL", hide_default_value = true, hide_possible_values = true)]
+    oneshot: bool,
+
+    /// Records the animation to an asciinema cast file at this path instead of drawing it
+    #[clap(long, value_parser, conflicts_with = "oneshot")]
+    record: Option<PathBuf>,
+
+    /// Records the animation to an animated GIF at this path instead of drawing it
+    /// (requires --frames or --duration)
+    #[cfg(feature = "gif")]
+    #[clap(long, value_parser, conflicts_with_all = &["oneshot", "record"])]
+    gif: Option<PathBuf>,
+
+    /// Draws this many columns wide, regardless of the terminal size (requires --height)
+    #[clap(long, requires = "height", value_parser=clap::value_parser!(u16).range(1..))]
+    width: Option<u16>,
+
+    /// Draws this many rows high, regardless of the terminal size (requires --width)
+    #[clap(long, requires = "width", value_parser=clap::value_parser!(u16).range(1..))]
+    height: Option<u16>,
+
+    /// Loads options from a TOML file; options given on the command line take priority
+    #[cfg(feature = "config")]
+    #[clap(long, value_parser)]
+    config: Option<PathBuf>
+
+}
+
+/// Options loaded from a TOML file with `--config`
+/// 
+/// Each field has the same name, values, and meaning as the command line option it mirrors,
+/// and any field that is left out keeps its usual default.
+#[cfg(feature = "config")]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct MatrixConfig {
+    color_mode: Option<String>,
+    charset: Option<String>,
+    sync_scrolling: Option<bool>,
+    framerate: Option<usize>,
+    update: Option<u8>,
+    custom_charset: Option<String>,
+    charsets: Option<String>,
+    color_hex: Option<String>,
+    color_rgb: Option<String>,
+    color: Option<String>,
+    color_hex_from: Option<String>,
+    color_hex_to: Option<String>,
+    palette: Option<String>,
+    leader_hex: Option<String>,
+    falloff: Option<f32>,
+    min_length: Option<u16>,
+    max_length_offset: Option<u16>,
+    direction: Option<String>,
+    speed_variation: Option<bool>,
+    fall_speed: Option<f64>,
+    glitch: Option<f32>,
+    density: Option<f32>,
+    drops_per_column: Option<u8>,
+    leader_refresh: Option<u32>,
+    static_leader: Option<bool>,
+    no_bold: Option<bool>,
+    random_bold: Option<bool>,
+    all_bold: Option<bool>,
+    no_color: Option<bool>,
+    brightness: Option<f32>,
+    vignette: Option<f32>,
+    scanlines: Option<bool>,
+    glow: Option<u8>,
+    wind: Option<f32>,
+    color_depth: Option<String>,
+    message: Option<String>,
+    decode: Option<String>,
+    decode_rate: Option<f64>,
+    start_paused: Option<bool>,
+    fade_out: Option<bool>,
+    seed: Option<u64>,
+    duration: Option<f64>,
+    frames: Option<u64>,
+    oneshot: Option<bool>,
+    record: Option<String>,
+    #[cfg(feature = "gif")]
+    gif: Option<String>,
+    width: Option<u16>,
+    height: Option<u16>
+}
+
+#[cfg(feature = "config")]
+impl MatrixConfig {
+    /// Reads a config from the TOML file at `path`
+    fn load(path: &Path) -> Result<Self, String>
+    {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("couldn't read config file {}: {}", path.display(), error))?;
+        toml::from_str(&contents)
+            .map_err(|error| format!("couldn't parse config file {}: {}", path.display(), error))
+    }
+
+    /// Returns the command line arguments that set the same options as this config
+    fn to_args(&self) -> Vec<String>
+    {
+        let mut args = Vec::new();
+
+        //values are joined to their option with '=', so values starting with '-' aren't taken as options
+        let mut option = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                args.push(format!("--{}={}", name, value));
+            }
+        };
+        option("color-mode", self.color_mode.clone());
+        option("charset", self.charset.clone());
+        option("framerate", self.framerate.map(|framerate| framerate.to_string()));
+        option("update", self.update.map(|update| update.to_string()));
+        option("custom-charset", self.custom_charset.clone());
+        option("charsets", self.charsets.clone());
+        option("color-hex", self.color_hex.clone());
+        option("color-rgb", self.color_rgb.clone());
+        option("color", self.color.clone());
+        option("color-hex-from", self.color_hex_from.clone());
+        option("color-hex-to", self.color_hex_to.clone());
+        option("palette", self.palette.clone());
+        option("leader-hex", self.leader_hex.clone());
+        option("falloff", self.falloff.map(|falloff| falloff.to_string()));
+        option("min-length", self.min_length.map(|min_length| min_length.to_string()));
+        option("max-length-offset", self.max_length_offset.map(|offset| offset.to_string()));
+        option("direction", self.direction.clone());
+        option("fall-speed", self.fall_speed.map(|speed| speed.to_string()));
+        option("glitch", self.glitch.map(|glitch| glitch.to_string()));
+        option("density", self.density.map(|density| density.to_string()));
+        option("drops-per-column", self.drops_per_column.map(|drops| drops.to_string()));
+        option("leader-refresh", self.leader_refresh.map(|frames| frames.to_string()));
+        option("brightness", self.brightness.map(|brightness| brightness.to_string()));
+        option("vignette", self.vignette.map(|vignette| vignette.to_string()));
+        option("glow", self.glow.map(|glow| glow.to_string()));
+        option("wind", self.wind.map(|wind| wind.to_string()));
+        option("color-depth", self.color_depth.clone());
+        option("message", self.message.clone());
+        option("decode", self.decode.clone());
+        option("decode-rate", self.decode_rate.map(|rate| rate.to_string()));
+        option("seed", self.seed.map(|seed| seed.to_string()));
+        option("duration", self.duration.map(|duration| duration.to_string()));
+        option("frames", self.frames.map(|frames| frames.to_string()));
+        option("record", self.record.clone());
+        #[cfg(feature = "gif")]
+        option("gif", self.gif.clone());
+        option("width", self.width.map(|width| width.to_string()));
+        option("height", self.height.map(|height| height.to_string()));
+
+        //flags are only passed when turned on, as every flag is already off by default
+        let flags = [
+            ("sync-scrolling", self.sync_scrolling),
+            ("speed-variation", self.speed_variation),
+            ("static-leader", self.static_leader),
+            ("no-bold", self.no_bold),
+            ("random-bold", self.random_bold),
+            ("all-bold", self.all_bold),
+            ("no-color", self.no_color),
+            ("scanlines", self.scanlines),
+            ("start-paused", self.start_paused),
+            ("fade-out", self.fade_out),
+            ("oneshot", self.oneshot)
+        ];
+        for (name, value) in flags {
+            if value == Some(true) {
+                args.push(format!("--{}", name));
+            }
+        }
+
+        args
+    }
+}
+
+/// Parses the command line arguments, filling in any options they leave out from the `--config` file
+#[cfg(feature = "config")]
+fn parse_args() -> Args
+{
+    let command = Args::command();
+    let matches = command.clone().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    let config_path = match &args.config {
+        Some(config_path) => config_path,
+        None => return args
+    };
+    let config = MatrixConfig::load(config_path)
+        .unwrap_or_else(|error| Args::command().error(ErrorKind::Io, error).exit());
+
+    //the config's options are parsed on their own, so they're checked just like the command line's
+    let program = std::env::args_os().next().unwrap_or_else(|| OsString::from("mrs-matrix"));
+    let config_matches = command.clone()
+        .try_get_matches_from(std::iter::once(program).chain(config.to_args().into_iter().map(OsString::from)))
+        .unwrap_or_else(|error| error.exit());
+    let config_args = Args::from_arg_matches(&config_matches).unwrap_or_else(|error| error.exit());
+
+    //an option is taken from the config unless the command line gives it, 
+    //or gives another option that it conflicts with
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let from_config = |id: &str| {
+        let arg = command.get_arguments().find(|arg| arg.get_id() == id)
+            .expect("every field of Args has an argument of the same name");
+        let conflicts_with = |arg: &Arg, other: &Arg| command.get_arg_conflicts_with(arg).iter()
+            .any(|conflict| conflict.get_id() == other.get_id());
+        let conflicts = |other: &Arg| conflicts_with(arg, other) || conflicts_with(other, arg);
+        config_matches.value_source(id) == Some(ValueSource::CommandLine) 
+            && !given(id)
+            && !command.get_arguments().any(|other| given(other.get_id()) && conflicts(other))
+    };
+    macro_rules! merge {
+        ($($field:ident),*) => {$(
+            if from_config(&stringify!($field).replace('_', "-")) {
+                args.$field = config_args.$field;
+            }
+        )*};
+    }
+    merge!(color_mode, charset, sync_scrolling, framerate, update, custom_charset, charsets, color_hex, 
+        color_rgb, color, color_hex_from, color_hex_to, palette, leader_hex, falloff, min_length, 
+        max_length_offset, direction, speed_variation, fall_speed, glitch, density, drops_per_column, 
+        leader_refresh, static_leader, no_bold, random_bold, all_bold, no_color, brightness, vignette, 
+        scanlines, glow, wind, color_depth, message, decode, decode_rate, start_paused, fade_out, seed, 
+        duration, frames, oneshot, record, width, height);
+    #[cfg(feature = "gif")]
+    merge!(gif);
+
+    args
+}
+
+/// Parses the command line arguments
+#[cfg(not(feature = "config"))]
+fn parse_args() -> Args
+{
+    Args::parse()
 }
 
-fn main() -> crossterm::Result<()> 
+fn main() -> Result<(), MatrixError> 
 {
-    let args = Args::parse();
+    let args = parse_args();
 
     let advance_chance = if args.sync_scrolling {1.0} else {0.75};
-    let target_framerate = args.framerate;
+    let target_framerate = args.update.map_or(args.framerate, update_delay_framerate);
+
+    let raindrop_config = RaindropConfig{
+        follower_min_length: args.min_length,
+        follower_max_length_offset: args.max_length_offset,
+        direction: match args.direction {
+            DirectionMode::Down => Direction::Down,
+            DirectionMode::Up => Direction::Up,
+            DirectionMode::Left => Direction::Left,
+            DirectionMode::Right => Direction::Right
+        },
+        speed_variation: args.speed_variation,
+        glitch_rate: args.glitch,
+        density: args.density,
+        drops_per_lane: args.drops_per_column,
+        leader_refresh_frames: args.leader_refresh,
+        static_leader: args.static_leader
+    };
+
+    //make sure the requested trail lengths make sense for the current terminal
+    //(raindrops moving sideways are limited by the terminal's width rather than its height)
+    //terminals too small for even the default lengths, like a tiny tmux pane, are let through;
+    //trails longer than the terminal are simply cut off at its edge
+    let fixed_size = args.width.zip(args.height);
+    let term_size = match fixed_size {
+        Some(fixed_size) => Ok(fixed_size),
+        None => terminal::size()
+    };
+    if let Ok((term_cols, term_rows)) = term_size {
+        let (_, lane_length) = raindrop_config.direction.lane_dimensions(term_cols, term_rows);
+        if RaindropConfig::default().fits_terminal_height(lane_length) 
+            && !raindrop_config.fits_terminal_height(lane_length) 
+        {
+            let dimension = if raindrop_config.direction.is_horizontal() {"width"} else {"height"};
+            Args::command().error(ErrorKind::ValueValidation, format!(
+                "min length {} doesn't fit in a terminal of {} {} with max length offset {}",
+                args.min_length, dimension, lane_length, args.max_length_offset)).exit();
+        }
+    }
 
-    let charset = if args.custom_charset == None {
-        match args.charset {
-            CharsetType::Alphanumeric => charsets::Alphanumeric().get_charset(),
-            CharsetType::PrintableAscii => charsets::PrintableAscii().get_charset(),
-            CharsetType::AsciiAndSymbols => charsets::AsciiAndSymbols().get_charset(),
+    //the rain draws from one of --charsets at a time, but a decode effect scrambles through all of them
+    let (charset, charset_pool) = if !args.charsets.is_empty() {
+        let charset_pool = args.charsets.iter()
+            .map(|charset_type| charset_type.charset().sampler())
+            .collect();
+        let charset = charsets::Composite(args.charsets.iter().map(|charset_type| charset_type.charset()).collect())
+            .get_charset();
+        (charset, charset_pool)
+    } else if args.custom_charset == None {
+        //a single charset is drawn from through its sampler, keeping any weighting it has
+        match args.charset[..] {
+            [charset_type] => {
+                let charset = charset_type.charset();
+                (charset.get_charset(), vec![charset.sampler()])
+            },
+            _ => (charsets::Composite(args.charset.iter().map(|charset_type| charset_type.charset()).collect())
+                .get_charset(), Vec::new())
         }
     } else {
-        args.custom_charset.unwrap().chars().collect()
+        (charsets::Custom::from(args.custom_charset.unwrap().as_str()).get_charset(), Vec::new())
+    };
+
+    //a gradient between two custom colors (or a custom palette) takes priority over the color mode,
+    //followed by a single custom color
+    let custom_color = args.color_hex.or(args.color_rgb).or(args.color);
+    let color_algorithm: Box<dyn ColorAlgorithm> = match (args.color_hex_from, args.color_hex_to, args.palette) {
+        (Some(head), Some(tail), _) => Box::new(color_algorithms::Gradient::new(head, tail)?),
+        (_, _, Some(colors)) => Box::new(color_algorithms::Palette::new(colors)?),
+
+        _ => match (args.color_mode, custom_color) {
+            (ColorMode::Solid, Some(color)) => Box::new(color_algorithms::Solid::new(color)?),
+            (ColorMode::Solid, None) => Args::command().error(ErrorKind::MissingRequiredArgument, 
+                "the solid color mode requires --color-hex, --color-rgb, or --color").exit(),
+            //in any other mode, a custom color fades along the trail like the preset colors
+            (_, Some(color)) => Box::new(color_algorithms::LightnessDescending::new(color.h, color.s)
+                .and_then(|lightness_descending| lightness_descending.with_exponent(args.falloff))
+                .expect("colors converted from rgb and falloffs checked when parsed are in range")),
+            (ColorMode::Green, None) => scheme_with_falloff(ColorScheme::Green, args.falloff),
+            (ColorMode::Blue, None) => scheme_with_falloff(ColorScheme::Blue, args.falloff),
+            (ColorMode::Purple, None) => scheme_with_falloff(ColorScheme::Purple, args.falloff),
+            (ColorMode::Red, None) => scheme_with_falloff(ColorScheme::Red, args.falloff),
+            (ColorMode::Yellow, None) => scheme_with_falloff(ColorScheme::Yellow, args.falloff),
+            (ColorMode::Rainbow, None) => Box::new(ColorScheme::Rainbow),
+            (ColorMode::RandomPerDrop, None) => Box::new(color_algorithms::RandomPerDrop::default()),
+            (ColorMode::RotatingRainbow, None) => Box::new(color_algorithms::RotatingRainbow::default())
+        }
     };
 
-    //we need a seperate call to anim_loop for each possible type of ColorAlgorithm
-    //to avoid this, we would need to use a trait object (like Box<dyn ColorAlgorithm>),
-    //but that would incur a runtime penalty that we could like to avoid
-    
-    match args.color_mode {
-        ColorMode::Green => {
-            let color_algorithm = color_algorithms::LightnessDescending{
-                hue: 118.0,
-                saturation: 1.0
-            };
-            anim_loop(charset, color_algorithm, advance_chance, target_framerate)
+    //a GIF isn't limited to the colors of the terminal it's recorded in
+    #[cfg(feature = "gif")]
+    let default_color_depth = if args.gif.is_some() {ColorDepth::TrueColor} else {ColorDepth::detect()};
+    #[cfg(not(feature = "gif"))]
+    let default_color_depth = ColorDepth::detect();
+
+    let mut render_config = RenderConfig{
+        bold_leader: !args.no_bold, 
+        use_color: !args.no_color, 
+        brightness: args.brightness,
+        vignette: args.vignette,
+        scanlines: args.scanlines,
+        glow: args.glow,
+        wind: args.wind,
+        follower_bold: match (args.random_bold, args.all_bold) {
+            (true, _) => FollowerBold::Random,
+            (_, true) => FollowerBold::All,
+            _ => FollowerBold::None
         },
-        
-        ColorMode::Blue => {
-            let color_algorithm = color_algorithms::LightnessDescending{
-                hue: 244.0,
-                saturation: 1.0
-            };
-            anim_loop(charset, color_algorithm, advance_chance, target_framerate)
+        color_depth: match args.color_depth {
+            Some(ColorDepthMode::Truecolor) => ColorDepth::TrueColor,
+            Some(ColorDepthMode::Ansi256) => ColorDepth::Ansi256,
+            Some(ColorDepthMode::Ansi16) => ColorDepth::Ansi16,
+            None => default_color_depth
         },
+        ..RenderConfig::default()
+    };
+    if let Some(leader_color) = args.leader_hex {
+        render_config.leader_color = Color::Hsl(leader_color);
+    }
 
-        ColorMode::Purple => {
-            let color_algorithm = color_algorithms::LightnessDescending{
-                hue: 302.0,
-                saturation: 1.0
-            };
-            anim_loop(charset, color_algorithm, advance_chance, target_framerate)
-        },
+    //the cost of dynamic dispatch is negligible next to the terminal I/O, 
+    //and it means we only need a single builder for every color algorithm
+    let mut builder = MatrixBuilder::new()
+        .charset(charset)
+        .charsets(charset_pool)
+        .color(color_algorithm)
+        .advance_chance(advance_chance)
+        .framerate(target_framerate)
+        .raindrop_config(raindrop_config)
+        .render_config(render_config)
+        .fall_speed(args.fall_speed)
+        .start_paused(args.start_paused)
+        .fade_out(args.fade_out);
 
-        ColorMode::Red => {
-            let color_algorithm = color_algorithms::LightnessDescending{
-                hue: 0.0,
-                saturation: 1.0
-            };
-            anim_loop(charset, color_algorithm, advance_chance, target_framerate)
-        },
+    if let Some(message) = args.message {
+        builder = builder.message(message);
+    }
+    if let Some(decode) = args.decode {
+        builder = builder.decode(decode, args.decode_rate);
+    }
+    if let Some(seed) = args.seed {
+        builder = builder.seed(seed);
+    }
+    if let Some(duration) = args.duration {
+        builder = builder.duration(duration);
+    }
+    if let Some(frames) = args.frames {
+        builder = builder.frames(frames);
+    }
+    if let Some((width, height)) = fixed_size {
+        builder = builder.size(width, height);
+    }
 
-        ColorMode::Yellow => {
-            let color_algorithm = color_algorithms::LightnessDescending{
-                hue: 51.0,
-                saturation: 1.0
-            };
-            anim_loop(charset, color_algorithm, advance_chance, target_framerate)
-        }
+    if args.oneshot {
+        print!("{}", builder.render_oneshot()?);
+        return Ok(());
+    }
 
-        ColorMode::Rainbow => {
-            let color_algorithm = color_algorithms::HueVariation{
-                saturation: 1.0, lightness: 0.5
-            };
-            anim_loop(charset, color_algorithm, advance_chance, target_framerate)
+    #[cfg(feature = "gif")]
+    if let Some(gif_path) = &args.gif {
+        if args.frames.is_none() && args.duration.is_none() {
+            Args::command().error(ErrorKind::MissingRequiredArgument, 
+                "--gif requires --frames or --duration").exit();
         }
+        //a GIF is written as fast as it can be drawn, rather than in real time
+        let gif = BufWriter::new(File::create(gif_path)?);
+        return builder.render_gif(gif);
+    }
+
+    if let Some(record_path) = &args.record {
+        //the cast is drawn at a fixed size, as it can't follow the terminal being resized
+        let (width, height) = fixed_size.unwrap_or_else(terminal_size);
+        let frame_interval = Duration::from_secs_f64(1.0 / target_framerate as f64);
+        let cast = CastWriter::new(File::create(record_path)?, width, height, frame_interval)?;
+        return builder.size(width, height).run_with_writer(cast, false);
+    }
+
+    builder.run()
+}
+
+/// Returns a single-color scheme, with lightness falling off along the curve given by `falloff`
+fn scheme_with_falloff(scheme: ColorScheme, falloff: f32) -> Box<dyn ColorAlgorithm>
+{
+    //the preset scheme is used as is where possible, as its colors are only computed once
+    match scheme.lightness_descending() {
+        Some(lightness_descending) if falloff != 1.0 => Box::new(lightness_descending
+            .with_exponent(falloff).expect("falloff is checked when parsed")),
+        _ => Box::new(scheme)
+    }
+}
+
+/// Returns the framerate matching a cmatrix update `delay`
+/// 
+/// cmatrix waits `delay` hundredths of a second between frames, so the framerate is 
+/// `100 / delay`, rounded; the default cmatrix delay of 4 matches the default framerate of 25.
+/// A delay of 0 runs at [UPDATE_ZERO_FRAMERATE].
+fn update_delay_framerate(delay: u8) -> usize
+{
+    if delay == 0 {
+        UPDATE_ZERO_FRAMERATE
+    } else {
+        (100.0 / f64::from(delay)).round() as usize
     }
-        
 }
 
 /// framerate parser/validator function
@@ -132,4 +721,170 @@ fn framerate_in_range(s: &str) -> Result<usize, String>
     } else {
         Ok(framerate)
     }
+}
+
+/// glitch rate parser/validator function
+fn glitch_rate_in_range(s: &str) -> Result<f32, String>
+{
+    let glitch_rate: f32 = s.parse().map_err(|_| format!("\"{}\" isn't a valid rate", s))?;
+
+    if (0.0..=1.0).contains(&glitch_rate) {
+        Ok(glitch_rate)
+    } else {
+        Err("glitch rate must be between 0.0 and 1.0".to_string())
+    }
+}
+
+/// fall speed parser/validator function
+fn fall_speed_in_range(s: &str) -> Result<f64, String>
+{
+    let fall_speed: f64 = s.parse().map_err(|_| format!("\"{}\" isn't a valid speed", s))?;
+
+    if fall_speed.is_finite() && fall_speed > 0.0 {
+        Ok(fall_speed)
+    } else {
+        Err("fall speed must be greater than 0.0".to_string())
+    }
+}
+
+/// decode rate parser/validator function
+fn decode_rate_in_range(s: &str) -> Result<f64, String>
+{
+    let decode_rate: f64 = s.parse().map_err(|_| format!("\"{}\" isn't a valid rate", s))?;
+
+    if (0.0..=1.0).contains(&decode_rate) {
+        Ok(decode_rate)
+    } else {
+        Err("decode rate must be between 0.0 and 1.0".to_string())
+    }
+}
+
+/// falloff exponent parser/validator function
+fn falloff_in_range(s: &str) -> Result<f32, String>
+{
+    let falloff: f32 = s.parse().map_err(|_| format!("\"{}\" isn't a valid exponent", s))?;
+
+    if falloff.is_finite() && falloff > 0.0 {
+        Ok(falloff)
+    } else {
+        Err("falloff must be greater than 0.0".to_string())
+    }
+}
+
+/// brightness parser/validator function
+fn brightness_in_range(s: &str) -> Result<f32, String>
+{
+    let brightness: f32 = s.parse().map_err(|_| format!("\"{}\" isn't a valid brightness", s))?;
+
+    if (0.0..=1.0).contains(&brightness) {
+        Ok(brightness)
+    } else {
+        Err("brightness must be between 0.0 and 1.0".to_string())
+    }
+}
+
+/// vignette strength parser/validator function
+fn vignette_in_range(s: &str) -> Result<f32, String>
+{
+    let vignette: f32 = s.parse().map_err(|_| format!("\"{}\" isn't a valid strength", s))?;
+
+    if (0.0..=1.0).contains(&vignette) {
+        Ok(vignette)
+    } else {
+        Err("vignette strength must be between 0.0 and 1.0".to_string())
+    }
+}
+
+/// wind amount parser/validator function
+fn wind_in_range(s: &str) -> Result<f32, String>
+{
+    let wind: f32 = s.parse().map_err(|_| format!("\"{}\" isn't a valid amount", s))?;
+
+    if (-1.0..=1.0).contains(&wind) {
+        Ok(wind)
+    } else {
+        Err("wind must be between -1.0 and 1.0".to_string())
+    }
+}
+
+/// density parser/validator function
+fn density_in_range(s: &str) -> Result<f32, String>
+{
+    let density: f32 = s.parse().map_err(|_| format!("\"{}\" isn't a valid density", s))?;
+
+    if (0.0..=1.0).contains(&density) {
+        Ok(density)
+    } else {
+        Err("density must be between 0.0 and 1.0".to_string())
+    }
+}
+
+/// duration parser/validator function
+/// 
+/// accepts a positive number of seconds, which may be fractional
+fn parse_duration(s: &str) -> Result<Duration, String>
+{
+    let seconds: f64 = s.parse().map_err(|_| format!("\"{}\" isn't a valid number of seconds", s))?;
+
+    if seconds.is_finite() && seconds > 0.0 {
+        Ok(Duration::from_secs_f64(seconds))
+    } else {
+        Err("duration must be a positive number of seconds".to_string())
+    }
+}
+
+/// hex color parser/validator function
+/// 
+/// accepts colors in the form `#rrggbb` (the leading `#` is optional)
+fn parse_hex_color(s: &str) -> Result<Hsl, String>
+{
+    let hex = s.strip_prefix('#').unwrap_or(s);
+
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("\"{}\" isn't a valid #rrggbb color", s));
+    }
+
+    //every char is an ascii hex digit, so slicing by byte and parsing can't fail
+    let component = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).unwrap();
+
+    Ok(Rgb{r: component(0), g: component(2), b: component(4)}.to_hsl())
+}
+
+/// r,g,b color parser/validator function
+fn parse_rgb_color(s: &str) -> Result<Hsl, String>
+{
+    let invalid = || format!("\"{}\" isn't a valid r,g,b color", s);
+
+    let components: Vec<u8> = s.split(',')
+        .map(|component| component.trim().parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+
+    match components[..] {
+        [r, g, b] => Ok(Rgb{r, g, b}.to_hsl()),
+        _ => Err(invalid())
+    }
+}
+
+/// color name parser/validator function
+fn parse_named_color(s: &str) -> Result<Hsl, String>
+{
+    NAMED_COLORS.iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+        .map(|(_, color)| *color)
+        .ok_or_else(|| {
+            let names: Vec<&str> = NAMED_COLORS.iter().map(|(name, _)| *name).collect();
+            format!("\"{}\" isn't a known color; valid colors are {}", s, names.join(", "))
+        })
+}
+
+/// min length parser/validator function
+fn min_length_in_range(s: &str) -> Result<u16, String>
+{
+    let min_length: u16 = s.parse().map_err(|_| format!("\"{}\" isn't a valid length", s))?;
+
+    if min_length == 0 {
+        Err("min length must be at least 1".to_string())
+    } else {
+        Ok(min_length)
+    }
 }
\ No newline at end of file
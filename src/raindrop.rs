@@ -1,37 +1,411 @@
 //! Raindrop structure + implementation
 
-use rand::{self, Rng, rngs, seq::SliceRandom};
+use std::ops::RangeInclusive;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
 use crossterm::style::{self, Stylize};
+use coolor::{AnsiColor, Color};
 
-use self::color_algorithms::ColorAlgorithm;
+use self::charsets::CharSampler;
+use self::color_algorithms::{ColorAlgorithm, ColorContext};
+use self::color_depth::ColorDepth;
 
 pub mod charsets;
 pub mod color_algorithms;
+pub mod color_depth;
 
-// shortest length a follower will be
-const FOLLOWER_MIN_LENGTH: u16 = 4;
+// default shortest length a follower will be
+const DEFAULT_FOLLOWER_MIN_LENGTH: u16 = 4;
 
-// the longest follower is the terminal height minus this offset
-const FOLLOWER_MAX_LENGTH_OFFSET: u16 = 4;
+// by default, the longest follower is the terminal height minus this offset
+const DEFAULT_FOLLOWER_MAX_LENGTH_OFFSET: u16 = 4;
 
 // rows will start with a position offset from 0 by a value 
 // that is (pseudo)randomly selected from this range
-const START_OFFSET_RANGE: std::ops::RangeInclusive<i32> = -64..=-1;
+const START_OFFSET_RANGE: RangeInclusive<i32> = -64..=-1;
+
+// with speed variation, raindrops take a (pseudo)randomly selected number of frames
+// from this range to move one row
+const SPEED_VARIATION_RANGE: RangeInclusive<u8> = 1..=3;
+
+// rows that a raindrop can be placed on with set_row_index; this reaches well past the edges of
+// any terminal, while leaving room to move the raindrop and subtract its follower length without overflow
+const SET_ROW_INDEX_RANGE: RangeInclusive<i32> = -(u16::MAX as i32)..=(u16::MAX as i32);
+
+// however low the brightness is set, leaders keep at least this proportion of their lightness,
+// so the rain never disappears entirely
+const MIN_LEADER_BRIGHTNESS: f32 = 0.2;
+
+// with scanlines, chars on every other row keep this proportion of their lightness
+const SCANLINE_BRIGHTNESS: f32 = 0.6;
+
+// the number of frames the lean of the rain takes to gust and settle again, with wind
+const WIND_PERIOD: f32 = 250.0;
+
+// the storage for follower chars; with the smallvec feature, followers of up to 32 chars
+// are kept inline rather than on the heap, so most raindrops respawn without allocating
+#[cfg(feature = "smallvec")]
+type FollowerContent = smallvec::SmallVec<[char; 32]>;
+#[cfg(not(feature = "smallvec"))]
+type FollowerContent = Vec<char>;
+
+/// The direction that a `Raindrop` falls in
+/// 
+/// Each `Raindrop` falls along a single 'lane': a column of the terminal for vertical 
+/// directions, or a row for horizontal ones. A `Raindrop` always tracks its position as 
+/// a distance from the edge of the terminal it enters from; the direction only changes
+/// which edge that is, and which way lanes run, when it's drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// Falls from the top of the terminal to the bottom
+    #[default]
+    Down,
+    /// Rises from the bottom of the terminal to the top
+    Up,
+    /// Streams from the right of the terminal to the left
+    Left,
+    /// Streams from the left of the terminal to the right
+    Right
+}
+
+impl Direction {
+
+    /// Returns `true` if lanes run along the rows of the terminal rather than its columns
+    pub fn is_horizontal(&self) -> bool
+    {
+        matches!(self, Direction::Left | Direction::Right)
+    }
+
+    /// Returns `true` if raindrops enter from the bottom or right edge of the terminal
+    pub fn is_reversed(&self) -> bool
+    {
+        matches!(self, Direction::Up | Direction::Left)
+    }
+
+    /// Returns the number of lanes and the length of each lane on a terminal of the given size
+    /// 
+    /// For vertical directions, there is one lane per column, each as long as the terminal is tall.
+    /// For horizontal directions, there is one lane per row, each as long as the terminal is wide.
+    pub fn lane_dimensions(&self, terminal_width: u16, terminal_height: u16) -> (u16, u16)
+    {
+        if self.is_horizontal() {
+            (terminal_height, terminal_width)
+        } else {
+            (terminal_width, terminal_height)
+        }
+    }
+}
+
+/// Settings that control the shape and behavior of a `Raindrop`
+/// 
+/// `follower_min_length` is the shortest length a follower will be. It must be at least 1.
+/// 
+/// `follower_max_length_offset` determines the longest length a follower will be; 
+/// the longest follower is the terminal height minus this offset.
+/// 
+/// `direction` is the [Direction] the `Raindrop` falls in.
+/// 
+/// `speed_variation` gives each `Raindrop` its own speed, chosen every time it respawns, 
+/// so that some move every frame and others only every 2 or 3 frames. When `false`, 
+/// every `Raindrop` can move on every frame.
+/// 
+/// `glitch_rate` is the chance (from 0.0 to 1.0) that a `Raindrop` re-randomizes a few of its 
+/// follower characters on any given frame, making them flicker. It defaults to 0.0, 
+/// which leaves followers unchanged until the `Raindrop` respawns.
+/// 
+/// `density` is roughly the proportion (from 0.0 to 1.0) of lanes that hold a `Raindrop` at 
+/// any one time. Whenever a `Raindrop` (re)spawns, it has a `1.0 - density` chance of leaving 
+/// its lane empty instead, only coming back after a random wait. It defaults to 1.0, 
+/// which keeps every lane filled.
+/// 
+/// `drops_per_lane` is the most `Raindrop`s that can share a lane at once, letting a new 
+/// stream start before the previous one has left the terminal. It defaults to 1.
+/// 
+/// `leader_refresh_frames` is the number of frames each leader char is shown for before it 
+/// changes to another, so leaders can change less often than every frame. It defaults to 1, 
+/// which changes them on every frame; 0 is treated as 1.
+/// 
+/// `static_leader` gives each `Raindrop` a single leader char, picked whenever it (re)spawns 
+/// and kept until it respawns again, instead of changing the leader as it falls. 
+/// `leader_refresh_frames` has no effect when it is set. It defaults to `false`.
+/// 
+/// If the terminal is too short to fit a follower of `follower_min_length` 
+/// under these settings, followers will be between `follower_min_length` and 
+/// `follower_min_length + 1` chars long.
+#[derive(Clone, Copy, Debug)]
+pub struct RaindropConfig {
+    pub follower_min_length: u16,
+    pub follower_max_length_offset: u16,
+    pub direction: Direction,
+    pub speed_variation: bool,
+    pub glitch_rate: f32,
+    pub density: f32,
+    pub drops_per_lane: u8,
+    pub leader_refresh_frames: u32,
+    pub static_leader: bool
+}
+
+impl RaindropConfig {
+
+    /// Returns the range of follower lengths that will be used on a terminal of height `terminal_height`
+    /// 
+    /// The returned range is never empty; see the notes on [RaindropConfig] for what happens
+    /// when the terminal is too short.
+    pub fn follower_length_range(&self, terminal_height: u16) -> RangeInclusive<u16>
+    {
+        // determine max follower length by subtracting offset from current terminal height
+        let max_follower_length = terminal_height.saturating_sub(self.follower_max_length_offset)
+        // ensure max follower length is at least follower_min_length + 1
+        .max(self.follower_min_length.saturating_add(1));
+
+        self.follower_min_length..=max_follower_length
+    }
+
+    /// Returns `true` if a follower of `follower_min_length` fits within the max length 
+    /// for a terminal of height `terminal_height`; `false` otherwise
+    pub fn fits_terminal_height(&self, terminal_height: u16) -> bool
+    {
+        self.follower_min_length <= terminal_height.saturating_sub(self.follower_max_length_offset)
+    }
+}
+
+impl Default for RaindropConfig {
+    fn default() -> Self
+    {
+        Self {
+            follower_min_length: DEFAULT_FOLLOWER_MIN_LENGTH,
+            follower_max_length_offset: DEFAULT_FOLLOWER_MAX_LENGTH_OFFSET,
+            direction: Direction::default(),
+            speed_variation: false,
+            glitch_rate: 0.0,
+            density: 1.0,
+            drops_per_lane: 1,
+            leader_refresh_frames: 1,
+            static_leader: false
+        }
+    }
+}
+
+/// Which follower characters are drawn bold, as set by [RenderConfig::follower_bold]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FollowerBold {
+    /// No followers are bold, leaving only the leader bold
+    #[default]
+    None,
+    /// About half of the followers are bold, picked afresh for every char on every frame
+    Random,
+    /// Every follower is bold
+    All
+}
+
+/// Settings that control how a `Raindrop`'s characters are styled when drawn
+/// 
+/// `bold_leader` makes the leader bold. Some terminals draw bold text in a brighter color, 
+/// so this can be turned off; the leader keeps its color either way. It defaults to `true`.
+/// 
+/// `leader_color` is the [Color](coolor::Color) of the leader. It defaults to bright white 
+/// (ANSI color 15); a pale version of the follower color is a popular alternative.
+/// 
+/// `use_color` colors the leader and followers. When it is `false`, chars are drawn without
+/// any color, for terminals and other consumers that can't handle color escapes; 
+/// the leader is still bold if `bold_leader` is `true`. It defaults to `true`.
+/// 
+/// `color_depth` is the [ColorDepth] of the terminal; every color is mapped to the nearest
+/// color the terminal can display. It defaults to [TrueColor](ColorDepth::TrueColor), 
+/// which leaves colors unchanged.
+/// 
+/// `brightness` scales the lightness of every char, from `0.0` to `1.0`, as a final step after 
+/// the color algorithm has picked its color. Leaders are never dimmed by more than a fixed 
+/// amount, so they stay visible even at a brightness of `0.0`. It defaults to `1.0`, 
+/// which leaves colors unchanged.
+/// 
+/// `vignette` darkens chars further from the center of the screen, for the look of an old CRT,
+/// on top of `brightness`. At `1.0`, chars in the very corners are dimmed to black (except 
+/// leaders, which never fade out entirely), with the lightness of each char 
+/// falling off with the square of its distance from the center. It defaults to `0.0`, 
+/// which turns the vignette off.
+/// 
+/// `scanlines` dims every other row of the screen (starting with the second), on top of both 
+/// `brightness` and `vignette`, to look like the scanlines of a CRT. It defaults to `false`.
+/// 
+/// `glow` is the number of follower chars right behind the leader that are lightened, 
+/// on top of the color picked by the color algorithm, to make the leader look like it's glowing.
+/// The follower closest to the leader is lightened the most, and the glow fades out evenly 
+/// over the rest. It defaults to `0`, which turns the glow off.
+/// 
+/// `follower_bold` decides which follower chars are bold, like the `-b` and `-B` options 
+/// of cmatrix; see [FollowerBold]. It applies whether or not color is used, and regardless of 
+/// `bold_leader`. It defaults to [FollowerBold::None].
+/// 
+/// `wind` makes the rain lean sideways, as the number of columns (or rows, for horizontal 
+/// directions) each lane drifts by per row (or column) of the screen. A positive `wind` pushes 
+/// the lower part of the screen to the right (or the right side of the screen down), and a negative 
+/// `wind` the other way. The lean slowly gusts between three quarters of `wind` and all of it, 
+/// and chars drifting off one side of the screen wrap around to the other. It should be within 
+/// the range `[-1.0, 1.0]`, and small values look the most natural. It defaults to `0.0`, 
+/// which keeps every lane straight.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderConfig {
+    pub bold_leader: bool,
+    pub leader_color: Color,
+    pub use_color: bool,
+    pub color_depth: ColorDepth,
+    pub brightness: f32,
+    pub vignette: f32,
+    pub scanlines: bool,
+    pub glow: u8,
+    pub follower_bold: FollowerBold,
+    pub wind: f32
+}
+
+impl RenderConfig {
+    // returns c styled as a leader: in the leader color (if color is used) and bold (if turned on)
+    pub(crate) fn style_leader(&self, c: char) -> style::StyledContent<char>
+    {
+        self.style_leader_with_brightness(c, self.brightness)
+    }
+
+    // returns c styled as a leader, with its lightness scaled by brightness 
+    // (but never by less than MIN_LEADER_BRIGHTNESS)
+    fn style_leader_with_brightness(&self, c: char, brightness: f32) -> style::StyledContent<char>
+    {
+        let leader = if self.use_color {
+            let leader_color = self.apply_brightness(self.leader_color, 
+                brightness.max(MIN_LEADER_BRIGHTNESS));
+            let leader_color = self.color_depth.downsample(leader_color);
+            c.with(leader_color.into())
+        } else {
+            style::style(c)
+        };
+        if self.bold_leader {
+            leader.attribute(style::Attribute::Bold)
+        } else {
+            leader
+        }
+    }
+
+    // returns the brightness of a char drawn at the place on screen given by color_context,
+    // taking the brightness, the vignette, and the scanlines into account
+    fn brightness_at(&self, color_context: &ColorContext) -> f32
+    {
+        let distance = color_context.distance_from_center();
+        let brightness = self.brightness * (1.0 - self.vignette * distance * distance);
+        if self.scanlines && color_context.row_index % 2 == 1 {
+            brightness * SCANLINE_BRIGHTNESS
+        } else {
+            brightness
+        }
+    }
+
+    // returns follower styled bold if follower_bold says the char drawn at the place and time 
+    // given by color_context should be
+    fn style_follower_bold(&self, follower: style::StyledContent<char>, color_context: &ColorContext)
+    -> style::StyledContent<char>
+    {
+        let bold = match self.follower_bold {
+            FollowerBold::None => false,
+            FollowerBold::Random => char_roll(color_context) & 1 == 1,
+            FollowerBold::All => true
+        };
+        if bold {
+            follower.attribute(style::Attribute::Bold)
+        } else {
+            follower
+        }
+    }
+
+    // returns color lightened by the glow, if the follower position_in_follower chars behind 
+    // the leader is close enough to it to glow
+    fn apply_glow(&self, color: Color, position_in_follower: f32) -> Color
+    {
+        let glow = f32::from(self.glow);
+        if position_in_follower >= glow {
+            return color;
+        }
+        //the glow closes this proportion of the gap between the color's lightness and white
+        let boost = (glow - position_in_follower) / (glow + 1.0);
+        let mut hsl = color.hsl();
+        hsl.l += (1.0 - hsl.l) * boost;
+        Color::Hsl(hsl)
+    }
+
+    // returns the number of lanes the char position_on_screen cells along its lane drifts by
+    // in the wind, phase frames into the animation
+    pub(crate) fn wind_offset(&self, phase: f32, position_on_screen: u16) -> i32
+    {
+        if self.wind == 0.0 {
+            return 0;
+        }
+        let gust = 0.75 + 0.25 * (std::f32::consts::TAU * phase / WIND_PERIOD).sin();
+        (self.wind * gust * f32::from(position_on_screen)).round() as i32
+    }
+
+    // returns color with its lightness scaled by brightness, clamped to the range [0.0, 1.0]
+    fn apply_brightness(&self, color: Color, brightness: f32) -> Color
+    {
+        //converting to HSL and back can shift a color slightly, so full brightness is left alone
+        if brightness >= 1.0 {
+            return color;
+        }
+        let mut hsl = color.hsl();
+        hsl.l *= brightness.max(0.0);
+        Color::Hsl(hsl)
+    }
+}
+
+// returns a pseudorandom number for the char drawn at the place and time given by color_context;
+// rendering doesn't change any state, so this hashes the char's position and phase instead of 
+// drawing from an rng, giving every char a fresh roll on every frame
+fn char_roll(color_context: &ColorContext) -> u64
+{
+    let mut roll = (u64::from(color_context.column_index) << 48)
+        ^ (u64::from(color_context.row_index) << 32)
+        ^ u64::from(color_context.phase.to_bits());
+
+    //the finalizer of splitmix64, which spreads every input bit across the whole output
+    roll = (roll ^ (roll >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    roll = (roll ^ (roll >> 27)).wrapping_mul(0x94d049bb133111eb);
+    roll ^ (roll >> 31)
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self
+    {
+        Self {
+            bold_leader: true,
+            leader_color: Color::Ansi(AnsiColor::new(15)),
+            use_color: true,
+            color_depth: ColorDepth::default(),
+            brightness: 1.0,
+            vignette: 0.0,
+            scanlines: false,
+            glow: 0,
+            follower_bold: FollowerBold::default(),
+            wind: 0.0
+        }
+    }
+}
 
 /// A `Raindrop` describes a single 'falling stream' of randomized characters
 /// 
 /// Raindrops consist of a 'leader' and a 'follower'.
-/// The leader is a continuously (per frame) randomized single character at the bottom of the raindrop.
+/// The leader is a continuously (per frame, by default) randomized single character at the bottom of the raindrop.
 /// The follower is a string of characters that follow the leader. They have randomized length and content,
 /// but unlike leaders, are randomized only once (at instantiation) rather than continuously (per frame)
-pub struct Raindrop<'a, T>
-where T: ColorAlgorithm
+pub struct Raindrop<'a>
 {
     // follower_content is ordered such that index 0 represents
     // the first char above the leader, index 1 represents the second, and so on
-    // note that Vec<char> is used instead of String; this is because we care about
+    // note that a list of chars is used instead of String; this is because we care about
     // char-by-char indexing more than we care about the potential waste of 3 bytes per char
-    follower_content: Vec<char>,
+    follower_content: FollowerContent,
+
+    // the char currently displayed as the leader; re-rolled by advance_animation (once per frame,
+    // unless RaindropConfig::leader_refresh_frames says otherwise) so that reading it doesn't 
+    // mutate the raindrop
+    leader_char: char,
+
+    // number of frames left before the leader char is re-rolled
+    frames_until_leader_refresh: u32,
 
     // row index representing the terminal row that the leader is on
     // the follower will be on indecies below this value
@@ -40,99 +414,170 @@ where T: ColorAlgorithm
     row_index: i32,
 
     // reference to a set of characters that will be selected from
-    // when generating pseudorandom characters
+    // when generating pseudorandom characters, unless set_charsets gives it others
     charset: &'a Vec<char>,
 
+    // the charsets that sampler is picked from whenever the raindrop respawns;
+    // empty unless set_charsets gives it some to pick from
+    charsets: &'a [CharSampler],
+
+    // the one of charsets that chars are drawn from until the next respawn, 
+    // or None to draw them from charset
+    sampler: Option<&'a CharSampler>,
+
     // probability of advancing position on any given frame,
-    // defaults to 1.0, but can be any value `n` where `0.0 < n <= 1.0`
+    // defaults to 1.0, but can be any value `n` where `0.0 <= n <= 1.0`
     advance_chance: f64,
 
-    // ColorAlgorithm implementor that is used to color follower chars
-    color_algorithm: T,
+    // settings controlling follower length
+    config: RaindropConfig,
+
+    // number of frames it takes to move one row; 1 moves on every frame
+    speed: u8,
+
+    // number of frames left to wait before the next move
+    frames_until_move: u8,
+
+    // whether this raindrop is leaving its lane empty for now (see RaindropConfig::density);
+    // a dormant raindrop stays above the terminal until it wakes up
+    dormant: bool,
+
+    // value within the range [0.0, 1.0) passed to the color algorithm, allowing it to
+    // color each raindrop differently; re-rolled every time the raindrop respawns
+    hue_seed: f32,
 
     // locally cached random number generator
-    local_rng: rngs::ThreadRng
+    // this is seeded either from system entropy or from a user provided seed
+    local_rng: StdRng
 }
 
-impl<'a, T> Raindrop<'a, T>
-where T: ColorAlgorithm
+impl<'a> Raindrop<'a>
 {
 
     /// Returns a (pseudo)randomly generated character from the internal charset
     pub fn gen_char(&mut self) -> char 
     {
-        *(self.charset.choose(&mut self.local_rng).unwrap())
+        match self.sampler {
+            Some(sampler) => sampler.sample(&mut self.local_rng),
+            None => *(self.charset.choose(&mut self.local_rng).unwrap())
+        }
     }
 
     /// Returns a new `Raindrop` instance
     /// 
     /// `charset` should be a reference to Vector of chars.
     /// 
-    /// `color_algorithm` should implement 
-    /// [ColorAlgorithm](crate::raindrop::color_algorithms::ColorAlgorithm). It defines
-    /// how follower characters will be colored.
-    /// 
     /// `advance_chance` is the chance that, on any given frame, this `Raindrop` will 
-    /// advance its animation. This can be any real number within the range `[0.0, 1.0)`.
-    /// If the `advance_chance` is 1.0, this `Raindrop` will always advance its animation.
+    /// advance its animation. This can be any real number within the range `[0.0, 1.0]`.
+    /// If the `advance_chance` is 1.0, this `Raindrop` will always advance its animation;
+    /// if it is 0.0, this `Raindrop` will never advance.
+    /// 
+    /// `config` should be a [RaindropConfig]. It defines the range of lengths the follower may have.
     /// 
     /// `terminal_height` should be the current height of the terminal, in rows.
     /// 
     ///# Panics
     /// 
-    /// This function panics if `advance_chance` is outside the range `[0.0, 1.0)`
+    /// This function panics if `advance_chance` is outside the range `[0.0, 1.0]`
+    /// 
+    /// This function panics if `config.follower_min_length` is zero
     /// 
     ///# Examples
     /// ```
-    /// use mrs_matrix::raindrop::{Raindrop, color_algorithms};
+    /// use mrs_matrix::raindrop::{Raindrop, RaindropConfig};
     /// use crossterm::terminal;
     /// 
     /// let charset = vec!['a','b', 'c'];
     /// 
-    /// let color_algorithm = color_algorithms::LightnessDescending{
-    ///     hue: 118.0,
-    ///     saturation: 0.82
-    /// };
-    /// 
     /// let advance_chance = 0.75;
     /// 
     /// let term_height = terminal::size().unwrap().1;
     /// 
-    /// let new_raindrop_instance = Raindrop::new(&charset, color_algorithm, advance_chance, term_height);
+    /// let config = RaindropConfig::default();
+    /// 
+    /// let new_raindrop_instance = Raindrop::new(&charset, advance_chance, config, term_height);
     /// // do something with instance
     /// ```
-    pub fn new(charset: &'a Vec<char>, color_algorithm: T, advance_chance: f64, terminal_height: u16) -> Self
+    pub fn new(charset: &'a Vec<char>, advance_chance: f64, 
+        config: RaindropConfig, terminal_height: u16) -> Self
     {
-        
-        assert!(advance_chance > 0.0, "Attempted to set advance chance at 0 or below");
+        Self::with_rng(charset, &[], advance_chance, config, terminal_height, StdRng::from_entropy())
+    }
+
+    /// Returns a new `Raindrop` instance whose randomness is fully determined by `seed`
+    /// 
+    /// This is identical to [Raindrop::new](crate::raindrop::Raindrop::new), except
+    /// that two `Raindrop`s created with the same arguments and the same `seed` will
+    /// produce exactly the same sequence of characters and movements. This is useful for
+    /// creating reproducible animations.
+    /// 
+    ///# Panics
+    /// 
+    /// This function panics under the same conditions as [Raindrop::new](crate::raindrop::Raindrop::new)
+    pub fn new_seeded(charset: &'a Vec<char>, advance_chance: f64, 
+        config: RaindropConfig, terminal_height: u16, seed: u64) -> Self
+    {
+        Self::with_rng(charset, &[], advance_chance, config, terminal_height, 
+            StdRng::seed_from_u64(seed))
+    }
+
+    // shared implementation of new and new_seeded; chars are drawn from charset if charsets is empty
+    fn with_rng(charset: &'a Vec<char>, charsets: &'a [CharSampler], advance_chance: f64, 
+        config: RaindropConfig, terminal_height: u16, local_rng: StdRng) -> Self
+    {
+        assert!(advance_chance >= 0.0, "Attempted to set advance chance below 0");
         assert!(advance_chance <= 1.0, "Attempted to set advance chance greater than 1");
+        assert!(config.follower_min_length > 0, "Attempted to set follower min length to 0");
+        assert!((0.0..=1.0).contains(&config.glitch_rate), 
+            "Attempted to set glitch rate outside of range [0.0, 1.0]");
+        assert!((0.0..=1.0).contains(&config.density), 
+            "Attempted to set density outside of range [0.0, 1.0]");
 
         // create a new `Raindrop` instance
-        // use an empty vector for follower content and a zero for row index;
+        // use an empty vector for follower content and zeroes for row index and hue seed;
         // these will be overwritten by the call to reinit_state; in fact they could safely be null
         // if rust had a null type
         let mut new_instance  = Self {
             charset,
-            color_algorithm,
-            local_rng: rand::thread_rng(),
-            follower_content: Vec::new(),
+            charsets,
+            sampler: charsets.first(),
+            config,
+            local_rng,
+            follower_content: FollowerContent::new(),
+            leader_char: ' ',
+            frames_until_leader_refresh: 0,
             row_index: 0,
+            hue_seed: 0.0,
+            speed: 1,
+            frames_until_move: 0,
+            dormant: false,
             advance_chance
         };
 
-        // do the work of initializing the state of the raindrop;
-        // setting its follower_content and row_index pseudorandomly
-        new_instance.reinit_state(terminal_height);
+        // do the work of initializing the state of the raindrop
+        new_instance.start(terminal_height);
 
         // return the newly created and initialized instance
         new_instance
     }
 
+    // sets the follower_content, row_index and leader pseudorandomly, as a new raindrop starts out
+    fn start(&mut self, terminal_height: u16)
+    {
+        self.reinit_state(terminal_height);
+
+        // pick the first leader char; after this, it's re-rolled by advance_animation
+        // (static leaders were already picked by reinit_state)
+        if !self.config.static_leader {
+            self.leader_char = self.gen_char();
+        }
+    }
+
     /// Re-initializes the state of the `Raindrop` instance 
     /// 
     /// Uses an internally cached random number generator to generate
-    /// pseudorandom follower chars and sets the row index to a pseudorandom value
-    /// less than (visually 'above') row 0.
+    /// pseudorandom follower chars and a new hue seed, and sets the row index to a 
+    /// pseudorandom value less than (visually 'above') row 0.
     /// 
     /// `terminal_height` should be the current height of the terminal, in rows
     /// 
@@ -140,35 +585,63 @@ where T: ColorAlgorithm
     /// 
     /// The [Raindrop::new](crate::raindrop::Raindrop::new) function uses this function internally
     /// to set the initial state. Calling this function manually is similar to creating
-    /// a new `Raindrop` instance outright, but avoids the need to create a new [Rng].
+    /// a new `Raindrop` instance outright, but avoids the need to create a new [Rng]
+    /// (and keeps the sequence of a seeded `Raindrop` reproducible). The new follower chars 
+    /// are written into the space the previous ones took up, so this only allocates when 
+    /// the new follower is longer than any this `Raindrop` has had before.
     pub fn reinit_state(&mut self, terminal_height: u16)
     {
-        // determine max follower length by subtracting offset from current terminal height
-        let max_follower_length = terminal_height.saturating_sub(FOLLOWER_MAX_LENGTH_OFFSET)
-        // ensure max follower length is at least FOLLOWER_MIN_LENGTH + 1
-        .max(FOLLOWER_MIN_LENGTH + 1);
- 
+        // pick which charset to draw from until the next respawn, skipping the rng call
+        // if there's only one, so that seeded raindrops with a single charset are unaffected
+        if self.charsets.len() > 1 {
+            self.sampler = self.charsets.choose(&mut self.local_rng);
+        }
+
         // use rng to generate follower_content and row_index
-        // first determine follower length
-        let follower_length = self.local_rng.gen_range(FOLLOWER_MIN_LENGTH..=max_follower_length);
+        // first determine follower length from the range allowed by the config
+        let follower_length = self.local_rng.gen_range(
+            self.config.follower_length_range(terminal_height));
  
-        // create empty vector with capacity great enough to hold all follower chars
-        let mut new_follower_content = Vec::with_capacity(follower_length.into());
+        // empty the follower content, keeping its allocation so that respawning only 
+        // allocates when the new follower is longer than any this raindrop has had
+        self.follower_content.clear();
+        self.follower_content.reserve(follower_length.into());
          
-        // generate follower_length chars and place them in new_follower_content vec
+        // generate follower_length chars and place them in the follower content
         for _ in 0..follower_length{
-            new_follower_content.push(self.gen_char());
+            // this needs to be done seperately from pushing the char 
+            // to satisfy the borrow checker (as both self.follower_content.push 
+            // and self.gen_char mutably borrow self)
+            let new_char = self.gen_char();
+            self.follower_content.push(new_char);
         }
-
-        // store new follower content
-        // this needs to be done seperately from using self.gen_char 
-        // to satisfy the borrow checker (as both self.follower_length.push 
-        // and self.gen_char mutably borrow self)
-        self.follower_content = new_follower_content;
  
         // generate and store new row index value
         // this can be done in a single step
         self.row_index = self.local_rng.gen_range(START_OFFSET_RANGE); 
+
+        // generate and store new hue seed value within the range [0.0, 1.0)
+        self.hue_seed = self.local_rng.gen();
+
+        // pick a new speed if speeds vary; otherwise, skip the rng call 
+        // so that seeded raindrops without speed variation are unaffected
+        self.speed = if self.config.speed_variation {
+            self.local_rng.gen_range(SPEED_VARIATION_RANGE)
+        } else {
+            1
+        };
+        self.frames_until_move = self.speed - 1;
+
+        // decide whether to leave the lane empty for a while, skipping the rng call
+        // if every lane should be filled
+        self.dormant = self.config.density < 1.0 
+            && !self.local_rng.gen_bool(self.config.density.into());
+
+        // pick the leader char to keep for this whole fall if leaders are static; otherwise, 
+        // skip the rng call so that seeded raindrops with changing leaders are unaffected
+        if self.config.static_leader {
+            self.leader_char = self.gen_char();
+        }
  
         // don't return anything
     }
@@ -181,7 +654,10 @@ where T: ColorAlgorithm
     /// row that this instance has no char for (for example, because this raindrop 
     /// is above the provided row), `None` will be returned.
     /// If this instance does have a char for the provided row, `Some(char)` is returned.
-    pub fn get_char_at_row(&mut self, row_index: u16) -> Option<char>
+    /// 
+    /// The leader's char only changes when the `Raindrop` is advanced, so reading the same
+    /// row twice in a frame returns the same char.
+    pub fn get_char_at_row(&self, row_index: u16) -> Option<char>
     {
         
         // cast provided row index to i32 and bind to a more clear name
@@ -194,10 +670,10 @@ where T: ColorAlgorithm
             return None;
         }
         
-        // return a randomly selected char if provided row index points to the leader of this Raindrop
+        // return the leader char if provided row index points to the leader of this Raindrop
         // (i.e. if the provided row index and current row index match exactly)
         if self.row_index == provided_row_index {
-            return Some(self.gen_char());
+            return Some(self.leader_char);
         }
 
         // we already checked if provided row index was greater than row index
@@ -206,33 +682,33 @@ where T: ColorAlgorithm
 
         // find the index within follower_content that provided_row_index should point to,
         // keeping min mind that follower starts 1 row above (less than) row_index
-        match TryInto::<usize>::try_into((self.row_index - 1) - provided_row_index) 
-        {
-            Err(_) => {
-                //if follower_index can't be represented as a usize for whatever reason,
-                //print a warning to stderr and return None
-                eprintln!("Failed to represent follower_index ({}) as a usize; skipping char", 
-                    (self.row_index - 1) - provided_row_index);
-                return None
-            },
-            Ok(follower_index) => {
-                //return either the char at the follower index, or None if there isn't one
-                match self.follower_content.get(follower_index) {
-                    Some(&val) => Some(val),
-                    None => None
-                }
-            }
-        }
-        
+        // as provided row index is less than row index, this can never be negative
+        let follower_index = (self.row_index - 1) - provided_row_index;
+        debug_assert!(follower_index >= 0, "follower_index ({}) is negative", follower_index);
+
+        // return either the char at the follower index, or None if there isn't one
+        // (nothing is printed if the conversion somehow fails, as writing to stderr
+        // would corrupt the screen while the animation is running)
+        usize::try_from(follower_index).ok()
+            .and_then(|follower_index| self.follower_content.get(follower_index).copied())
     }
 
     /// Returns the character that should be printed for a given row with appropriate styling
     /// 
-    /// Internally, uses `get_styled_char` to retrieve the actual character. Then applies a color
-    /// according to this `Raindrop`'s `color_algorithm`
+    /// Internally, uses [get_char_at_row](crate::raindrop::Raindrop::get_char_at_row) to retrieve 
+    /// the actual character. Follower characters are then colored by the provided `color_algorithm`,
+    /// which should implement [ColorAlgorithm](crate::raindrop::color_algorithms::ColorAlgorithm).
     /// 
-    /// The leader of the raindrop will always be styled white (and bolded).
-    pub fn get_styled_char_at_row(&mut self, row_index: u16) -> Option<style::StyledContent<char>>
+    /// The leader of the raindrop is styled with `render_config.leader_color`, and is bolded 
+    /// if `render_config.bold_leader` is `true`. If `render_config.use_color` is `false`, 
+    /// no chars are colored.
+    /// 
+    /// `color_context` says when and where on the screen the char is drawn, and is passed on to
+    /// the `color_algorithm`; see [ColorContext](crate::raindrop::color_algorithms::ColorContext).
+    pub fn get_styled_char_at_row<T>(&self, row_index: u16, color_algorithm: &T, 
+        render_config: &RenderConfig, color_context: &ColorContext) 
+    -> Option<style::StyledContent<char>>
+    where T: ColorAlgorithm
     {
         match self.get_char_at_row(row_index){
             //if get_char_at_row returns None, return None immediately
@@ -241,9 +717,12 @@ where T: ColorAlgorithm
                 
                 
                 if self.row_index == row_index.into() {
-                    //if char is the leader, style as white (and bold)
-                    Some(unstyled_char.with(style::Color::White)
-                    .attribute(style::Attribute::Bold))
+                    //if char is the leader, style with the leader color (and bold, unless turned off)
+                    Some(render_config.style_leader_with_brightness(unstyled_char, 
+                        render_config.brightness_at(color_context)))
+                } else if !render_config.use_color {
+                    //without color, followers are drawn plain (but may still be bold)
+                    Some(render_config.style_follower_bold(style::style(unstyled_char), color_context))
                 } else {
                     //calculate follower proportion from position_in_follower and follower_length
                     let position_in_follower = ((self.row_index - 1) - (row_index as i32)) as f32;
@@ -252,14 +731,135 @@ where T: ColorAlgorithm
                     let follower_proportion = (position_in_follower/follower_length).min(1.0).max(0.0);
                     
                     let char_color = 
-                        self.color_algorithm.gen_color(follower_proportion);
+                        color_algorithm.gen_color(follower_proportion, self.hue_seed, color_context);
                     
-                    Some(unstyled_char.with(char_color.into()))
+                    //lighten the color near the leader, dim it, then map it onto what 
+                    //the terminal can display
+                    let char_color = render_config.apply_glow(char_color, position_in_follower);
+                    let char_color = render_config.apply_brightness(char_color, 
+                        render_config.brightness_at(color_context));
+                    let char_color = render_config.color_depth.downsample(char_color);
+                    Some(render_config.style_follower_bold(unstyled_char.with(char_color.into()), 
+                        color_context))
                 }
             }
         } 
     }
 
+    /// Returns the row that the leader of this `Raindrop` is on
+    /// 
+    /// This may be negative (above the top of the terminal) or greater than 
+    /// the terminal height (below the bottom of the terminal).
+    /// 
+    /// Like all rows passed to or returned from a `Raindrop`, this is counted from the edge
+    /// of the terminal the `Raindrop` enters from; see [Direction].
+    pub fn row_index(&self) -> i32
+    {
+        self.row_index
+    }
+
+    /// Moves the leader of this `Raindrop` to `row`, keeping its follower and leader chars
+    /// 
+    /// `row` is clamped to the range `[-65535, 65535]`, which reaches past the edges of any 
+    /// terminal. A `Raindrop` placed entirely below the terminal is re-randomized (and moved back
+    /// above the top) the next time it's advanced, just as if it had fallen there.
+    /// 
+    /// If the `Raindrop` was leaving its lane empty (see `density` in [RaindropConfig]), 
+    /// placing it wakes it up, so that it carries on falling from `row`.
+    pub fn set_row_index(&mut self, row: i32)
+    {
+        self.row_index = row.clamp(*SET_ROW_INDEX_RANGE.start(), *SET_ROW_INDEX_RANGE.end());
+        self.dormant = false;
+    }
+
+    /// Returns the row that the last char of this `Raindrop`'s follower is on
+    /// 
+    /// Once this is above zero, the whole `Raindrop` has entered the terminal.
+    pub fn tail_row_index(&self) -> i32
+    {
+        self.row_index - self.follower_content.len() as i32
+    }
+
+    /// Returns the number of chars in this `Raindrop`'s follower, not counting the leader
+    /// 
+    /// This only changes when the `Raindrop` is re-randomized by 
+    /// [reinit_state](crate::raindrop::Raindrop::reinit_state).
+    pub fn follower_len(&self) -> usize
+    {
+        self.follower_content.len()
+    }
+
+    /// Returns the char this `Raindrop`'s leader is displayed as on the current frame
+    /// 
+    /// The leader is rolled when the `Raindrop` is created or respawned, and re-rolled by 
+    /// [advance_animation](crate::raindrop::Raindrop::advance_animation) as its leader refreshes; 
+    /// reading it doesn't re-roll it.
+    pub fn leader_char(&self) -> char
+    {
+        self.leader_char
+    }
+
+    /// Returns a new `Raindrop` with the same charset, advance chance, and config as this one
+    /// 
+    /// The new `Raindrop` is seeded from this one's random number generator, so a seeded
+    /// `Raindrop` spawns the same siblings every time.
+    pub fn spawn_sibling(&mut self, terminal_height: u16) -> Raindrop<'a>
+    {
+        let sibling_rng = StdRng::from_rng(&mut self.local_rng)
+            .expect("seeding from another StdRng should never fail");
+        Self::with_rng(self.charset, self.charsets, self.advance_chance, self.config, terminal_height, 
+            sibling_rng)
+    }
+
+    /// Turns `recycled` into a new sibling of this `Raindrop`, reusing its allocations
+    /// 
+    /// Afterwards, `recycled` is the same as the `Raindrop` [spawn_sibling](Raindrop::spawn_sibling) 
+    /// would have returned, but keeps the space it had for its follower chars, so a raindrop 
+    /// that has finished falling can be reused rather than dropped and allocated again.
+    pub fn spawn_sibling_into(&mut self, recycled: &mut Raindrop<'a>, terminal_height: u16)
+    {
+        recycled.local_rng = StdRng::from_rng(&mut self.local_rng)
+            .expect("seeding from another StdRng should never fail");
+        recycled.charset = self.charset;
+        recycled.charsets = self.charsets;
+        recycled.sampler = self.charsets.first();
+        recycled.advance_chance = self.advance_chance;
+        recycled.config = self.config;
+        recycled.leader_char = ' ';
+        recycled.frames_until_leader_refresh = 0;
+        recycled.start(terminal_height);
+    }
+
+    /// Gives the `Raindrop` several charsets to pick from
+    /// 
+    /// One of `charsets` is picked at random straight away, redrawing the follower and leader 
+    /// chars from it, and another is picked every time the `Raindrop` respawns. Every char of a 
+    /// single fall comes from the same charset, picked as often as the [CharSampler] says, so 
+    /// even a single charset can be given here to have its chars picked by weight.
+    /// 
+    ///# Panics
+    /// 
+    /// This function panics if `charsets` is empty, or any of its charsets are
+    pub fn set_charsets(&mut self, charsets: &'a [CharSampler])
+    {
+        assert!(!charsets.is_empty(), "Attempted to set an empty list of charsets");
+        assert!(charsets.iter().all(|charset| !charset.is_empty()), 
+            "Attempted to set a charset with no characters");
+
+        self.charsets = charsets;
+        self.sampler = self.charsets.choose(&mut self.local_rng);
+        for follower_index in 0..self.follower_content.len() {
+            self.follower_content[follower_index] = self.gen_char();
+        }
+        self.leader_char = self.gen_char();
+    }
+
+    /// Returns the [Direction] this `Raindrop` falls in
+    pub fn direction(&self) -> Direction
+    {
+        self.config.direction
+    }
+
     /// Moves the `Raindrop` down one row.
     /// 
     /// To reset to the top, use [reinit_state](crate::raindrop::Raindrop::reinit_state).
@@ -281,20 +881,58 @@ where T: ColorAlgorithm
 
     }
 
+    // re-randomizes a small random subset (roughly an eighth, but at least one) of the follower chars
+    fn glitch(&mut self)
+    {
+        if self.follower_content.is_empty() {
+            return;
+        }
+
+        let glitch_count = (self.follower_content.len() / 8).max(1);
+        for _ in 0..glitch_count {
+            let follower_index = self.local_rng.gen_range(0..self.follower_content.len());
+            self.follower_content[follower_index] = self.gen_char();
+        }
+    }
+
     /// Advance the `Raindrop` by one 'frame'
     /// 
     /// `terminal_height` should be the current height of the terminal, in rows.
     /// 
-    /// This is similar to [move_drop](crate::raindrop::Raindrop::move_drop), with two key differences:
+    /// This is similar to [move_drop](crate::raindrop::Raindrop::move_drop), with these key differences:
     /// - If the `Raindrop` is not visible because it has fallen down below the bottom of the terminal,
-    /// [reinit_state](crate::raindrop::Raindrop::reinit_state) is called to re-randomize the `Raindrop` and
-    /// move it slightly above the top of the terminal.
+    ///   [reinit_state](crate::raindrop::Raindrop::reinit_state) is called to re-randomize the `Raindrop` and
+    ///   move it slightly above the top of the terminal.
     /// 
     /// - If the `Raindrop` has had its `advance_chance` set to some value that is not 1.0, this function
-    /// will only have a chance of advancing this raindrop's position. If you want to move the `Raindrop` 
-    /// for certain, use the [move_drop](crate::raindrop::Raindrop::move_drop) method
+    ///   will only have a chance of advancing this raindrop's position. If you want to move the `Raindrop` 
+    ///   for certain, use the [move_drop](crate::raindrop::Raindrop::move_drop) method
+    /// 
+    /// - If the `Raindrop` is leaving its lane empty (see `density` in [RaindropConfig]), it 
+    ///   has a chance of waking up instead of moving.
+    /// 
+    /// - If the `Raindrop` has a `glitch_rate` above 0.0 (see [RaindropConfig]), it has a chance
+    ///   of re-randomizing a few of its follower characters.
+    /// 
+    /// - If the `Raindrop` was given a slower speed (see [RaindropConfig]), it only has a chance of
+    ///   advancing once every 2 or 3 frames, and stays put on the frames in between.
+    /// 
+    /// Whether or not it moves, the leader char is re-randomized on every call, or on every 
+    /// `leader_refresh_frames` calls (see [RaindropConfig]), unless the `Raindrop` has a 
+    /// `static_leader`.
     pub fn advance_animation(&mut self, terminal_height: u16)
     {
+        // re-roll the leader char first, so that it changes on schedule however this call ends;
+        // static leaders keep the char picked by reinit_state instead
+        if !self.config.static_leader {
+            if self.frames_until_leader_refresh == 0 {
+                self.leader_char = self.gen_char();
+                self.frames_until_leader_refresh = self.config.leader_refresh_frames.max(1) - 1;
+            } else {
+                self.frames_until_leader_refresh -= 1;
+            }
+        }
+
         // only perform visibility check if current row is not less than 0
         // if we didn't make this check conditional, advance_animation would continuously call reinit_state
         // as raindrops always start above row 0 but are never visible until they reach row 0
@@ -304,6 +942,28 @@ where T: ColorAlgorithm
                 return;
             }
         }
+
+        // dormant raindrops wait to wake up rather than moving; the chance of waking is scaled
+        // to the time a raindrop takes to cross the terminal, so that the proportion of filled
+        // lanes settles at around the configured density
+        if self.dormant {
+            let wake_chance = f64::from(self.config.density) / f64::from(terminal_height.max(1));
+            self.dormant = !self.local_rng.gen_bool(wake_chance.min(1.0));
+            return;
+        }
+
+        // occasionally re-randomize some follower chars; the rng is only used when glitching 
+        // is enabled, so that seeded raindrops without it are unaffected
+        if self.config.glitch_rate > 0.0 && self.local_rng.gen_bool(self.config.glitch_rate.into()) {
+            self.glitch();
+        }
+
+        // slower raindrops wait out the frames between moves
+        if self.frames_until_move > 0 {
+            self.frames_until_move -= 1;
+            return;
+        }
+        self.frames_until_move = self.speed - 1;
         
         if self.advance_chance == 1.0 {
             // unconditionally move if advance_chance is 1.0, skipping an uneeded rng call
@@ -1,18 +1,173 @@
 //! The Charset trait and a variety of pre-made character sets
 
+use std::collections::HashSet;
 use std::ops::RangeInclusive;
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedError, WeightedIndex};
+use rand::seq::SliceRandom;
+use unicode_width::UnicodeWidthChar;
+
+// the unicode symbols AsciiAndSymbols adds to the printable ascii chars
+const SYMBOL_CHARCODE_RANGES: [RangeInclusive<u32>; 3] = [
+    0x2100..=0x2138, //exclude U+2139 which doesn't always style properly on Windows
+    0x213A..=0x214F, 
+    0x2A00..=0x2AFF
+];
+
+// the half-width katakana block (U+FF66 through U+FF9D), followed by the ASCII digits 
+const KATAKANA_CHARCODE_RANGES: [RangeInclusive<u32>; 2] = [
+    0xFF66..=0xFF9D,
+    0x30..=0x39
+];
+
+// the braille patterns block, skipping the blank pattern at U+2800
+const BRAILLE_CHARCODE_RANGE: RangeInclusive<u32> = 0x2801..=0x28FF;
+
+// the number of printable ascii chars (sans the space character)
+const PRINTABLE_ASCII_LEN: usize = 94;
+
+// in the Weighted::matrix preset, how much more often each katakana char is picked than each digit
+const MATRIX_KATAKANA_WEIGHT: u32 = 6;
+
+/// Returns `true` if `c` takes up exactly one terminal cell
+/// 
+/// Zero-width chars (such as combining marks, which draw over the previous char) and wide chars
+/// (such as CJK ideographs, which take up two cells) would make the columns of rain drift out of line.
+pub fn is_single_width(c: char) -> bool
+{
+    c.width() == Some(1)
+}
+
+// returns chars, without any that aren't single width if charset is sanitized
+fn sanitize<C: Charset + ?Sized>(charset: &C, mut chars: Vec<char>) -> Vec<char>
+{
+    if charset.sanitize() {
+        chars.retain(|c| is_single_width(*c));
+    }
+    chars
+}
+
+// returns the number of charcodes in each of ranges, combined
+fn ranges_len(ranges: &[RangeInclusive<u32>]) -> usize
+{
+    ranges.iter().map(|range| (range.end() - range.start() + 1) as usize).sum()
+}
+
+// returns true if c is in any of ranges
+fn ranges_contain(ranges: &[RangeInclusive<u32>], c: char) -> bool
+{
+    ranges.iter().any(|range| range.contains(&(c as u32)))
+}
 
 pub trait Charset {
-    ///Return the character set as a vector of chars
-    fn get_charset(&self) -> Vec<char>;
+    ///Return an iterator over the characters in the set, without collecting them
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_>;
+
+    ///Return `true` if chars that aren't exactly one terminal cell wide (see [is_single_width]) 
+    ///are left out of the collected set; `true` by default, and `false` for [Unsanitized] sets
+    fn sanitize(&self) -> bool
+    {
+        true
+    }
+
+    ///Return the character set as a vector of chars, sanitized if [sanitize](Charset::sanitize) 
+    ///returns `true`
+    fn get_charset(&self) -> Vec<char>
+    {
+        sanitize(self, self.charset_iter().collect())
+    }
+
+    ///Return the number of characters in the set, without collecting them
+    fn len(&self) -> usize
+    {
+        let sanitize = self.sanitize();
+        self.charset_iter().filter(|c| !sanitize || is_single_width(*c)).count()
+    }
+
+    ///Return `true` if the set has no characters
+    fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+
+    ///Return `true` if `c` is one of the characters in the set, without collecting them
+    fn contains(&self, c: char) -> bool
+    {
+        (!self.sanitize() || is_single_width(c)) && self.charset_iter().any(|set_char| set_char == c)
+    }
+
+    ///Return a [CharSampler] that raindrops can draw the characters of the set from
+    ///
+    ///By default, each char of [get_charset](Charset::get_charset) is equally likely to be 
+    ///picked, so a char that appears more than once is picked more often.
+    fn sampler(&self) -> CharSampler
+    {
+        CharSampler::new(self.get_charset())
+    }
+}
+
+/// A collected character set that raindrops draw chars from, picking each char either equally 
+/// often or in proportion to its weight
+/// 
+/// Collecting the chars (and their weights) up front means that picking a char never allocates.
+/// A `CharSampler` is returned by [Charset::sampler], or made from a `Vec<char>` with 
+/// [new](CharSampler::new).
+#[derive(Clone, Debug)]
+pub struct CharSampler {
+    chars: Vec<char>,
+
+    // how often each of chars is picked, or None to pick them all equally often
+    weights: Option<WeightedIndex<u32>>
+}
+
+impl CharSampler {
+    /// Returns a new `CharSampler` that picks each of `chars` equally often, so a char that 
+    /// appears more than once is picked more often
+    pub fn new(chars: Vec<char>) -> Self
+    {
+        Self{chars, weights: None}
+    }
+
+    /// Returns the chars that can be picked
+    pub fn chars(&self) -> &[char]
+    {
+        &self.chars
+    }
+
+    /// Returns `true` if there are no chars to pick
+    pub fn is_empty(&self) -> bool
+    {
+        self.chars.is_empty()
+    }
+
+    /// Returns a (pseudo)randomly picked char, using `rng`
+    /// 
+    ///# Panics
+    /// 
+    /// This function panics if there are no chars to pick
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> char
+    {
+        match &self.weights {
+            Some(weights) => self.chars[weights.sample(rng)],
+            None => *self.chars.choose(rng).expect("cannot sample a char from an empty charset")
+        }
+    }
+}
+
+impl From<Vec<char>> for CharSampler {
+    fn from(chars: Vec<char>) -> Self
+    {
+        Self::new(chars)
+    }
 }
 
 /// ASCII letter and number characters
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Alphanumeric();
 impl Charset for Alphanumeric {
-    fn get_charset(&self) -> Vec<char> 
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
     {
-        //generate and return vector of all alphanumeric chars
+        //iterate over all alphanumeric chars
         //alphanumerics make up ASCII (or UTF-8) codes 0x30 through 0x39 for digits,
         //0x41 to 0x5A for capitals, and 0x61 to 0x7A for lowercase
         const CHARCODE_RANGES: [RangeInclusive<u8>; 3] = [
@@ -20,54 +175,299 @@ impl Charset for Alphanumeric {
             0x41..=0x5a,
             0x61..=0x7a
         ];
-        //the length of these three combined is 62 chars; hence capacity of 62
+        Box::new(CHARCODE_RANGES.into_iter().flatten().map(char::from))
+    }
+
+    fn get_charset(&self) -> Vec<char> 
+    {
+        //the length of the three ranges combined is 62 chars; hence capacity of 62
         let mut outvec: Vec<char> = Vec::with_capacity(62);
-        for charcode_range in CHARCODE_RANGES {
-            for charcode in charcode_range {
-                outvec.push(charcode as char);
-            }
-        }
-        outvec
+        outvec.extend(self.charset_iter());
+        sanitize(self, outvec)
+    }
+
+    fn len(&self) -> usize
+    {
+        62
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        false
+    }
+
+    fn contains(&self, c: char) -> bool
+    {
+        c.is_ascii_alphanumeric()
     }
 }
 
 /// All printable ASCII characters
+#[derive(Clone, Copy, Debug, Default)]
 pub struct PrintableAscii();
 impl Charset for PrintableAscii {
-    fn get_charset(&self) -> Vec<char> 
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
     {
-        //generate and return vector of all printable ascii chars (sans the space character)
+        //iterate over all printable ascii chars (sans the space character)
         //printable ascii chars make up codes 0x21 through 0x7E
         //note that 0x20 is the space character
-        let mut outvec: Vec<char> = Vec::with_capacity(93);
-        for charcode in 0x21..=0x7E_u8 {
-            outvec.push(charcode as char);
-        }
+        Box::new((0x21..=0x7E_u8).map(char::from))
+    }
 
-        outvec
+    fn len(&self) -> usize
+    {
+        PRINTABLE_ASCII_LEN
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        false
+    }
+
+    fn contains(&self, c: char) -> bool
+    {
+        //the graphic ascii chars are exactly codes 0x21 through 0x7E
+        c.is_ascii_graphic()
     }
 }
 
 /// All printable ASCII characters plus some fun unicode symbols
+#[derive(Clone, Copy, Debug, Default)]
 pub struct AsciiAndSymbols();
 impl Charset for AsciiAndSymbols {
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
+    {
+        //iterate over all printable ascii chars (sans the space character)
+        //followed by some fun unicode symbols
+        let symbols = SYMBOL_CHARCODE_RANGES.into_iter().flatten().map(|charcode| {
+            char::from_u32(charcode).expect("tried to add invalid char to AsciiAndSymbols")
+        });
+        Box::new(PrintableAscii().charset_iter().chain(symbols))
+    }
+
+    fn len(&self) -> usize
+    {
+        PRINTABLE_ASCII_LEN + ranges_len(&SYMBOL_CHARCODE_RANGES)
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        false
+    }
+
+    fn contains(&self, c: char) -> bool
+    {
+        PrintableAscii().contains(c) || ranges_contain(&SYMBOL_CHARCODE_RANGES, c)
+    }
+}
+
+/// Half-width Japanese katakana plus the ASCII digits, as seen in the films
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Katakana();
+impl Charset for Katakana {
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
+    {
+        //iterate over the half-width katakana block followed by the ASCII digits
+        let chars = KATAKANA_CHARCODE_RANGES.into_iter().flatten().map(|charcode| {
+            char::from_u32(charcode).expect("tried to add invalid char to Katakana")
+        });
+        //skip anything that doesn't take up exactly one cell, as that would make columns drift
+        Box::new(chars.filter(|new_char| is_single_width(*new_char)))
+    }
+
+    fn len(&self) -> usize
+    {
+        //all of the half-width katakana and digits are single width, so none of them are skipped
+        ranges_len(&KATAKANA_CHARCODE_RANGES)
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        false
+    }
+
+    fn contains(&self, c: char) -> bool
+    {
+        ranges_contain(&KATAKANA_CHARCODE_RANGES, c) && is_single_width(c)
+    }
+}
+
+/// The braille patterns, without the blank one
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Braille();
+impl Charset for Braille {
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
+    {
+        Box::new(BRAILLE_CHARCODE_RANGE.map(|charcode| {
+            char::from_u32(charcode).expect("tried to add invalid char to Braille")
+        }))
+    }
+
+    fn len(&self) -> usize
+    {
+        ranges_len(&[BRAILLE_CHARCODE_RANGE])
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        false
+    }
+
+    fn contains(&self, c: char) -> bool
+    {
+        BRAILLE_CHARCODE_RANGE.contains(&(c as u32))
+    }
+}
+
+/// A character set made up of any chars, such as those of a string
+/// 
+/// The chars are kept in the order they're given, including any duplicates, 
+/// which makes duplicated chars more likely to be picked. Like any [Charset], it leaves out 
+/// chars that aren't exactly one cell wide when collected, unless wrapped in [Unsanitized].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Custom(pub Vec<char>);
+impl Charset for Custom {
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
+    {
+        Box::new(self.0.iter().copied())
+    }
+
     fn get_charset(&self) -> Vec<char> 
     {
-        //generate vector of all printable ascii chars (sans the space character)
-        //then add some fun unicode symbols and return the result
-        let mut outvec = PrintableAscii().get_charset();
+        sanitize(self, self.0.clone())
+    }
+}
 
-        const CHARCODE_RANGES: [RangeInclusive<u32>; 3] = [
-            0x2100..=0x2138, //exclude U+2139 which doesn't always style properly on Windows
-            0x213A..=0x214F, 
-            0x2A00..=0x2AFF];
+impl From<&str> for Custom {
+    fn from(chars: &str) -> Self
+    {
+        Self(chars.chars().collect())
+    }
+}
 
-        for charcode_range in CHARCODE_RANGES {
-            for charcode in charcode_range {
-                outvec.push(char::from_u32(charcode).expect("tried to add invalid char to AsciiAndSymbols"));
-            }
-        }
+/// A character set that keeps every char of another, including those that aren't exactly one 
+/// cell wide
+/// 
+/// This is for sets whose wide or zero-width chars are known to draw correctly in the terminal 
+/// they're shown in, such as a [Custom] set of emoji. Elsewhere, they can leave the columns of 
+/// rain out of line. The inner set's chars are all picked equally often, so a [Weighted] set 
+/// loses its weighting (though it leaves out chars that aren't single width itself).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Unsanitized<C: Charset>(pub C);
+impl<C: Charset> Charset for Unsanitized<C> {
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
+    {
+        self.0.charset_iter()
+    }
+
+    fn sanitize(&self) -> bool
+    {
+        false
+    }
+}
+
+/// A character set made up of the chars of several others, such as [Katakana] and [Alphanumeric]
+/// 
+/// The members' chars are chained together in order, keeping only the first of any chars 
+/// that appear more than once, so each char is as likely to be picked as any other 
+/// (even those of a [Custom] member that repeats them, or of a [Weighted] member).
+#[derive(Default)]
+pub struct Composite(pub Vec<Box<dyn Charset>>);
+impl Charset for Composite {
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
+    {
+        //skip any char already yielded by this or an earlier member
+        let mut seen = HashSet::new();
+        Box::new(self.0.iter()
+            .flat_map(|member| member.charset_iter())
+            .filter(move |c| seen.insert(*c)))
+    }
+
+    fn is_empty(&self) -> bool
+    {
+        self.0.iter().all(|member| member.is_empty())
+    }
+
+    fn contains(&self, c: char) -> bool
+    {
+        self.0.iter().any(|member| member.contains(c))
+    }
+}
+
+/// A character set whose chars are picked more or less often, in proportion to their weights
+/// 
+/// The collected set holds each char once, and [sampler](Charset::sampler) returns a 
+/// [CharSampler] that picks chars by weight, which is how raindrops follow the weighting. 
+/// Chars with a weight of zero are never picked, and chars that aren't exactly one cell wide 
+/// are left out.
+#[derive(Clone, Debug)]
+pub struct Weighted {
+    weights: Vec<(char, u32)>,
+    index: WeightedIndex<u32>
+}
+
+impl Weighted {
+    /// Returns a new `Weighted` charset, picking each char in proportion to its weight
+    /// 
+    ///# Errors
+    /// 
+    /// This function returns an error if `weights` has no single width chars, 
+    /// or all of their weights are zero
+    pub fn new(mut weights: Vec<(char, u32)>) -> Result<Self, WeightedError>
+    {
+        weights.retain(|(c, _)| is_single_width(*c));
+        WeightedIndex::new(weights.iter().map(|(_, weight)| *weight))?;
+
+        //chars that are never picked are left out, so that they aren't part of the collected set
+        weights.retain(|(_, weight)| *weight > 0);
+        let index = WeightedIndex::new(weights.iter().map(|(_, weight)| *weight))
+            .expect("at least one weight is above zero");
+        Ok(Self{weights, index})
+    }
 
-        outvec
+    /// Returns the half-width katakana and digits of [Katakana], weighted like the films: 
+    /// mostly katakana, with a digit now and then
+    pub fn matrix() -> Self
+    {
+        let weights = Katakana().charset_iter().map(|c| {
+            let weight = if c.is_ascii_digit() {1} else {MATRIX_KATAKANA_WEIGHT};
+            (c, weight)
+        }).collect();
+        Self::new(weights).expect("the matrix weights are never all zero")
+    }
+
+    /// Returns the chars of this set, each paired with its weight
+    /// 
+    /// Chars that were given a weight of zero, or aren't exactly one cell wide, are left out.
+    pub fn weights(&self) -> &[(char, u32)]
+    {
+        &self.weights
+    }
+}
+
+impl Charset for Weighted {
+    fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_> 
+    {
+        Box::new(self.weights.iter().map(|(c, _)| *c))
+    }
+
+    fn len(&self) -> usize
+    {
+        self.weights.len()
     }
-}
\ No newline at end of file
+
+    fn is_empty(&self) -> bool
+    {
+        false
+    }
+
+    fn contains(&self, c: char) -> bool
+    {
+        self.weights.iter().any(|(set_char, _)| *set_char == c)
+    }
+
+    fn sampler(&self) -> CharSampler
+    {
+        CharSampler{chars: self.charset_iter().collect(), weights: Some(self.index.clone())}
+    }
+}
@@ -1,81 +1,406 @@
 //! Algorithms that determine the color of `Raindrop` follower characters
 
-use coolor::{Color, Hsl};
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+use std::sync::OnceLock;
+use coolor::{Color, Hsl, Rgb};
 
-pub trait ColorAlgorithm: Sized + Copy{
+// valid hue degrees
+const HUE_RANGE: Range<f32> = 0.0..360.0;
+
+// valid saturation and lightness amounts
+const AMOUNT_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+
+// the number of steps from the leader to the end of the follower that 
+// LightnessDescending precomputes colors for
+const LIGHTNESS_STEPS: usize = 256;
+
+// the lightness that LightnessDescending (and Breathing) followers never fade below, by default
+const DEFAULT_MIN_LIGHTNESS: f32 = 0.1;
+
+// the hues of the single-color schemes, in the order of ColorScheme::ALL
+const SCHEME_HUES: [f32; 5] = [118.0, 244.0, 302.0, 0.0, 51.0];
+
+// the hue of the green scheme, which the algorithms with a base hue default to
+const DEFAULT_HUE: f32 = SCHEME_HUES[0];
+
+// the fully saturated green, and the dark green a green follower fades down to, 
+// which the algorithms taking whole colors default to
+const DEFAULT_COLOR: Hsl = Hsl{h: DEFAULT_HUE, s: 1.0, l: 0.5};
+const DEFAULT_DARK_COLOR: Hsl = Hsl{h: DEFAULT_HUE, s: 1.0, l: DEFAULT_MIN_LIGHTNESS};
+
+// the number of frames a Breathing breath takes by default; at the default framerate, 
+// this is one breath every four seconds
+const DEFAULT_BREATH_PERIOD: f32 = 100.0;
+
+// hue degrees a RotatingRainbow turns by each frame by default; at the default framerate, 
+// this is one full turn every few seconds
+const DEFAULT_ROTATION_SPEED: f32 = 4.0;
+
+// the algorithms for the single-color schemes, created the first time a scheme is used
+// so that their colors are only computed once
+static SCHEME_ALGORITHMS: OnceLock<[LightnessDescending; 5]> = OnceLock::new();
+
+/// An error returned when a color algorithm is constructed with a component outside of its range
+/// 
+/// Each variant holds the rejected value, apart from [EmptyPalette](ColorError::EmptyPalette).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorError {
+    /// The hue was outside of the range `[0.0, 360.0)`
+    Hue(f32),
+    /// The saturation was outside of the range `[0.0, 1.0]`
+    Saturation(f32),
+    /// The lightness was outside of the range `[0.0, 1.0]`
+    Lightness(f32),
+    /// The period was not a positive, finite number of frames
+    Period(f32),
+    /// The speed was not a finite number of degrees per frame
+    Speed(f32),
+    /// The exponent was not a positive, finite number
+    Exponent(f32),
+    /// A palette was given no colors
+    EmptyPalette
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            ColorError::Hue(hue) => write!(f, "hue {} outside of expected bounds [0, 360)", hue),
+            ColorError::Saturation(saturation) => 
+                write!(f, "saturation {} outside of expected bounds [0, 1]", saturation),
+            ColorError::Lightness(lightness) => 
+                write!(f, "lightness {} outside of expected bounds [0, 1]", lightness),
+            ColorError::Period(period) => 
+                write!(f, "period {} is not a positive number of frames", period),
+            ColorError::Speed(speed) => 
+                write!(f, "speed {} is not a finite number of degrees per frame", speed),
+            ColorError::Exponent(exponent) => 
+                write!(f, "exponent {} is not a positive number", exponent),
+            ColorError::EmptyPalette => write!(f, "palette has no colors")
+        }
+    }
+}
+
+impl std::error::Error for ColorError {}
+
+// returns an error if hue is outside of HUE_RANGE
+fn check_hue(hue: f32) -> Result<(), ColorError>
+{
+    if HUE_RANGE.contains(&hue) { Ok(()) } else { Err(ColorError::Hue(hue)) }
+}
+
+// returns an error if saturation is outside of AMOUNT_RANGE
+fn check_saturation(saturation: f32) -> Result<(), ColorError>
+{
+    if AMOUNT_RANGE.contains(&saturation) { Ok(()) } else { Err(ColorError::Saturation(saturation)) }
+}
+
+// returns an error if lightness is outside of AMOUNT_RANGE
+fn check_lightness(lightness: f32) -> Result<(), ColorError>
+{
+    if AMOUNT_RANGE.contains(&lightness) { Ok(()) } else { Err(ColorError::Lightness(lightness)) }
+}
+
+// returns an error if any component of color is outside of its range
+fn check_hsl(color: Hsl) -> Result<(), ColorError>
+{
+    check_hue(color.h)?;
+    check_saturation(color.s)?;
+    check_lightness(color.l)
+}
+
+/// When and where on the screen a character is drawn, passed to [ColorAlgorithm::gen_color]
+/// 
+/// The default `ColorContext` has a `phase` of 0.0 and puts the char in the top left corner of 
+/// a terminal with no rows or columns, which algorithms should treat as the top of the screen 
+/// (and renderers as its center). It is used where there is no running animation, such as in 
+/// [render_frame](crate::render_frame).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColorContext {
+    /// The number of frames the animation has advanced by
+    pub phase: f32,
+    /// The row of the terminal the char is drawn on, counting down from the top
+    pub row_index: u16,
+    /// The height of the terminal in rows
+    pub term_rows: u16,
+    /// The column of the terminal the char is drawn in, counting right from the left edge
+    pub column_index: u16,
+    /// The width of the terminal in columns
+    pub term_cols: u16
+}
+
+impl ColorContext {
+    /// Returns how far down the screen the char is, from 0.0 in the top row to 1.0 in the bottom row
+    pub fn screen_proportion(&self) -> f32
+    {
+        if self.term_rows <= 1 {
+            return 0.0;
+        }
+        (f32::from(self.row_index) / f32::from(self.term_rows - 1)).min(1.0)
+    }
+
+    /// Returns how far the char is from the center of the screen, from 0.0 in the center 
+    /// to 1.0 in the corners
+    /// 
+    /// Distances are measured relative to the size of the screen, so the middle of every edge 
+    /// is the same distance from the center, however wide or tall the screen is. Screens with 
+    /// no more than one row (or column) are treated as though every char is in the middle row 
+    /// (or column).
+    pub fn distance_from_center(&self) -> f32
+    {
+        //the offset from the center along one axis, from -1.0 at one edge to 1.0 at the other
+        let offset = |index: u16, length: u16| {
+            if length <= 1 {
+                return 0.0;
+            }
+            let half_length = f32::from(length - 1) / 2.0;
+            ((f32::from(index) - half_length) / half_length).clamp(-1.0, 1.0)
+        };
+        let column_offset = offset(self.column_index, self.term_cols);
+        let row_offset = offset(self.row_index, self.term_rows);
+        ((column_offset * column_offset + row_offset * row_offset) / 2.0).sqrt()
+    }
+}
+
+/// A supertrait of [ColorAlgorithm] that requires [Sync] when the `parallel` feature is enabled,
+/// so that frames can be colored from multiple threads
+/// 
+/// This is implemented for every type that meets its requirements, so it never needs to be
+/// implemented by hand.
+#[cfg(feature = "parallel")]
+pub trait SyncIfParallel: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync + ?Sized> SyncIfParallel for T {}
+
+/// A supertrait of [ColorAlgorithm] that requires [Sync] when the `parallel` feature is enabled,
+/// so that frames can be colored from multiple threads
+/// 
+/// This is implemented for every type that meets its requirements, so it never needs to be
+/// implemented by hand.
+#[cfg(not(feature = "parallel"))]
+pub trait SyncIfParallel {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> SyncIfParallel for T {}
+
+/// Determines the color of `Raindrop` follower characters
+/// 
+/// This trait is object safe, so algorithms chosen at runtime can be used 
+/// as a `Box<dyn ColorAlgorithm>`. With the `parallel` feature, algorithms must also be [Sync].
+pub trait ColorAlgorithm: SyncIfParallel {
     
     ///Returns a [Color](coolor::Color) that will be applied to a character
     /// 
     /// Passed a `follower_proportion` within the range `[0.0, 1.0]` representing
     /// how far away this char is from the leader (with 1.0 being max distance)
     /// 
+    /// Also passed a `hue_seed` within the range `[0.0, 1.0)`. This is chosen randomly for 
+    /// each `Raindrop` and stays fixed until it respawns, so algorithms can use it to give 
+    /// each `Raindrop` its own stable color. Most algorithms ignore it.
+    /// 
+    /// Finally, passed a [ColorContext] describing when and where on the screen the char is drawn.
+    /// Algorithms can use it to change colors over time or across the screen; most ignore it.
+    /// 
     ///# Notes
     /// 
     /// This function should panic if `follower_proportion` is less than 0 or greater than 1.
-    fn gen_color(&self, follower_proportion: f32) -> Color;
+    fn gen_color(&self, follower_proportion: f32, hue_seed: f32, context: &ColorContext) -> Color;
 
 }
 
-/// Colors characters with varying lightness according to their distance from the leader
-/// 
-/// `hue` is the hue degree of the base color. It must be within the range `(0.0, 360.0]`.
+impl<C: ColorAlgorithm + ?Sized> ColorAlgorithm for Box<C> {
+    fn gen_color(&self, follower_proportion: f32, hue_seed: f32, context: &ColorContext) -> Color {
+        (**self).gen_color(follower_proportion, hue_seed, context)
+    }
+}
+
+impl<C: ColorAlgorithm + ?Sized> ColorAlgorithm for &C {
+    fn gen_color(&self, follower_proportion: f32, hue_seed: f32, context: &ColorContext) -> Color {
+        (**self).gen_color(follower_proportion, hue_seed, context)
+    }
+}
+
+/// Interpolates between hues `a` and `b` along the shorter arc of the color wheel
 /// 
-/// `saturation` is the saturation amount of the base color. It must be within the range `(0.0, 1.0)`.
+/// `t` is how far along the arc to go, with 0.0 returning `a` and 1.0 returning `b`. 
+/// The result is wrapped into the range `[0.0, 360.0)`, so interpolating from 350 to 10 
+/// passes through 0 rather than through 180.
+pub fn lerp_hue(a: f32, b: f32, t: f32) -> f32
+{
+    //find the signed difference between the two hues along the shorter arc;
+    //this will always be within the range [-180, 180)
+    let difference = (b - a).rem_euclid(360.0);
+    let difference = if difference >= 180.0 { difference - 360.0 } else { difference };
+
+    let hue = (a + difference * t).rem_euclid(360.0);
+
+    //rem_euclid can round up to exactly 360 for tiny negative values
+    if hue >= 360.0 { 0.0 } else { hue }
+}
+
+/// Colors characters with varying lightness according to their distance from the leader
 /// 
-///# Notes
+/// Lightness falls from 0.9 next to the leader down to a floor of 0.1, which can be changed
+/// with [with_min_lightness](LightnessDescending::with_min_lightness). It falls linearly with 
+/// the distance from the leader, unless given a different curve with 
+/// [with_exponent](LightnessDescending::with_exponent).
 /// 
-/// If `hue` or `saturation` are outside of their expected ranges, `gen_color` will panic
+/// Every color is converted to RGB once, when the `LightnessDescending` is created, and
+/// looked up by the (quantized) distance from the leader rather than converted per char.
 #[derive(Copy, Clone)]
 pub struct LightnessDescending {
-    pub hue: f32,
-    pub saturation: f32
+    hue: f32,
+    saturation: f32,
+
+    // the lightness followers never fade below
+    min_lightness: f32,
+
+    // the power the distance from the leader is raised to before lightness is taken from it
+    exponent: f32,
+
+    // the color of each step from the leader to the end of the follower
+    colors: [Rgb; LIGHTNESS_STEPS]
+}
+impl LightnessDescending {
+    /// Returns a new `LightnessDescending`
+    /// 
+    /// `hue` is the hue degree of the base color. It must be within the range `[0.0, 360.0)`.
+    /// 
+    /// `saturation` is the saturation amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `hue` or `saturation` are outside of their expected ranges
+    pub fn new(hue: f32, saturation: f32) -> Result<Self, ColorError>
+    {
+        check_hue(hue)?;
+        check_saturation(saturation)?;
+        Ok(Self::precompute(hue, saturation, DEFAULT_MIN_LIGHTNESS, 1.0))
+    }
+
+    /// Returns this `LightnessDescending` with followers fading no lower than `min_lightness`
+    /// 
+    /// `min_lightness` must be within the range `[0.0, 1.0]`. At `0.0`, the ends of followers 
+    /// fade all the way to black; higher values keep them brighter. The default is `0.1`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `min_lightness` is outside of its expected range
+    pub fn with_min_lightness(self, min_lightness: f32) -> Result<Self, ColorError>
+    {
+        check_lightness(min_lightness)?;
+        Ok(Self::precompute(self.hue, self.saturation, min_lightness, self.exponent))
+    }
+
+    /// Returns this `LightnessDescending` with lightness falling off along a curve
+    /// 
+    /// The distance of each char from the leader (from 0.0 to 1.0) is raised to the power of
+    /// `exponent` before its lightness is worked out, which must be positive. At `1.0` (the 
+    /// default), lightness falls linearly. Higher values keep the follower bright for longer, 
+    /// with lightness dropping off faster towards the tail; lower values do the opposite.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `exponent` isn't a positive, finite number
+    pub fn with_exponent(self, exponent: f32) -> Result<Self, ColorError>
+    {
+        if !(exponent.is_finite() && exponent > 0.0) {
+            return Err(ColorError::Exponent(exponent));
+        }
+        Ok(Self::precompute(self.hue, self.saturation, self.min_lightness, exponent))
+    }
+
+    /// Returns the lightness followers never fade below
+    pub fn min_lightness(&self) -> f32
+    {
+        self.min_lightness
+    }
+
+    /// Returns the exponent of the curve lightness falls off along
+    pub fn exponent(&self) -> f32
+    {
+        self.exponent
+    }
+
+    // computes the colors of every step, without checking any of the components
+    fn precompute(hue: f32, saturation: f32, min_lightness: f32, exponent: f32) -> Self
+    {
+        let mut colors = [Rgb{r: 0, g: 0, b: 0}; LIGHTNESS_STEPS];
+        for (step, color) in colors.iter_mut().enumerate() {
+            let follower_proportion = step as f32 / (LIGHTNESS_STEPS - 1) as f32;
+            *color = Hsl{
+                h: hue, 
+                s: saturation,
+                l: descending_lightness(follower_proportion.powf(exponent), min_lightness)
+            }.to_rgb();
+        }
+        Self{hue, saturation, min_lightness, exponent, colors}
+    }
+}
+impl Default for LightnessDescending {
+    /// Returns the same `LightnessDescending` as the green [ColorScheme]
+    fn default() -> Self
+    {
+        ColorScheme::Green.lightness_descending().expect("the green scheme uses lightness descending")
+    }
 }
 impl ColorAlgorithm for LightnessDescending {
 
-    fn gen_color(&self, follower_proportion: f32) -> Color {
-        assert!(follower_proportion >= 0.0 && follower_proportion <= 1.0,
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
             "follower_proportion outside of expected bounds (0, 1)");
-        assert!(self.hue >= 0.0 && self.hue < 360.0, "hue outside of expected bounds (0, 360]");
-        assert!(self.saturation >= 0.0 && self.saturation <= 1.0, 
-            "saturation outside of expected bounds (0, 1)");
 
-            //determine color lightness by subtracting the follower_proportion from 0.9; 
-            //this results in follower chars decreasing in brightness as their distance 
-            //from the leader increases
-            
-            coolor::Color::Hsl(
-                Hsl{     
-                    h:self.hue, 
-                    s:self.saturation,
-                    //use of max ensures lightness is always 0.1 or above 
-                    l:((0.9 - follower_proportion).max(0.1))
-                }
-            )
+        //look up the precomputed color of the nearest step
+        let step = (follower_proportion * (LIGHTNESS_STEPS - 1) as f32).round() as usize;
+        Color::Rgb(self.colors[step])
     }
 
 }
 
+// returns the lightness of a LightnessDescending follower char follower_proportion of the way 
+// from the leader to the end of the follower
+fn descending_lightness(follower_proportion: f32, min_lightness: f32) -> f32
+{
+    //determine color lightness by subtracting the follower_proportion from 0.9; 
+    //this results in follower chars decreasing in brightness as their distance 
+    //from the leader increases, down to min_lightness
+    (0.9 - follower_proportion).max(min_lightness)
+}
+
 /// Colors characters with varying saturation according to their distance from the leader
-/// 
-/// `hue` is the hue degree of the base color. It must be within the range `(0.0, 360.0]`.
-/// 
-/// `lightness` is the lightness amount of the base color. It must be within the range `(0.0, 1.0)`.
-/// 
-///# Notes
-/// 
-/// If `hue` or `lightness` are outside of their expected ranges, `gen_color` will panic
 #[derive(Clone, Copy)]
 pub struct SaturationDescending{
-    pub hue: f32,
-    pub lightness: f32
+    hue: f32,
+    lightness: f32
+}
+impl SaturationDescending {
+    /// Returns a new `SaturationDescending`
+    /// 
+    /// `hue` is the hue degree of the base color. It must be within the range `[0.0, 360.0)`.
+    /// 
+    /// `lightness` is the lightness amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `hue` or `lightness` are outside of their expected ranges
+    pub fn new(hue: f32, lightness: f32) -> Result<Self, ColorError>
+    {
+        check_hue(hue)?;
+        check_lightness(lightness)?;
+        Ok(Self{hue, lightness})
+    }
+}
+impl Default for SaturationDescending {
+    /// Returns a `SaturationDescending` with the hue of the green [ColorScheme] and a lightness of `0.5`
+    fn default() -> Self
+    {
+        Self{hue: DEFAULT_HUE, lightness: 0.5}
+    }
 }
 impl ColorAlgorithm for SaturationDescending {
-    fn gen_color(&self, follower_proportion: f32) -> Color {
-        assert!(follower_proportion >= 0.0 && follower_proportion <= 1.0,
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
             "follower_proportion outside of expected bounds (0, 1)");
-        assert!(self.hue >= 0.0 && self.hue < 360.0, "hue outside of expected bounds (0, 360]");
-        assert!(self.lightness >= 0.0 && self.lightness <= 1.0, 
-            "lightness outside of expected bounds (0, 1)");
 
             //determine color saturation by subtracting the follower_proportion from 1.0; 
             //this results in follower chars decreasing in saturation as their distance 
@@ -92,27 +417,40 @@ impl ColorAlgorithm for SaturationDescending {
 }
 
 /// Colors characters with varying hue according to their distance from the leader
-/// 
-/// `saturation` is the saturation amount of the base color. It must be within the range `(0.0, 1.0)`.
-/// 
-/// `lightness` is the lightness amount of the base color. It must be within the range `(0.0, 1.0)`.
-///
-///# Notes
-///  
-/// If `hue` or `lightness` are outside of their expected ranges, `gen_color` will panic
 #[derive(Clone, Copy)]
 pub struct HueVariation {
-    pub saturation: f32,
-    pub lightness: f32
+    saturation: f32,
+    lightness: f32
+}
+impl HueVariation {
+    /// Returns a new `HueVariation`
+    /// 
+    /// `saturation` is the saturation amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    /// `lightness` is the lightness amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `saturation` or `lightness` are outside of their expected ranges
+    pub fn new(saturation: f32, lightness: f32) -> Result<Self, ColorError>
+    {
+        check_saturation(saturation)?;
+        check_lightness(lightness)?;
+        Ok(Self{saturation, lightness})
+    }
+}
+impl Default for HueVariation {
+    /// Returns the same `HueVariation` as the rainbow [ColorScheme], 
+    /// with a saturation of `1.0` and a lightness of `0.5`
+    fn default() -> Self
+    {
+        Self{saturation: 1.0, lightness: 0.5}
+    }
 }
 impl ColorAlgorithm for HueVariation {
-    fn gen_color(&self, follower_proportion: f32) -> Color {
-        assert!(follower_proportion >= 0.0 && follower_proportion <= 1.0,
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
             "follower_proportion outside of expected bounds (0, 1)");
-        assert!(self.saturation >= 0.0 && self.saturation <= 1.0, 
-            "saturation outside of expected bounds (0, 1)");
-        assert!(self.lightness >= 0.0 && self.lightness <= 1.0, 
-            "lightness outside of expected bounds (0, 1)");
 
             //determine color hue by multiplying follower proportion by 360,
             //producing a valid hue value unique for each char position
@@ -124,4 +462,427 @@ impl ColorAlgorithm for HueVariation {
                 }
             )
     }
-}
\ No newline at end of file
+}
+
+/// Colors characters by blending between two colors according to their distance from the leader
+/// 
+/// Hue is blended along the shorter arc of the color wheel, so a gradient from green
+/// to blue passes through cyan rather than through red. Saturation and lightness are blended linearly.
+#[derive(Clone, Copy)]
+pub struct Gradient {
+    head: Hsl,
+    tail: Hsl
+}
+impl Gradient {
+    /// Returns a new `Gradient`
+    /// 
+    /// `head` is the color of the follower character closest to the leader, and `tail` is the 
+    /// color of the follower character furthest from the leader. For both colors, `h` must be 
+    /// within the range `[0.0, 360.0)`, and `s` and `l` must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if any component of `head` or `tail` is outside of its expected range
+    pub fn new(head: Hsl, tail: Hsl) -> Result<Self, ColorError>
+    {
+        check_hsl(head)?;
+        check_hsl(tail)?;
+        Ok(Self{head, tail})
+    }
+}
+impl Default for Gradient {
+    /// Returns a `Gradient` from fully saturated green at the leader to dark green at the tail
+    fn default() -> Self
+    {
+        Self{head: DEFAULT_COLOR, tail: DEFAULT_DARK_COLOR}
+    }
+}
+impl ColorAlgorithm for Gradient {
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
+            "follower_proportion outside of expected bounds (0, 1)");
+
+            //walk follower_proportion of the way along each component
+            coolor::Color::Hsl(
+                Hsl{
+                    h:lerp_hue(self.head.h, self.tail.h, follower_proportion),
+                    s:self.head.s + (self.tail.s - self.head.s) * follower_proportion,
+                    l:self.head.l + (self.tail.l - self.head.l) * follower_proportion
+                }
+            )
+    }
+}
+
+/// Colors every follower character with the same color, regardless of its distance from the leader
+#[derive(Clone, Copy)]
+pub struct Solid {
+    color: Hsl
+}
+impl Solid {
+    /// Returns a new `Solid`
+    /// 
+    /// `color` is the color of every follower character. `h` must be within the range 
+    /// `[0.0, 360.0)`, and `s` and `l` must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if any component of `color` is outside of its expected range
+    pub fn new(color: Hsl) -> Result<Self, ColorError>
+    {
+        check_hsl(color)?;
+        Ok(Self{color})
+    }
+}
+impl Default for Solid {
+    /// Returns a `Solid` coloring every char fully saturated green
+    fn default() -> Self
+    {
+        Self{color: DEFAULT_COLOR}
+    }
+}
+impl ColorAlgorithm for Solid {
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
+            "follower_proportion outside of expected bounds (0, 1)");
+
+            //every char gets the same color, so follower_proportion is only checked, not used
+            coolor::Color::Hsl(self.color)
+    }
+}
+
+/// Colors follower characters in bands, one for each color of a palette, from the leader to the tail
+/// 
+/// Each color covers an equal share of the follower, so the bands are stretched over long 
+/// followers and squeezed over short ones; a follower shorter than the palette skips some colors.
+/// Unlike [Gradient], colors aren't blended, so each band is a single flat color.
+#[derive(Clone, Debug)]
+pub struct Palette {
+    colors: Vec<Hsl>
+}
+impl Palette {
+    /// Returns a new `Palette`
+    /// 
+    /// `colors` are the colors of the bands, starting from the band closest to the leader. 
+    /// There must be at least one color, and for every color, `h` must be within the range 
+    /// `[0.0, 360.0)`, and `s` and `l` must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns [ColorError::EmptyPalette] if `colors` is empty, or another [ColorError] if any 
+    /// component of a color is outside of its expected range
+    pub fn new(colors: Vec<Hsl>) -> Result<Self, ColorError>
+    {
+        if colors.is_empty() {
+            return Err(ColorError::EmptyPalette);
+        }
+        for color in &colors {
+            check_hsl(*color)?;
+        }
+        Ok(Self{colors})
+    }
+}
+impl Default for Palette {
+    /// Returns a `Palette` of three bands, from fully saturated green at the leader to dark green 
+    /// at the tail
+    fn default() -> Self
+    {
+        Self{colors: vec![DEFAULT_COLOR, Hsl{l: 0.3, ..DEFAULT_COLOR}, DEFAULT_DARK_COLOR]}
+    }
+}
+impl ColorAlgorithm for Palette {
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
+            "follower_proportion outside of expected bounds (0, 1)");
+
+            //split the follower into one band per color; the very end of the follower 
+            //belongs to the last band
+            let band = ((follower_proportion * self.colors.len() as f32) as usize)
+                .min(self.colors.len() - 1);
+            coolor::Color::Hsl(self.colors[band])
+    }
+}
+
+/// Colors each `Raindrop` with its own randomly chosen hue, with lightness varying according 
+/// to distance from the leader
+/// 
+/// The hue is picked from the `Raindrop`'s hue seed, so it stays the same for the lifetime of 
+/// the `Raindrop` and is re-rolled whenever it respawns.
+#[derive(Clone, Copy)]
+pub struct RandomPerDrop {
+    saturation: f32,
+    lightness: f32
+}
+impl RandomPerDrop {
+    /// Returns a new `RandomPerDrop`
+    /// 
+    /// `saturation` is the saturation amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    /// `lightness` is the lightness of the follower char closest to the leader. Chars further
+    /// away fade down to a tenth of this value. It must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `saturation` or `lightness` are outside of their expected ranges
+    pub fn new(saturation: f32, lightness: f32) -> Result<Self, ColorError>
+    {
+        check_saturation(saturation)?;
+        check_lightness(lightness)?;
+        Ok(Self{saturation, lightness})
+    }
+}
+impl Default for RandomPerDrop {
+    /// Returns a `RandomPerDrop` with a saturation of `1.0` and a lightness of `0.6`
+    fn default() -> Self
+    {
+        Self{saturation: 1.0, lightness: 0.6}
+    }
+}
+impl ColorAlgorithm for RandomPerDrop {
+    fn gen_color(&self, follower_proportion: f32, hue_seed: f32, _context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
+            "follower_proportion outside of expected bounds (0, 1)");
+
+            //determine color hue by scaling the hue seed onto the color wheel,
+            //and reduce lightness as distance from the leader increases
+            coolor::Color::Hsl(
+                Hsl{     
+                    h:hue_seed * 360.0,
+                    s:self.saturation, 
+                    l:self.lightness * (1.0 - 0.9 * follower_proportion)
+                }
+            )
+    }
+}
+
+/// Colors characters like [LightnessDescending], while slowly brightening and dimming 
+/// the whole animation over time
+#[derive(Clone, Copy)]
+pub struct Breathing {
+    hue: f32,
+    saturation: f32,
+    period: f32
+}
+impl Breathing {
+    /// Returns a new `Breathing`
+    /// 
+    /// `hue` is the hue degree of the base color. It must be within the range `[0.0, 360.0)`.
+    /// 
+    /// `saturation` is the saturation amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    /// `period` is the number of frames one breath (from brightest, to dimmest, and back) takes. 
+    /// It must be positive.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `hue`, `saturation`, or `period` are outside of their expected ranges
+    pub fn new(hue: f32, saturation: f32, period: f32) -> Result<Self, ColorError>
+    {
+        check_hue(hue)?;
+        check_saturation(saturation)?;
+        if !(period.is_finite() && period > 0.0) {
+            return Err(ColorError::Period(period));
+        }
+        Ok(Self{hue, saturation, period})
+    }
+}
+impl Default for Breathing {
+    /// Returns a `Breathing` of fully saturated green, taking 100 frames for each breath
+    fn default() -> Self
+    {
+        Self{hue: DEFAULT_HUE, saturation: 1.0, period: DEFAULT_BREATH_PERIOD}
+    }
+}
+impl ColorAlgorithm for Breathing {
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
+            "follower_proportion outside of expected bounds (0, 1)");
+
+            //scale the lightness of LightnessDescending between 40% and 100% along a cosine wave,
+            //so the animation starts at its brightest
+            let breath = 0.7 + 0.3 * (std::f32::consts::TAU * context.phase / self.period).cos();
+            coolor::Color::Hsl(
+                Hsl{
+                    h:self.hue,
+                    s:self.saturation,
+                    l:descending_lightness(follower_proportion, DEFAULT_MIN_LIGHTNESS) * breath
+                }
+            )
+    }
+}
+
+/// Colors characters like [HueVariation], while rotating the hues over time so the rainbow 
+/// scrolls along each `Raindrop`
+#[derive(Clone, Copy)]
+pub struct RotatingRainbow {
+    saturation: f32,
+    lightness: f32,
+    speed: f32
+}
+impl RotatingRainbow {
+    /// Returns a new `RotatingRainbow`
+    /// 
+    /// `saturation` is the saturation amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    /// `lightness` is the lightness amount of the base color. It must be within the range `[0.0, 1.0]`.
+    /// 
+    /// `speed` is how many hue degrees the rainbow rotates by each frame. A negative `speed` 
+    /// rotates it the other way.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if `saturation`, `lightness`, or `speed` are outside of their expected ranges
+    pub fn new(saturation: f32, lightness: f32, speed: f32) -> Result<Self, ColorError>
+    {
+        check_saturation(saturation)?;
+        check_lightness(lightness)?;
+        if !speed.is_finite() {
+            return Err(ColorError::Speed(speed));
+        }
+        Ok(Self{saturation, lightness, speed})
+    }
+}
+impl Default for RotatingRainbow {
+    /// Returns a `RotatingRainbow` with the colors of the default [HueVariation], 
+    /// rotating by 4 hue degrees each frame
+    fn default() -> Self
+    {
+        let HueVariation{saturation, lightness} = HueVariation::default();
+        Self{saturation, lightness, speed: DEFAULT_ROTATION_SPEED}
+    }
+}
+impl ColorAlgorithm for RotatingRainbow {
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
+            "follower_proportion outside of expected bounds (0, 1)");
+
+            //offset the hue of HueVariation by how far the rainbow has rotated; 
+            //at a phase of 0.0 this is the same as HueVariation
+            let rotation = (context.phase * self.speed).rem_euclid(360.0);
+            coolor::Color::Hsl(
+                Hsl{
+                    h:(follower_proportion * 360.0 + rotation) % 360.0,
+                    s:self.saturation,
+                    l:self.lightness
+                }
+            )
+    }
+}
+
+/// Colors characters by blending between two colors according to the row of the screen they're on,
+/// regardless of which `Raindrop` they belong to
+/// 
+/// This gives the whole screen a banded fade from `top` to `bottom`. Hue is blended along the 
+/// shorter arc of the color wheel, in the same way as [Gradient].
+#[derive(Clone, Copy)]
+pub struct ScreenGradient {
+    top: Hsl,
+    bottom: Hsl
+}
+impl ScreenGradient {
+    /// Returns a new `ScreenGradient`
+    /// 
+    /// `top` is the color of chars in the top row, and `bottom` is the color of chars in the 
+    /// bottom row. For both colors, `h` must be within the range `[0.0, 360.0)`, and 
+    /// `s` and `l` must be within the range `[0.0, 1.0]`.
+    /// 
+    ///# Errors
+    /// 
+    /// Returns a [ColorError] if any component of `top` or `bottom` is outside of its expected range
+    pub fn new(top: Hsl, bottom: Hsl) -> Result<Self, ColorError>
+    {
+        check_hsl(top)?;
+        check_hsl(bottom)?;
+        Ok(Self{top, bottom})
+    }
+}
+impl Default for ScreenGradient {
+    /// Returns a `ScreenGradient` from fully saturated green at the top of the screen to 
+    /// dark green at the bottom
+    fn default() -> Self
+    {
+        Self{top: DEFAULT_COLOR, bottom: DEFAULT_DARK_COLOR}
+    }
+}
+impl ColorAlgorithm for ScreenGradient {
+    fn gen_color(&self, follower_proportion: f32, _hue_seed: f32, context: &ColorContext) -> Color {
+        assert!((0.0..=1.0).contains(&follower_proportion),
+            "follower_proportion outside of expected bounds (0, 1)");
+
+            //walk as far along each component as the char is down the screen
+            let screen_proportion = context.screen_proportion();
+            coolor::Color::Hsl(
+                Hsl{
+                    h:lerp_hue(self.top.h, self.bottom.h, screen_proportion),
+                    s:self.top.s + (self.bottom.s - self.top.s) * screen_proportion,
+                    l:self.top.l + (self.bottom.l - self.top.l) * screen_proportion
+                }
+            )
+    }
+}
+
+/// One of the preset color schemes, which can be switched between while the animation is running
+/// 
+/// Each of the single-color schemes uses [LightnessDescending] at full saturation, 
+/// while `Rainbow` uses [HueVariation].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorScheme {
+    #[default]
+    Green,
+    Blue,
+    Purple,
+    Red,
+    Yellow,
+    Rainbow
+}
+
+impl ColorScheme {
+    /// All of the preset color schemes, in the order of their number keys (`1` to `6`)
+    pub const ALL: [ColorScheme; 6] = [
+        ColorScheme::Green,
+        ColorScheme::Blue,
+        ColorScheme::Purple,
+        ColorScheme::Red,
+        ColorScheme::Yellow,
+        ColorScheme::Rainbow
+    ];
+
+    /// Returns the [LightnessDescending] this scheme colors chars with, 
+    /// or `None` for `Rainbow`, which doesn't use one
+    /// 
+    /// This is a starting point for a variation on a single-color scheme, such as one 
+    /// with a different [exponent](LightnessDescending::with_exponent).
+    pub fn lightness_descending(&self) -> Option<LightnessDescending>
+    {
+        self.scheme_algorithm().copied()
+    }
+
+    // returns the precomputed lightness descending algorithm of a single-color scheme, 
+    // or None for the rainbow scheme
+    fn scheme_algorithm(&self) -> Option<&'static LightnessDescending>
+    {
+        //each single-color scheme is lightness descending with its own hue
+        let scheme_index = match self {
+            ColorScheme::Green => 0,
+            ColorScheme::Blue => 1,
+            ColorScheme::Purple => 2,
+            ColorScheme::Red => 3,
+            ColorScheme::Yellow => 4,
+            ColorScheme::Rainbow => return None
+        };
+
+        let scheme_algorithms = SCHEME_ALGORITHMS.get_or_init(|| {
+            SCHEME_HUES.map(|hue| LightnessDescending::precompute(hue, 1.0, DEFAULT_MIN_LIGHTNESS, 1.0))
+        });
+        Some(&scheme_algorithms[scheme_index])
+    }
+}
+
+impl ColorAlgorithm for ColorScheme {
+    fn gen_color(&self, follower_proportion: f32, hue_seed: f32, context: &ColorContext) -> Color {
+        match self.scheme_algorithm() {
+            Some(scheme_algorithm) => scheme_algorithm.gen_color(follower_proportion, hue_seed, context),
+            None => HueVariation{saturation: 1.0, lightness: 0.5}
+                .gen_color(follower_proportion, hue_seed, context)
+        }
+    }
+}
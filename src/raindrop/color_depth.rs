@@ -0,0 +1,177 @@
+//! Mapping computed colors onto the palettes of terminals without 24-bit color
+
+use std::env;
+use coolor::{AnsiColor, Color, Rgb};
+
+// the levels of each channel in the 6x6x6 color cube of the xterm 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// the first palette index of the 6x6x6 color cube
+const CUBE_START: u8 = 16;
+
+// the first palette index of the 24-step grayscale ramp, which runs from 8 to 238 in steps of 10
+const GRAY_START: u8 = 232;
+
+// the default xterm colors of the 16 ANSI colors
+const ANSI_16: [Rgb; 16] = [
+    Rgb{r: 0, g: 0, b: 0},       //black
+    Rgb{r: 205, g: 0, b: 0},     //red
+    Rgb{r: 0, g: 205, b: 0},     //green
+    Rgb{r: 205, g: 205, b: 0},   //yellow
+    Rgb{r: 0, g: 0, b: 238},     //blue
+    Rgb{r: 205, g: 0, b: 205},   //magenta
+    Rgb{r: 0, g: 205, b: 205},   //cyan
+    Rgb{r: 229, g: 229, b: 229}, //white
+    Rgb{r: 127, g: 127, b: 127}, //bright black
+    Rgb{r: 255, g: 0, b: 0},     //bright red
+    Rgb{r: 0, g: 255, b: 0},     //bright green
+    Rgb{r: 255, g: 255, b: 0},   //bright yellow
+    Rgb{r: 92, g: 92, b: 255},   //bright blue
+    Rgb{r: 255, g: 0, b: 255},   //bright magenta
+    Rgb{r: 0, g: 255, b: 255},   //bright cyan
+    Rgb{r: 255, g: 255, b: 255}  //bright white
+];
+
+/// The number of colors a terminal can display
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorDepth {
+    /// Any 24-bit RGB color
+    #[default]
+    TrueColor,
+    /// The xterm 256-color palette
+    Ansi256,
+    /// The 16 ANSI colors
+    Ansi16
+}
+
+impl ColorDepth {
+    /// Guesses the color depth of the terminal from the environment
+    ///
+    /// A `COLORTERM` of `truecolor` or `24bit` means [TrueColor](ColorDepth::TrueColor),
+    /// and otherwise a `TERM` containing `256color` means [Ansi256](ColorDepth::Ansi256).
+    /// Any other `TERM` means [Ansi16](ColorDepth::Ansi16). If `TERM` isn't set at all
+    /// (as on Windows), the terminal is assumed to support true color.
+    pub fn detect() -> Self
+    {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+
+        match env::var("TERM") {
+            Err(_) => ColorDepth::TrueColor,
+            Ok(term) if term.contains("256color") => ColorDepth::Ansi256,
+            Ok(_) => ColorDepth::Ansi16
+        }
+    }
+
+    /// Returns the color closest to `color` that can be displayed at this depth
+    ///
+    /// At [TrueColor](ColorDepth::TrueColor), `color` is returned unchanged. Otherwise,
+    /// the nearest palette color is returned as a [Color::Ansi]. Colors that are
+    /// already in the palette are returned unchanged.
+    pub fn downsample(self, color: Color) -> Color
+    {
+        let rgb = match (self, color) {
+            (ColorDepth::TrueColor, _) => return color,
+            (ColorDepth::Ansi256, Color::Ansi(_)) => return color,
+            (ColorDepth::Ansi16, Color::Ansi(ansi)) if ansi.code < 16 => return color,
+            (_, Color::Ansi(ansi)) => ansi_to_rgb(ansi.code),
+            (_, Color::Rgb(rgb)) => rgb,
+            (_, Color::Hsl(hsl)) => hsl.to_rgb()
+        };
+
+        let code = match self {
+            ColorDepth::Ansi16 => nearest_ansi_16(rgb),
+            _ => nearest_ansi_256(rgb)
+        };
+        Color::Ansi(AnsiColor::new(code))
+    }
+}
+
+// returns the xterm default rgb value of a palette index
+pub(crate) fn ansi_to_rgb(code: u8) -> Rgb
+{
+    match code {
+        0..=15 => ANSI_16[usize::from(code)],
+        CUBE_START..=231 => {
+            let cube_index = code - CUBE_START;
+            Rgb{
+                r: CUBE_LEVELS[usize::from(cube_index / 36)],
+                g: CUBE_LEVELS[usize::from(cube_index / 6 % 6)],
+                b: CUBE_LEVELS[usize::from(cube_index % 6)]
+            }
+        },
+        _ => {
+            let level = 8 + 10 * (code - GRAY_START);
+            Rgb{r: level, g: level, b: level}
+        }
+    }
+}
+
+// returns the rgb value of a terminal color, using the xterm defaults for palette colors,
+// or None for the terminal's default color
+pub(crate) fn terminal_color_to_rgb(color: crossterm::style::Color) -> Option<Rgb>
+{
+    use crossterm::style::Color;
+    let ansi_code = match color {
+        Color::Rgb{r, g, b} => return Some(Rgb{r, g, b}),
+        Color::AnsiValue(code) => code,
+        Color::Reset => return None,
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15
+    };
+    Some(ansi_to_rgb(ansi_code))
+}
+
+// returns the squared distance between two colors
+fn distance(a: Rgb, b: Rgb) -> u32
+{
+    let channel_distance = |a: u8, b: u8| u32::from(a.abs_diff(b)).pow(2);
+    channel_distance(a.r, b.r) + channel_distance(a.g, b.g) + channel_distance(a.b, b.b)
+}
+
+// returns the index of the 16-color palette entry nearest to rgb
+fn nearest_ansi_16(rgb: Rgb) -> u8
+{
+    (0..16u8).min_by_key(|&code| distance(rgb, ANSI_16[usize::from(code)]))
+        .expect("palette is not empty")
+}
+
+// returns the index of the 256-color palette entry nearest to rgb,
+// considering only the color cube and grayscale ramp as the first 16 colors vary between terminals
+fn nearest_ansi_256(rgb: Rgb) -> u8
+{
+    //the nearest cube color is made of the nearest level of each channel
+    let nearest_level = |channel: u8| {
+        (0..6u8).min_by_key(|&level| channel.abs_diff(CUBE_LEVELS[usize::from(level)]))
+            .expect("cube has levels")
+    };
+    let cube_code = CUBE_START 
+        + 36 * nearest_level(rgb.r) + 6 * nearest_level(rgb.g) + nearest_level(rgb.b);
+
+    //the nearest gray is found from the average of the channels
+    let average = (u16::from(rgb.r) + u16::from(rgb.g) + u16::from(rgb.b)) / 3;
+    let gray_step = ((average.saturating_sub(3)) / 10).min(23) as u8;
+    let gray_code = GRAY_START + gray_step;
+
+    if distance(rgb, ansi_to_rgb(gray_code)) < distance(rgb, ansi_to_rgb(cube_code)) {
+        gray_code
+    } else {
+        cube_code
+    }
+}
@@ -0,0 +1,102 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io::{self, Write};
+use mrs_matrix::animation::AnimationState;
+use mrs_matrix::backend::{Backend, CrosstermBackend};
+use mrs_matrix::raindrop::{Raindrop, RaindropConfig};
+use mrs_matrix::raindrop::color_algorithms::ColorScheme;
+
+//counts the allocations made on each thread, so tests running in parallel don't see each other's
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8
+    {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout)
+    {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8
+    {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// returns the number of allocations made on this thread while running f
+fn count_allocations(f: impl FnOnce()) -> usize
+{
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn drawing_frames_reuses_one_buffer()
+{
+    let charset = vec!['X', 'Y', 'Z'];
+    let mut state = AnimationState::new(
+        &charset, ColorScheme::Green, 0.75, RaindropConfig::default(), Some(4), 80, 24);
+    let mut frames = Vec::new();
+    for _ in 0..40 {
+        state.step();
+        frames.push(state.frame().clone());
+    }
+
+    //the same output is drawn whether or not the buffer is reused
+    let mut backend = CrosstermBackend::new(Vec::new());
+    backend.draw_frame(&frames[1], Some(&frames[0])).unwrap();
+    assert_eq!(backend.into_inner(), frames[1].render_diff(&frames[0]).into_bytes());
+
+    //once the buffer has grown to fit the largest frame, drawing doesn't allocate at all,
+    //where rendering each frame to a new String allocates at least once per frame
+    let mut backend = CrosstermBackend::new(io::sink());
+    let mut draw_frames = || {
+        backend.draw_frame(&frames[0], None).unwrap();
+        for pair in frames.windows(2) {
+            backend.draw_frame(&pair[1], Some(&pair[0])).unwrap();
+        }
+    };
+    draw_frames();
+    let reused = count_allocations(draw_frames);
+    let fresh = count_allocations(|| {
+        for pair in frames.windows(2) {
+            io::sink().write_all(pair[1].render_diff(&pair[0]).as_bytes()).unwrap();
+        }
+    });
+    assert_eq!(reused, 0);
+    assert!(fresh >= frames.len() - 1);
+}
+
+#[test]
+fn respawning_reuses_follower_content()
+{
+    const TERMINAL_HEIGHT: u16 = 24;
+
+    let charset = vec!['X', 'Y', 'Z'];
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 5);
+
+    //once the follower has been as long as it can be, respawning never allocates
+    let longest_follower = *RaindropConfig::default().follower_length_range(TERMINAL_HEIGHT).end();
+    while raindrop.follower_len() < usize::from(longest_follower) {
+        raindrop.reinit_state(TERMINAL_HEIGHT);
+    }
+    let allocations = count_allocations(|| {
+        for _ in 0..100 {
+            raindrop.reinit_state(TERMINAL_HEIGHT);
+        }
+    });
+    assert_eq!(allocations, 0);
+}
@@ -0,0 +1,600 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use coolor::Color;
+use mrs_matrix::Frame;
+use mrs_matrix::backend::{CrosstermBackend, DrawCall, TestBackend};
+use mrs_matrix::animation::{anim_loop_with_backend, anim_loop_with_writer, resize_raindrops, AnimationState, FallAccumulator, FrameClock, LoopOptions, TerminalGuard};
+use mrs_matrix::raindrop::{Direction, Raindrop, RaindropConfig, RenderConfig};
+use mrs_matrix::raindrop::color_depth::ColorDepth;
+use mrs_matrix::raindrop::color_algorithms::{ColorAlgorithm, ColorContext, ColorScheme};
+
+// a writer that can still be inspected after being moved into something else
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn contents(&self) -> String
+    {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+}
+
+// a color algorithm that panics as soon as it's used
+#[derive(Clone, Copy)]
+struct PanickingAlgorithm;
+
+impl ColorAlgorithm for PanickingAlgorithm {
+    fn gen_color(&self, _follower_proportion: f32, _hue_seed: f32, _context: &ColorContext) -> Color
+    {
+        panic!("PanickingAlgorithm was asked for a color");
+    }
+}
+
+#[test]
+fn terminal_restored_on_panic()
+{
+    const TERM_COLS: u16 = 10;
+    const TERM_ROWS: u16 = 10;
+
+    let buffer = SharedBuffer::default();
+    let charset = vec!['X'];
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        //raw mode is left alone, as tests aren't run in a terminal
+        let mut guard = TerminalGuard::new(CrosstermBackend::new(buffer.clone()), false).unwrap();
+
+        let mut raindrops: Vec<Raindrop> = (0..TERM_COLS).map(|column_index| {
+            Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERM_ROWS, 
+                column_index.into())
+        }).collect();
+
+        //run frames until a follower becomes visible and the color algorithm panics
+        loop {
+            let frame = Frame::capture(&raindrops, TERM_COLS, TERM_ROWS, &PanickingAlgorithm, 
+                &RenderConfig::default(), 0.0);
+            guard.write_all(frame.render().as_bytes()).unwrap();
+            for raindrop in raindrops.iter_mut() {
+                raindrop.advance_animation(TERM_ROWS);
+            }
+        }
+    }));
+    assert!(result.is_err());
+
+    //the alternate screen should have been left and the cursor shown again
+    let contents = buffer.contents();
+    assert!(contents.starts_with("\x1b[?1049h\x1b[?25l"));
+    assert!(contents.ends_with("\x1b[?1049l\x1b[?25h"));
+}
+
+#[test]
+fn resize_keeps_surviving_columns()
+{
+    let charset = vec!['X'];
+    let config = RaindropConfig::default();
+    let seed = Some(7);
+    let (cols, rows) = (80, 24);
+
+    let mut raindrops: Vec<Raindrop> = (0..cols)
+        .map(|column| Raindrop::new_seeded(&charset, 0.75, config, rows, column))
+        .collect();
+    for _ in 0..40 {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(rows);
+        }
+    }
+    let row_indices: Vec<i32> = raindrops.iter().map(Raindrop::row_index).collect();
+
+    // narrowing drops the extra columns and leaves the rest alone
+    resize_raindrops(&mut raindrops, &charset, 0.75, config, seed, 60, rows);
+    assert_eq!(raindrops.len(), 60);
+    for (raindrop, row_index) in raindrops.iter().zip(&row_indices) {
+        assert_eq!(raindrop.row_index(), *row_index);
+    }
+
+    // widening adds new columns after the existing ones
+    resize_raindrops(&mut raindrops, &charset, 0.75, config, seed, 100, rows);
+    assert_eq!(raindrops.len(), 100);
+    for (raindrop, row_index) in raindrops.iter().zip(&row_indices[..60]) {
+        assert_eq!(raindrop.row_index(), *row_index);
+    }
+}
+
+#[test]
+fn resized_lanes_share_the_charset()
+{
+    let charset = vec!['X'];
+    let mut state = AnimationState::new(
+        &charset, ColorScheme::Green, 1.0, RaindropConfig::default(), Some(5), 40, 24);
+
+    // new lanes are created from the same charset as the original ones
+    state.resize(80, 24);
+    for _ in 0..60 {
+        state.step();
+    }
+    let chars: Vec<char> = (0..80)
+        .flat_map(|column| (0..24).map(move |row| (column, row)))
+        .filter_map(|(column, row)| state.frame().get(column, row))
+        .map(|styled_char| *styled_char.content())
+        .collect();
+    assert!(!chars.is_empty());
+    assert!(chars.iter().all(|c| *c == 'X'));
+}
+
+#[test]
+fn animation_state_steps_reproducibly()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let new_state = || AnimationState::new(
+        &charset, ColorScheme::Green, 0.75, RaindropConfig::default(), Some(3), 80, 24);
+
+    let mut first = new_state();
+    let mut second = new_state();
+    let initial_frame = first.frame().clone();
+    for _ in 0..10 {
+        first.step();
+        second.step();
+        assert_eq!(first.render(), second.render());
+    }
+
+    // some raindrops should have fallen into view by now
+    assert_ne!(first.frame(), &initial_frame);
+}
+
+#[test]
+fn frames_render_then_step()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let new_state = || AnimationState::new(
+        &charset, ColorScheme::Green, 0.75, RaindropConfig::default(), Some(4), 80, 24);
+
+    let mut iterated = new_state();
+    let frames: Vec<String> = iterated.frames().take(10).collect();
+
+    //the first frame is the current one, and each frame after it is one step further along
+    let mut stepped = new_state();
+    for frame in frames.iter() {
+        assert_eq!(frame, &stepped.render());
+        stepped.step();
+    }
+    assert_eq!(iterated.render(), stepped.render());
+}
+
+#[test]
+fn upward_rain_mirrors_downward_rain()
+{
+    let charset = vec!['X'];
+    let (cols, rows) = (40, 20);
+    let capture = |direction| {
+        let config = RaindropConfig{direction, ..RaindropConfig::default()};
+        let mut state = AnimationState::new(
+            &charset, ColorScheme::Green, 0.75, config, Some(5), cols, rows);
+        for _ in 0..50 {
+            state.step();
+        }
+        state.frame().clone()
+    };
+
+    let down = capture(Direction::Down);
+    let up = capture(Direction::Up);
+    for column_index in 0..cols {
+        for row_index in 0..rows {
+            assert_eq!(down.get(column_index, row_index), up.get(column_index, rows - 1 - row_index));
+        }
+    }
+}
+
+#[test]
+fn wind_rotates_each_row_of_the_rain()
+{
+    let charset: Vec<char> = ('A'..='Z').collect();
+    let (cols, rows) = (40, 20);
+    let capture = |wind| {
+        let mut state = AnimationState::new(
+            &charset, ColorScheme::Green, 0.75, RaindropConfig::default(), Some(3), cols, rows);
+        state.set_render_config(RenderConfig{wind, ..RenderConfig::default()});
+        for _ in 0..50 {
+            state.step();
+        }
+        state.frame().clone()
+    };
+    let chars = |frame: &Frame, column_index, row_index| {
+        frame.get(column_index, row_index).map(|styled_char| *styled_char.content())
+    };
+
+    let straight = capture(0.0);
+    let windy = capture(1.0);
+    let mut drifted_rows = 0;
+    for row_index in 0..rows {
+        //every row holds the same chars as without wind, wrapped around by some offset
+        let offset = (0..cols).find(|offset| (0..cols).all(|column_index| {
+            chars(&windy, (column_index + offset) % cols, row_index) 
+                == chars(&straight, column_index, row_index)
+        }));
+        assert!(offset.is_some(), "row {} isn't a rotation of the straight rain", row_index);
+        if row_index == 0 {
+            assert_eq!(offset, Some(0));
+        }
+        if offset != Some(0) {
+            drifted_rows += 1;
+        }
+    }
+    assert!(drifted_rows > 0);
+}
+
+#[test]
+fn horizontal_rain_transposes_vertical_rain()
+{
+    let charset = vec!['X'];
+    let size = 30;
+    let capture = |direction| {
+        let config = RaindropConfig{direction, ..RaindropConfig::default()};
+        let mut state = AnimationState::new(
+            &charset, ColorScheme::Green, 0.75, config, Some(9), size, size);
+        for _ in 0..50 {
+            state.step();
+        }
+        state.frame().clone()
+    };
+
+    let down = capture(Direction::Down);
+    let right = capture(Direction::Right);
+    let left = capture(Direction::Left);
+    for lane_index in 0..size {
+        for position in 0..size {
+            let expected = down.get(lane_index, position);
+            assert_eq!(right.get(position, lane_index), expected);
+            assert_eq!(left.get(size - 1 - position, lane_index), expected);
+        }
+    }
+}
+
+#[test]
+fn density_leaves_lanes_empty()
+{
+    let charset = vec!['X'];
+    let (cols, rows) = (200, 24);
+    let filled_columns = |density| {
+        let config = RaindropConfig{density, ..RaindropConfig::default()};
+        let mut state = AnimationState::new(
+            &charset, ColorScheme::Green, 1.0, config, Some(13), cols, rows);
+        for _ in 0..300 {
+            state.step();
+        }
+        let frame = state.frame();
+        (0..cols)
+            .filter(|column_index| (0..rows).any(|row_index| frame.get(*column_index, row_index).is_some()))
+            .count()
+    };
+
+    assert_eq!(filled_columns(0.0), 0);
+
+    let half_filled = filled_columns(0.5);
+    assert!((40..160).contains(&half_filled), "{} columns filled", half_filled);
+}
+
+#[test]
+fn lanes_hold_multiple_drops()
+{
+    let charset = vec!['X'];
+    let (cols, rows) = (80, 40);
+    let most_leaders_in_a_column = |drops_per_lane| {
+        let config = RaindropConfig{drops_per_lane, ..RaindropConfig::default()};
+        let mut state = AnimationState::new(
+            &charset, ColorScheme::Green, 1.0, config, Some(17), cols, rows);
+
+        let mut most_leaders = 0;
+        for _ in 0..200 {
+            state.step();
+            let frame = state.frame();
+            for column_index in 0..cols {
+                //leaders are the only bright white chars
+                let leaders = (0..rows)
+                    .filter_map(|row_index| frame.get(column_index, row_index))
+                    .filter(|styled_char| {
+                        styled_char.style().foreground_color == Some(crossterm::style::Color::AnsiValue(15))
+                    })
+                    .count();
+                most_leaders = most_leaders.max(leaders);
+            }
+        }
+        most_leaders
+    };
+
+    assert_eq!(most_leaders_in_a_column(1), 1);
+    let most_leaders = most_leaders_in_a_column(3);
+    assert!((2..=3).contains(&most_leaders), "{} leaders in one column", most_leaders);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_capture_matches_serial_capture()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let (cols, rows) = (120, 40);
+    let config = RaindropConfig{drops_per_lane: 2, ..RaindropConfig::default()};
+
+    // identically seeded lanes, advanced far enough that most columns are filled
+    let create_lanes = || {
+        let mut lanes: Vec<Vec<Raindrop>> = (0..cols)
+            .map(|column| vec![Raindrop::new_seeded(&charset, 0.75, config, rows, u64::from(column))])
+            .collect();
+        for _ in 0..60 {
+            for raindrop in lanes.iter_mut().flatten() {
+                raindrop.advance_animation(rows);
+            }
+        }
+        lanes
+    };
+
+    let render_config = RenderConfig::default();
+    let serial = Frame::capture_lanes(&create_lanes(), cols, rows, 
+        &ColorScheme::Green, &render_config, 0.0);
+    let parallel = Frame::capture_lanes_parallel(&create_lanes(), cols, rows, 
+        &ColorScheme::Green, &render_config, 0.0);
+    assert_eq!(serial, parallel);
+}
+
+#[test]
+fn frame_clock_does_not_drift()
+{
+    let frame_duration = Duration::from_millis(40);
+    let start_instant = Instant::now();
+    let mut frame_clock = FrameClock::new(start_instant, frame_duration);
+
+    //simulate a minute at 25 fps, where each frame takes an uneven amount of work 
+    //and every wait oversleeps slightly
+    let mut now = start_instant;
+    for frame_index in 0..1500u32 {
+        now += Duration::from_micros(2000 + 1000 * u64::from(frame_index % 7));
+        let next_frame_instant = frame_clock.schedule_next_frame(now);
+        now = next_frame_instant.max(now) + Duration::from_micros(300);
+    }
+
+    //the only drift left is the oversleep of the very last frame
+    let elapsed = now - start_instant;
+    assert!(elapsed >= Duration::from_secs(60), "ran fast: {:?}", elapsed);
+    assert!(elapsed <= Duration::from_secs(60) + Duration::from_millis(1), "drifted: {:?}", elapsed);
+}
+
+#[test]
+fn frame_clock_skips_ahead_after_overrun()
+{
+    let frame_duration = Duration::from_millis(40);
+    let start_instant = Instant::now();
+    let mut frame_clock = FrameClock::new(start_instant, frame_duration);
+
+    //a frame that overruns by a whole second starts the next frame immediately...
+    let now = start_instant + Duration::from_secs(1);
+    assert_eq!(frame_clock.schedule_next_frame(now), now);
+
+    //...and then carries on at the normal rate, rather than rushing to make up the lost frames
+    assert_eq!(frame_clock.schedule_next_frame(now), now + frame_duration);
+}
+
+#[test]
+fn fall_speed_scales_steps_per_frame()
+{
+    let steps_over = |fall_speed: f64, frames: usize| -> Vec<u32> {
+        let mut fall_accumulator = FallAccumulator::new(fall_speed);
+        (0..frames).map(|_| fall_accumulator.steps_for_frame()).collect()
+    };
+
+    //a fall speed of 1.0 leaves the animation stepping once per frame, as before
+    assert!(steps_over(1.0, 100).iter().all(|steps| *steps == 1));
+    assert!(steps_over(3.0, 100).iter().all(|steps| *steps == 3));
+
+    //fractional speeds carry the remainder over, so steps are spread evenly over frames
+    assert_eq!(steps_over(0.25, 8), [0, 0, 0, 1, 0, 0, 0, 1]);
+    assert_eq!(steps_over(1.5, 4), [1, 2, 1, 2]);
+    assert_eq!(steps_over(0.1, 1000).iter().sum::<u32>(), 100);
+}
+
+#[test]
+fn tiny_terminals_do_not_panic()
+{
+    let charset = vec!['X'];
+    for (cols, rows) in [(0, 0), (1, 1), (80, 1), (1, 80), (0, 24), (80, 0)] {
+        for direction in [Direction::Down, Direction::Up, Direction::Left, Direction::Right] {
+            let config = RaindropConfig{direction, drops_per_lane: 2, speed_variation: true, 
+                glitch_rate: 0.5, density: 0.5, ..RaindropConfig::default()};
+            let mut state = AnimationState::new(
+                &charset, ColorScheme::Green, 1.0, config, Some(1), cols, rows);
+            state.set_message(Some(String::from("Wake up")));
+            for _ in 0..200 {
+                state.step();
+                state.render();
+            }
+            state.resize(40, 10);
+            state.step();
+            state.resize(cols, rows);
+            for _ in 0..50 {
+                state.step();
+                state.render();
+            }
+        }
+    }
+}
+
+#[test]
+fn messages_are_centered_in_front_of_the_rain()
+{
+    let charset = vec!['X'];
+    let mut state = AnimationState::new(&charset, ColorScheme::Green, 1.0, 
+        RaindropConfig::default(), Some(2), 20, 9);
+    state.set_message(Some(String::from("Wake up")));
+
+    //the message is on the middle row, centered, with its space left empty, on every frame
+    for _ in 0..100 {
+        state.step();
+        let frame = state.frame();
+        let message_row: String = (6..13).map(|column| {
+            frame.get(column, 4).map_or(' ', |styled_char| *styled_char.content())
+        }).collect();
+        assert_eq!(message_row, "Wake up");
+
+        //it's styled like the leaders, in bold bright white
+        let style = *frame.get(6, 4).unwrap().style();
+        assert_eq!(style.foreground_color, Some(crossterm::style::Color::AnsiValue(15)));
+        assert!(style.attributes.has(crossterm::style::Attribute::Bold));
+    }
+
+    //a message wider than the terminal is cut off at its right edge
+    state.set_message(Some(String::from("0123456789abcdefghijklmnopqrstuv")));
+    let message_row: String = (0..20).map(|column| *state.frame().get(column, 4).unwrap().content())
+        .collect();
+    assert_eq!(message_row, "0123456789abcdefghij");
+
+    //and removing the message lets the rain through again
+    state.set_message(None);
+    let mut rain_in_row = false;
+    for _ in 0..100 {
+        state.step();
+        rain_in_row |= (0..20).any(|column| state.frame().get(column, 4).is_some());
+    }
+    assert!(rain_in_row);
+}
+
+#[test]
+fn dimmed_frames_fade_to_black()
+{
+    use crossterm::style::{Attribute, Color};
+
+    let charset = vec!['X'];
+    let mut state = AnimationState::new(&charset, ColorScheme::Green, 1.0, 
+        RaindropConfig::default(), Some(3), 20, 9);
+    state.set_message(Some(String::from("Wake up")));
+    for _ in 0..30 {
+        state.step();
+    }
+    let frame = state.frame();
+    let color_depth = ColorDepth::TrueColor;
+
+    //full brightness leaves the frame as it is, and zero brightness leaves it empty
+    assert_eq!(frame.dimmed(1.0, color_depth), *frame);
+    let black = frame.dimmed(0.0, color_depth);
+    assert!((0..20).all(|column| (0..9).all(|row| black.get(column, row).is_none())));
+
+    //in between, each color keeps that proportion of its channels, and every other style is kept
+    let half = frame.dimmed(0.5, color_depth);
+    let style = *half.get(6, 4).unwrap().style();
+    assert_eq!(style.foreground_color, Some(Color::Rgb{r: 128, g: 128, b: 128}));
+    assert!(style.attributes.has(Attribute::Bold));
+    assert_eq!(half.get(6, 4).map(|cell| *cell.content()), Some('W'));
+    for column in 0..20 {
+        for row in 0..9 {
+            assert_eq!(half.get(column, row).is_some(), frame.get(column, row).is_some());
+        }
+    }
+}
+
+#[test]
+fn on_frame_sees_every_frame_drawn()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let mut frame_indices = Vec::new();
+    let mut rendered_frames = Vec::new();
+    let mut on_frame = |state: &AnimationState<_>, frame_index| {
+        frame_indices.push(frame_index);
+        rendered_frames.push(state.render());
+    };
+
+    let mut out = Vec::new();
+    let options = LoopOptions{
+        seed: Some(9), 
+        frames: Some(5), 
+        size: Some((20, 10)), 
+        on_frame: Some(&mut on_frame), 
+        ..LoopOptions::default()
+    };
+    anim_loop_with_writer(&mut out, charset, ColorScheme::Green, 1.0, 1000, RaindropConfig::default(), options)
+        .unwrap();
+    assert_eq!(frame_indices, vec![0, 1, 2, 3, 4]);
+
+    //the first frame is drawn in full, and the callback sees it as it was drawn
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with(&rendered_frames[0]));
+}
+
+#[test]
+fn backends_record_what_the_loop_draws()
+{
+    let charset = vec!['X'];
+    let mut backend = TestBackend::new();
+    let options = LoopOptions{seed: Some(2), frames: Some(3), size: Some((4, 2)), ..LoopOptions::default()};
+    anim_loop_with_backend(&mut backend, charset, ColorScheme::Green, 1.0, 1000, RaindropConfig::default(), options)
+        .unwrap();
+    let calls = backend.calls();
+
+    //the first frame is drawn in full, cell by cell, and every frame is flushed
+    for (cell_index, call) in calls.iter().step_by(2).take(8).enumerate() {
+        assert_eq!(*call, DrawCall::MoveTo(cell_index as u16 % 4, cell_index as u16 / 4));
+    }
+    assert_eq!(calls.iter().filter(|call| **call == DrawCall::Flush).count(), 4);
+
+    //without a terminal the user is looking at, the terminal is never set up
+    assert!(!calls.contains(&DrawCall::EnterAlternateScreen));
+    assert!(!calls.contains(&DrawCall::EnableRawMode));
+}
+
+#[test]
+fn ripples_lighten_the_rain_around_them_then_fade()
+{
+    use crossterm::style::Color;
+
+    let charset = vec!['X'];
+    let new_state = || {
+        let mut state = AnimationState::new(&charset, ColorScheme::Green, 1.0, 
+            RaindropConfig::default(), Some(3), 20, 9);
+        for _ in 0..30 {
+            state.step();
+        }
+        state
+    };
+    let lightness = |state: &AnimationState<ColorScheme>, column, row| {
+        match state.frame().get(column, row).and_then(|cell| cell.style().foreground_color) {
+            Some(Color::Rgb{r, g, b}) => u32::from(r) + u32::from(g) + u32::from(b),
+            _ => 0
+        }
+    };
+
+    let plain = new_state();
+    let mut rippled = new_state();
+    rippled.add_ripple(10, 4);
+
+    //every char near the ripple is at least as light as it was, and the center is lighter
+    for column in 0..20 {
+        for row in 0..9 {
+            assert!(lightness(&rippled, column, row) >= lightness(&plain, column, row));
+        }
+    }
+    let colored_cells: Vec<(u16, u16)> = (8..=12)
+        .flat_map(|column| (3..=5).map(move |row| (column, row)))
+        .filter(|(column, row)| lightness(&plain, *column, *row) > 0)
+        .collect();
+    assert!(colored_cells.iter()
+        .any(|(column, row)| lightness(&rippled, *column, *row) > lightness(&plain, *column, *row)));
+
+    //once the ripple has faded, the two animations look the same again
+    let mut plain = plain;
+    for _ in 0..12 {
+        plain.step();
+        rippled.step();
+    }
+    assert_eq!(plain.frame(), rippled.frame());
+}
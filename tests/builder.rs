@@ -0,0 +1,81 @@
+use std::error::Error;
+use mrs_matrix::{MatrixBuilder, MatrixError};
+use mrs_matrix::raindrop::color_algorithms::{ColorError, LightnessDescending};
+
+#[test]
+fn oneshot_frames_are_reproducible()
+{
+    let render = |seed| MatrixBuilder::new()
+        .charset(vec!['X'])
+        .seed(seed)
+        .size(20, 10)
+        .render_oneshot()
+        .unwrap();
+
+    let frame = render(5);
+    assert_eq!(frame, render(5));
+    assert_ne!(frame, render(6));
+
+    //one line per row, with rain already on the screen
+    assert_eq!(frame.lines().count(), 10);
+    assert!(frame.ends_with('\n'));
+    assert!(frame.contains('X'));
+
+    //the only escape codes are styles, which end in 'm'; cursor movements end in other letters
+    for escape_code in frame.split("\x1b[").skip(1) {
+        assert_eq!(escape_code.chars().find(char::is_ascii_alphabetic), Some('m'));
+    }
+}
+
+#[test]
+fn oneshot_frames_report_invalid_settings()
+{
+    let result = MatrixBuilder::new().charset(Vec::new()).size(20, 10).render_oneshot();
+    assert!(matches!(result, Err(MatrixError::Config(_))));
+}
+
+#[test]
+fn start_paused_is_ignored_without_input()
+{
+    let run = |start_paused| {
+        let mut out = Vec::new();
+        MatrixBuilder::new()
+            .seed(7)
+            .size(20, 10)
+            .framerate(1000)
+            .frames(20)
+            .start_paused(start_paused)
+            .run_with_writer(&mut out, false)
+            .expect("writing to a Vec should never fail");
+        out
+    };
+
+    //with no way to resume, a non-interactive animation runs as if it had never been paused
+    assert_eq!(run(true), run(false));
+}
+
+#[test]
+fn invalid_settings_are_returned_as_errors()
+{
+    let mut out = Vec::new();
+    let error = MatrixBuilder::new()
+        .charset(Vec::new())
+        .frames(1)
+        .run_with_writer(&mut out, false)
+        .unwrap_err();
+    assert!(matches!(error, MatrixError::Config(_)));
+    assert!(out.is_empty(), "nothing should be drawn with an invalid setting");
+
+    let error = MatrixBuilder::new()
+        .framerate(0)
+        .frames(1)
+        .run_with_writer(&mut out, false)
+        .unwrap_err();
+    assert!(matches!(error, MatrixError::Config(_)));
+
+    //color errors can be passed along with ?, keeping the original error as the source
+    let color = || -> Result<LightnessDescending, MatrixError> {Ok(LightnessDescending::new(400.0, 1.0)?)};
+    let error = color().err().expect("a hue of 400 is out of range");
+    assert!(matches!(error, MatrixError::InvalidColor(ColorError::Hue(_))));
+    assert!(error.source().is_some());
+}
@@ -0,0 +1,55 @@
+use std::io::Write;
+use std::time::Duration;
+use mrs_matrix::MatrixBuilder;
+use mrs_matrix::cast::CastWriter;
+
+#[test]
+fn flushes_become_timed_events()
+{
+    let mut cast = Vec::new();
+    {
+        let mut writer = CastWriter::new(&mut cast, 80, 24, Duration::from_millis(40)).unwrap();
+        write!(writer, "a\"b").unwrap();
+        writeln!(writer, "\\c").unwrap();
+        writer.flush().unwrap();
+
+        //flushing with nothing written doesn't add an event, but does take up time
+        writer.flush().unwrap();
+
+        //anything left over is written when the writer is dropped
+        write!(writer, "\x1b[1m").unwrap();
+    }
+
+    let cast = String::from_utf8(cast).unwrap();
+    let lines: Vec<_> = cast.lines().collect();
+    assert_eq!(lines, [
+        r#"{"version": 2, "width": 80, "height": 24}"#,
+        r#"[0.000000, "o", "a\"b\\c\n"]"#,
+        r#"[0.080000, "o", "\u001b[1m"]"#
+    ]);
+}
+
+#[test]
+fn seeded_recordings_are_reproducible()
+{
+    let record = |seed| {
+        let mut cast = Vec::new();
+        let writer = CastWriter::new(&mut cast, 20, 10, Duration::from_millis(10)).unwrap();
+        MatrixBuilder::new()
+            .seed(seed)
+            .size(20, 10)
+            .framerate(1000)
+            .frames(40)
+            .run_with_writer(writer, false)
+            .unwrap();
+        String::from_utf8(cast).unwrap()
+    };
+
+    let cast = record(3);
+    assert_eq!(cast, record(3));
+    assert_ne!(cast, record(4));
+
+    //a header, then events for the frames that drew something
+    assert!(cast.lines().count() > 2);
+    assert!(cast.lines().skip(1).all(|line| line.starts_with('[') && line.ends_with(']')));
+}
@@ -0,0 +1,177 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use mrs_matrix::raindrop::charsets::{Alphanumeric, AsciiAndSymbols, Braille, Charset, Composite, Custom, Katakana, PrintableAscii, Unsanitized, Weighted, is_single_width};
+
+#[test]
+fn iterators_match_collected_charsets()
+{
+    let charsets: [&dyn Charset; 5] = [&Alphanumeric(), &PrintableAscii(), &AsciiAndSymbols(), &Katakana(), &Braille()];
+    for charset in charsets {
+        let collected = charset.get_charset();
+        assert!(!collected.is_empty());
+        assert!(charset.charset_iter().eq(collected.iter().copied()));
+    }
+    assert_eq!(Alphanumeric().get_charset().len(), 62);
+    assert_eq!(PrintableAscii().get_charset().len(), 94);
+}
+
+#[test]
+fn get_charset_collects_charset_iter_by_default()
+{
+    struct Binary();
+    impl Charset for Binary {
+        fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_>
+        {
+            Box::new("01".chars())
+        }
+    }
+
+    assert_eq!(Binary().get_charset(), vec!['0', '1']);
+}
+
+#[test]
+fn len_and_contains_match_collected_charsets()
+{
+    let charsets: [&dyn Charset; 5] = [&Alphanumeric(), &PrintableAscii(), &AsciiAndSymbols(), &Katakana(), &Braille()];
+    for charset in charsets {
+        let collected = charset.get_charset();
+        assert_eq!(charset.len(), collected.len());
+        assert!(!charset.is_empty());
+
+        //every char in the set is contained, and nothing around the ranges sneaks in
+        for c in collected.iter() {
+            assert!(charset.contains(*c), "{:?} not contained", c);
+        }
+        let nearby = (0..0x3000).chain(0xFF00..0x10000);
+        for c in nearby.filter_map(char::from_u32) {
+            assert_eq!(charset.contains(c), collected.contains(&c), "{:?}", c);
+        }
+    }
+}
+
+#[test]
+fn len_and_contains_fall_back_to_charset_iter()
+{
+    struct Binary();
+    impl Charset for Binary {
+        fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_>
+        {
+            Box::new("01".chars())
+        }
+    }
+    struct Nothing();
+    impl Charset for Nothing {
+        fn charset_iter(&self) -> Box<dyn Iterator<Item = char> + '_>
+        {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    assert_eq!(Binary().len(), 2);
+    assert!(Binary().contains('1'));
+    assert!(!Binary().contains('2'));
+    assert!(!Binary().is_empty());
+    assert!(Nothing().is_empty());
+    assert_eq!(Nothing().len(), 0);
+}
+
+#[test]
+fn custom_charsets_keep_the_chars_of_a_string()
+{
+    let custom = Custom::from("01ab1");
+    assert_eq!(custom.get_charset(), vec!['0', '1', 'a', 'b', '1']);
+    assert_eq!(custom.len(), 5);
+    assert!(custom.contains('b'));
+    assert!(!custom.contains('c'));
+    assert!(Custom::default().is_empty());
+
+    //the default of a pre-made set is the same as its tuple-struct constructor
+    assert_eq!(AsciiAndSymbols::default().get_charset(), AsciiAndSymbols().get_charset());
+}
+
+#[test]
+fn weighted_charsets_pick_chars_by_weight()
+{
+    let weighted = Weighted::new(vec![('a', 30), ('b', 10), ('c', 0)]).unwrap();
+    assert_eq!(weighted.get_charset(), vec!['a', 'b']);
+    assert_eq!(weighted.len(), 2);
+    assert!(weighted.contains('b'));
+    assert!(!weighted.contains('c'));
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let sampler = weighted.sampler();
+    assert_eq!(sampler.chars(), ['a', 'b']);
+    let samples: Vec<char> = (0..4000).map(|_| sampler.sample(&mut rng)).collect();
+    let a_count = samples.iter().filter(|c| **c == 'a').count();
+    assert!(!samples.contains(&'c'));
+    assert!((2700..3300).contains(&a_count), "picked 'a' {} times out of 4000", a_count);
+
+    assert!(Weighted::new(vec![]).is_err());
+    assert!(Weighted::new(vec![('a', 0)]).is_err());
+
+    //however large the weights are, each char is only collected once
+    let heavy = Weighted::new(vec![('a', 1_000_000), ('b', 999_999)]).unwrap();
+    assert_eq!(heavy.get_charset(), vec!['a', 'b']);
+}
+
+#[test]
+fn matrix_charset_is_mostly_katakana()
+{
+    let matrix = Weighted::matrix();
+    assert_eq!(matrix.get_charset(), Katakana().get_charset());
+
+    let mut rng = StdRng::seed_from_u64(2);
+    let sampler = matrix.sampler();
+    let samples: Vec<char> = (0..2000).map(|_| sampler.sample(&mut rng)).collect();
+    let digits = samples.iter().filter(|c| c.is_ascii_digit()).count();
+    assert!(digits * 10 < samples.len());
+}
+
+#[test]
+fn composite_charsets_chain_their_members_without_duplicates()
+{
+    let composite = Composite(vec![Box::new(Custom::from("cab")), Box::new(Alphanumeric()), Box::new(Katakana())]);
+    let collected = composite.get_charset();
+
+    //chars keep the order they're first seen in
+    assert_eq!(collected[..4], ['c', 'a', 'b', '0']);
+    assert_eq!(collected.len(), 62 + Katakana().len() - 10);
+    assert_eq!(composite.len(), collected.len());
+    assert!(composite.contains('\u{FF66}'));
+    assert!(!composite.contains('!'));
+
+    assert!(Composite(vec![]).is_empty());
+    assert!(!composite.is_empty());
+}
+
+#[test]
+fn charsets_leave_out_chars_that_are_not_single_width()
+{
+    //combining marks take up no cells, and CJK ideographs take up two
+    let custom = Custom::from("a\u{301}b\u{20DD}漢字c");
+    assert_eq!(custom.get_charset(), vec!['a', 'b', 'c']);
+    assert_eq!(custom.len(), 3);
+    assert!(!custom.contains('\u{301}'));
+    assert!(!custom.contains('漢'));
+    assert!(Custom::from("\u{301}漢").is_empty());
+
+    let composite = Composite(vec![Box::new(Custom::from("漢x")), Box::new(Katakana())]);
+    assert!(composite.get_charset().iter().all(|c| is_single_width(*c)));
+
+    let weighted = Weighted::new(vec![('漢', 5), ('a', 1), ('\u{301}', 5)]).unwrap();
+    assert_eq!(weighted.get_charset(), vec!['a']);
+    assert!(Weighted::new(vec![('漢', 1)]).is_err());
+}
+
+#[test]
+fn unsanitized_charsets_keep_every_char()
+{
+    let unsanitized = Unsanitized(Custom::from("a\u{301}漢"));
+    assert_eq!(unsanitized.get_charset(), vec!['a', '\u{301}', '漢']);
+    assert_eq!(unsanitized.len(), 3);
+    assert!(unsanitized.contains('漢'));
+    assert_eq!(unsanitized.sampler().chars(), ['a', '\u{301}', '漢']);
+
+    //sets without any wide or zero-width chars are unchanged
+    assert_eq!(Unsanitized(Katakana()).get_charset(), Katakana().get_charset());
+}
@@ -0,0 +1,315 @@
+use coolor::{Color, Hsl, Rgb};
+use mrs_matrix::raindrop::color_algorithms::{
+    lerp_hue, Breathing, ColorAlgorithm, ColorError, Gradient, HueVariation, LightnessDescending, 
+    ColorContext, ColorScheme, Palette, RandomPerDrop, RotatingRainbow, SaturationDescending, ScreenGradient, Solid
+};
+
+fn hue_of(color: Color) -> f32
+{
+    match color {
+        Color::Hsl(hsl) => hsl.h,
+        _ => panic!("expected an HSL color")
+    }
+}
+
+// returns the context of a char drawn at the top of the screen, phase frames into the animation
+fn at_phase(phase: f32) -> ColorContext
+{
+    ColorContext{phase, ..ColorContext::default()}
+}
+
+#[test]
+fn gradient_takes_shorter_hue_arc()
+{
+    //green to blue should pass through cyan, not red
+    let gradient = Gradient::new(Hsl{h: 120.0, s: 1.0, l: 0.5}, Hsl{h: 240.0, s: 1.0, l: 0.5}).unwrap();
+    assert!((hue_of(gradient.gen_color(0.5, 0.0, &ColorContext::default())) - 180.0).abs() < 0.001);
+
+    //350 to 10 should wrap through 0 rather than sweeping back through 180
+    let gradient = Gradient::new(Hsl{h: 350.0, s: 1.0, l: 0.5}, Hsl{h: 10.0, s: 1.0, l: 0.5}).unwrap();
+    assert!(hue_of(gradient.gen_color(0.5, 0.0, &ColorContext::default())).abs() < 0.001);
+    assert!((hue_of(gradient.gen_color(0.25, 0.0, &ColorContext::default())) - 355.0).abs() < 0.001);
+}
+
+#[test]
+fn solid_ignores_distance_from_leader()
+{
+    let color = Hsl{h: 30.0, s: 0.8, l: 0.4};
+    let solid = Solid::new(color).unwrap();
+    for follower_proportion in [0.0, 0.25, 0.5, 1.0] {
+        assert_eq!(solid.gen_color(follower_proportion, 0.5, &ColorContext::default()), Color::Hsl(color));
+    }
+}
+
+#[test]
+fn palette_colors_followers_in_bands()
+{
+    let colors = vec![
+        Hsl{h: 0.0, s: 1.0, l: 0.5},
+        Hsl{h: 120.0, s: 1.0, l: 0.5},
+        Hsl{h: 240.0, s: 1.0, l: 0.5}
+    ];
+    let palette = Palette::new(colors.clone()).unwrap();
+    let color_at = |follower_proportion| palette.gen_color(follower_proportion, 0.0, &ColorContext::default());
+
+    //each color covers an equal share of the follower, without blending into the next
+    assert_eq!(color_at(0.0), Color::Hsl(colors[0]));
+    assert_eq!(color_at(0.3), Color::Hsl(colors[0]));
+    assert_eq!(color_at(0.34), Color::Hsl(colors[1]));
+    assert_eq!(color_at(0.6), Color::Hsl(colors[1]));
+    assert_eq!(color_at(0.7), Color::Hsl(colors[2]));
+    assert_eq!(color_at(1.0), Color::Hsl(colors[2]));
+
+    //a palette of one color is the same as a solid color
+    let single = Palette::new(vec![colors[1]]).unwrap();
+    for follower_proportion in [0.0, 0.5, 1.0] {
+        assert_eq!(single.gen_color(follower_proportion, 0.0, &ColorContext::default()), Color::Hsl(colors[1]));
+    }
+}
+
+#[test]
+fn constructors_reject_out_of_range_components()
+{
+    assert!(LightnessDescending::new(118.0, 1.0).is_ok());
+    assert_eq!(LightnessDescending::new(360.0, 1.0).err(), Some(ColorError::Hue(360.0)));
+    assert_eq!(LightnessDescending::new(118.0, 1.5).err(), Some(ColorError::Saturation(1.5)));
+
+    assert!(SaturationDescending::new(0.0, 0.5).is_ok());
+    assert_eq!(SaturationDescending::new(-1.0, 0.5).err(), Some(ColorError::Hue(-1.0)));
+    assert_eq!(SaturationDescending::new(0.0, -0.5).err(), Some(ColorError::Lightness(-0.5)));
+
+    assert!(HueVariation::new(1.0, 0.5).is_ok());
+    assert_eq!(HueVariation::new(2.0, 0.5).err(), Some(ColorError::Saturation(2.0)));
+    assert!(matches!(HueVariation::new(1.0, f32::NAN), Err(ColorError::Lightness(_))));
+
+    let green = Hsl{h: 120.0, s: 1.0, l: 0.5};
+    assert!(Gradient::new(green, Hsl{h: 240.0, s: 1.0, l: 0.5}).is_ok());
+    assert_eq!(Gradient::new(green, Hsl{h: 360.0, s: 1.0, l: 0.5}).err(), Some(ColorError::Hue(360.0)));
+    assert_eq!(Gradient::new(Hsl{h: 120.0, s: 1.2, l: 0.5}, green).err(), Some(ColorError::Saturation(1.2)));
+
+    assert!(Solid::new(green).is_ok());
+    assert_eq!(Solid::new(Hsl{h: 120.0, s: 1.0, l: -0.1}).err(), Some(ColorError::Lightness(-0.1)));
+
+    assert!(RandomPerDrop::new(1.0, 0.6).is_ok());
+    assert_eq!(RandomPerDrop::new(-0.5, 0.6).err(), Some(ColorError::Saturation(-0.5)));
+    assert!(matches!(RandomPerDrop::new(1.0, f32::NAN), Err(ColorError::Lightness(_))));
+
+    assert!(Palette::new(vec![green]).is_ok());
+    assert_eq!(Palette::new(Vec::new()).err(), Some(ColorError::EmptyPalette));
+    assert_eq!(Palette::new(vec![green, Hsl{h: -30.0, s: 1.0, l: 0.5}]).err(), Some(ColorError::Hue(-30.0)));
+}
+
+#[test]
+fn lightness_descending_floor_is_configurable()
+{
+    let color_at_tail = |lightness_descending: LightnessDescending| {
+        match lightness_descending.gen_color(1.0, 0.0, &ColorContext::default()) {
+            Color::Rgb(rgb) => rgb,
+            _ => panic!("expected an RGB color")
+        }
+    };
+    let lightness_descending = LightnessDescending::new(118.0, 1.0).unwrap();
+    assert_eq!(lightness_descending.min_lightness(), 0.1);
+
+    //with no floor, the end of the follower fades all the way to black
+    let to_black = lightness_descending.with_min_lightness(0.0).unwrap();
+    assert_eq!(to_black.min_lightness(), 0.0);
+    assert_eq!(color_at_tail(to_black), Rgb{r: 0, g: 0, b: 0});
+
+    //a higher floor keeps the whole follower at least that light, leaving the head unchanged
+    let bright = lightness_descending.with_min_lightness(0.5).unwrap();
+    assert_eq!(color_at_tail(bright), Hsl{h: 118.0, s: 1.0, l: 0.5}.to_rgb());
+    assert_eq!(bright.gen_color(0.0, 0.0, &ColorContext::default()), 
+        lightness_descending.gen_color(0.0, 0.0, &ColorContext::default()));
+
+    assert_eq!(lightness_descending.with_min_lightness(1.5).err(), Some(ColorError::Lightness(1.5)));
+    assert!(matches!(lightness_descending.with_min_lightness(f32::NAN), Err(ColorError::Lightness(_))));
+}
+
+#[test]
+fn lightness_descending_falloff_follows_exponent()
+{
+    let rgb_at = |lightness_descending: LightnessDescending, follower_proportion| {
+        match lightness_descending.gen_color(follower_proportion, 0.0, &ColorContext::default()) {
+            Color::Rgb(rgb) => rgb,
+            _ => panic!("expected an RGB color")
+        }
+    };
+    let linear = LightnessDescending::new(118.0, 1.0).unwrap();
+    assert_eq!(linear.exponent(), 1.0);
+
+    //at 1.0, the falloff is the same linear descent as before
+    let unchanged = linear.with_exponent(1.0).unwrap();
+    for step in 0..=10 {
+        let follower_proportion = step as f32 / 10.0;
+        assert_eq!(rgb_at(unchanged, follower_proportion), rgb_at(linear, follower_proportion));
+    }
+
+    //halfway along, a squared falloff has only lost a quarter of the way's lightness
+    //(to within a shade, as colors are precomputed in steps)
+    let squared = linear.with_exponent(2.0).unwrap().with_min_lightness(0.0).unwrap();
+    assert_eq!(squared.exponent(), 2.0);
+    let (expected, actual) = (Hsl{h: 118.0, s: 1.0, l: 0.65}.to_rgb(), rgb_at(squared, 0.5));
+    for (expected, actual) in [(expected.r, actual.r), (expected.g, actual.g), (expected.b, actual.b)] {
+        assert!(expected.abs_diff(actual) <= 2, "{:?} != {:?}", expected, actual);
+    }
+
+    //both ends are left where they were
+    assert_eq!(rgb_at(squared, 0.0), rgb_at(linear, 0.0));
+    assert_eq!(rgb_at(squared, 1.0), Rgb{r: 0, g: 0, b: 0});
+
+    assert_eq!(linear.with_exponent(0.0).err(), Some(ColorError::Exponent(0.0)));
+    assert!(matches!(linear.with_exponent(f32::INFINITY), Err(ColorError::Exponent(_))));
+}
+
+#[test]
+fn lightness_descending_matches_hsl()
+{
+    let lightness_descending = LightnessDescending::new(118.0, 1.0).unwrap();
+
+    //the precomputed colors should be within a shade of converting each color directly
+    for step in 0..=100 {
+        let follower_proportion = step as f32 / 100.0;
+        let expected = Hsl{h: 118.0, s: 1.0, l: (0.9 - follower_proportion).max(0.1)}.to_rgb();
+        let actual = match lightness_descending.gen_color(follower_proportion, 0.0, &ColorContext::default()) {
+            Color::Rgb(rgb) => rgb,
+            _ => panic!("expected an RGB color")
+        };
+        for (expected, actual) in [(expected.r, actual.r), (expected.g, actual.g), (expected.b, actual.b)] {
+            assert!(expected.abs_diff(actual) <= 2, "{:?} != {:?} at {}", expected, actual, follower_proportion);
+        }
+    }
+}
+
+#[test]
+fn lerp_hue_wraps_around()
+{
+    let close = |a: f32, b: f32| (a - b).abs() < 0.001;
+
+    assert!(close(lerp_hue(350.0, 10.0, 0.5), 0.0));
+    assert!(close(lerp_hue(350.0, 10.0, 0.75), 5.0));
+    assert!(close(lerp_hue(10.0, 350.0, 0.75), 355.0));
+    assert!(close(lerp_hue(120.0, 240.0, 0.5), 180.0));
+
+    //the ends of the arc are returned unchanged
+    assert!(close(lerp_hue(350.0, 10.0, 0.0), 350.0));
+    assert!(close(lerp_hue(350.0, 10.0, 1.0), 10.0));
+
+    //results always stay within [0, 360)
+    for step in 0..=100 {
+        let hue = lerp_hue(359.9, 0.1, step as f32 / 100.0);
+        assert!((0.0..360.0).contains(&hue), "{} is out of range", hue);
+    }
+}
+
+#[test]
+fn breathing_changes_over_time()
+{
+    let lightness_of = |color: Color| match color {
+        Color::Hsl(hsl) => hsl.l,
+        _ => panic!("expected an HSL color")
+    };
+    let breathing = Breathing::new(118.0, 1.0, 100.0).unwrap();
+
+    //brightest at the start of each breath, and dimmest halfway through
+    let brightest = lightness_of(breathing.gen_color(0.0, 0.0, &ColorContext::default()));
+    let dimmest = lightness_of(breathing.gen_color(0.0, 0.0, &at_phase(50.0)));
+    assert!(dimmest < brightest);
+    assert!((lightness_of(breathing.gen_color(0.0, 0.0, &at_phase(100.0))) - brightest).abs() < 0.001);
+
+    assert_eq!(Breathing::new(118.0, 1.0, 0.0).err(), Some(ColorError::Period(0.0)));
+}
+
+#[test]
+fn rotating_rainbow_matches_hue_variation_without_phase()
+{
+    let hue_variation = HueVariation::new(1.0, 0.5).unwrap();
+    let rotating_rainbow = RotatingRainbow::new(1.0, 0.5, 10.0).unwrap();
+
+    for follower_proportion in [0.0, 0.25, 0.5, 0.75] {
+        assert_eq!(rotating_rainbow.gen_color(follower_proportion, 0.0, &ColorContext::default()), 
+            hue_variation.gen_color(follower_proportion, 0.0, &ColorContext::default()));
+    }
+
+    //after 3 frames at 10 degrees per frame, every hue has turned by 30 degrees
+    assert!((hue_of(rotating_rainbow.gen_color(0.0, 0.0, &at_phase(3.0))) - 30.0).abs() < 0.001);
+    assert!((hue_of(rotating_rainbow.gen_color(0.95, 0.0, &at_phase(3.0))) - 12.0).abs() < 0.001);
+}
+
+#[test]
+fn screen_gradient_follows_screen_row()
+{
+    let screen_gradient = ScreenGradient::new(
+        Hsl{h: 120.0, s: 1.0, l: 0.5}, 
+        Hsl{h: 240.0, s: 1.0, l: 0.5}
+    ).unwrap();
+    let at_row = |row_index| ColorContext{row_index, term_rows: 25, ..ColorContext::default()};
+
+    //the color only depends on the row, not on the position within the follower
+    for follower_proportion in [0.0, 0.5, 1.0] {
+        assert!((hue_of(screen_gradient.gen_color(follower_proportion, 0.0, &at_row(0))) - 120.0).abs() < 0.001);
+        assert!((hue_of(screen_gradient.gen_color(follower_proportion, 0.0, &at_row(12))) - 180.0).abs() < 0.001);
+        assert!((hue_of(screen_gradient.gen_color(follower_proportion, 0.0, &at_row(24))) - 240.0).abs() < 0.001);
+    }
+
+    assert!(ScreenGradient::new(Hsl{h: 400.0, s: 1.0, l: 0.5}, Hsl{h: 0.0, s: 1.0, l: 0.5}).is_err());
+}
+
+#[test]
+fn distance_from_center_reaches_one_in_the_corners()
+{
+    let at = |column_index, row_index| ColorContext{
+        column_index, term_cols: 81, row_index, term_rows: 25, ..ColorContext::default()
+    };
+
+    assert_eq!(at(40, 12).distance_from_center(), 0.0);
+    assert!((at(0, 0).distance_from_center() - 1.0).abs() < 0.001);
+    assert!((at(80, 24).distance_from_center() - 1.0).abs() < 0.001);
+
+    //the middle of every edge is the same distance away, however wide the screen is
+    let edge_distance = at(0, 12).distance_from_center();
+    assert!((at(40, 0).distance_from_center() - edge_distance).abs() < 0.001);
+    assert!((edge_distance - 0.5f32.sqrt()).abs() < 0.001);
+
+    //without a screen, every char is treated as being in the center
+    assert_eq!(ColorContext::default().distance_from_center(), 0.0);
+}
+
+#[test]
+fn default_color_algorithms_are_green()
+{
+    let context = ColorContext::default();
+    assert_eq!(
+        LightnessDescending::default().gen_color(0.5, 0.0, &context),
+        ColorScheme::Green.gen_color(0.5, 0.0, &context)
+    );
+    assert_eq!(hue_of(SaturationDescending::default().gen_color(0.5, 0.0, &context)), 118.0);
+    assert_eq!(hue_of(Solid::default().gen_color(0.5, 0.0, &context)), 118.0);
+    assert_eq!(ColorScheme::default(), ColorScheme::Green);
+
+    //the algorithms taking whole colors fade from green to dark green
+    let is_green_of_lightness = |color: Color, lightness: f32| match color {
+        Color::Hsl(hsl) => hsl.h == 118.0 && hsl.s == 1.0 && (hsl.l - lightness).abs() < 0.001,
+        _ => panic!("expected an HSL color")
+    };
+    assert!(is_green_of_lightness(Gradient::default().gen_color(0.0, 0.0, &context), 0.5));
+    assert!(is_green_of_lightness(Gradient::default().gen_color(1.0, 0.0, &context), 0.1));
+    assert!(is_green_of_lightness(Palette::default().gen_color(0.0, 0.0, &context), 0.5));
+    assert!(is_green_of_lightness(Palette::default().gen_color(1.0, 0.0, &context), 0.1));
+    let bottom = ColorContext{row_index: 9, term_rows: 10, ..ColorContext::default()};
+    assert!(is_green_of_lightness(ScreenGradient::default().gen_color(0.5, 0.0, &context), 0.5));
+    assert!(is_green_of_lightness(ScreenGradient::default().gen_color(0.5, 0.0, &bottom), 0.1));
+
+    //breathing is green, taking 100 frames for each breath, and the rotating rainbow 
+    //starts out like the plain rainbow
+    let breathing = Breathing::new(118.0, 1.0, 100.0).unwrap();
+    for phase in [0.0, 25.0, 50.0] {
+        assert_eq!(Breathing::default().gen_color(0.5, 0.0, &at_phase(phase)), breathing.gen_color(0.5, 0.0, &at_phase(phase)));
+    }
+    for follower_proportion in [0.0, 0.25, 0.5] {
+        assert_eq!(
+            RotatingRainbow::default().gen_color(follower_proportion, 0.0, &context),
+            HueVariation::default().gen_color(follower_proportion, 0.0, &context)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use coolor::{AnsiColor, Color, Hsl};
+use mrs_matrix::raindrop::color_depth::ColorDepth;
+
+fn ansi(code: u8) -> Color
+{
+    Color::Ansi(AnsiColor::new(code))
+}
+
+#[test]
+fn true_color_is_unchanged()
+{
+    let color = Color::Hsl(Hsl{h: 118.0, s: 1.0, l: 0.5});
+    assert_eq!(ColorDepth::TrueColor.downsample(color), color);
+}
+
+#[test]
+fn colors_map_to_nearest_256_color()
+{
+    //pure red, green, and blue are corners of the color cube
+    assert_eq!(ColorDepth::Ansi256.downsample(Color::Hsl(Hsl{h: 0.0, s: 1.0, l: 0.5})), ansi(196));
+    assert_eq!(ColorDepth::Ansi256.downsample(Color::Hsl(Hsl{h: 120.0, s: 1.0, l: 0.5})), ansi(46));
+    assert_eq!(ColorDepth::Ansi256.downsample(Color::Hsl(Hsl{h: 240.0, s: 1.0, l: 0.5})), ansi(21));
+
+    //mid gray is closer to the grayscale ramp than to the cube
+    assert_eq!(ColorDepth::Ansi256.downsample(Color::Hsl(Hsl{h: 0.0, s: 0.0, l: 0.5})), ansi(244));
+
+    //palette colors are left alone
+    assert_eq!(ColorDepth::Ansi256.downsample(ansi(15)), ansi(15));
+}
+
+#[test]
+fn colors_map_to_nearest_16_color()
+{
+    assert_eq!(ColorDepth::Ansi16.downsample(Color::Hsl(Hsl{h: 0.0, s: 1.0, l: 0.5})), ansi(9));
+    assert_eq!(ColorDepth::Ansi16.downsample(Color::Hsl(Hsl{h: 120.0, s: 1.0, l: 0.4})), ansi(2));
+    assert_eq!(ColorDepth::Ansi16.downsample(Color::Hsl(Hsl{h: 0.0, s: 0.0, l: 0.5})), ansi(8));
+
+    //256-color palette entries are mapped down, but the 16 colors are left alone
+    assert_eq!(ColorDepth::Ansi16.downsample(ansi(196)), ansi(9));
+    assert_eq!(ColorDepth::Ansi16.downsample(ansi(15)), ansi(15));
+}
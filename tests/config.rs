@@ -0,0 +1,60 @@
+#![cfg(feature = "config")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// writes a config file under name, then runs the binary with it and the given command line, 
+// in a terminal that supports color
+fn run_with_config(name: &str, config: &str, command_line: &[&str]) -> Output
+{
+    let config_path = temp_path(name, "toml");
+    fs::write(&config_path, config).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mrs-matrix"))
+        .env("TERM", "xterm-256color")
+        .env_remove("NO_COLOR")
+        .arg("--config")
+        .arg(&config_path)
+        .args(command_line)
+        .output()
+        .unwrap();
+    fs::remove_file(&config_path).unwrap();
+    output
+}
+
+// returns a path in the temp directory that no other test (or test run) writes to
+fn temp_path(name: &str, extension: &str) -> PathBuf
+{
+    env::temp_dir().join(format!("mrs-matrix-{}-{}.{}", name, std::process::id(), extension))
+}
+
+#[test]
+fn command_line_overrides_conflicting_config_options()
+{
+    //the config's charset can't be given along with --custom-charset, so it's left out
+    let output = run_with_config("charset", "charset = \"katakana\"\nseed = 1\n",
+        &["--custom-charset", "X", "--oneshot", "--width", "12", "--height", "6"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let frame = String::from_utf8(output.stdout).unwrap();
+    assert!(frame.contains('X'));
+    assert!(!frame.chars().any(|c| ('\u{FF66}'..='\u{FF9D}').contains(&c)));
+
+    //the same goes for a flag the config turns on
+    let cast_path = temp_path("oneshot", "cast");
+    let output = run_with_config("oneshot", "oneshot = true\n",
+        &["--record", cast_path.to_str().unwrap(), "--frames", "2", "--width", "12", "--height", "6"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+    assert!(fs::read_to_string(&cast_path).unwrap().starts_with('{'));
+    fs::remove_file(&cast_path).unwrap();
+}
+
+#[test]
+fn command_line_can_turn_off_config_flags()
+{
+    let output = run_with_config("flags", "no-color = true\noneshot = true\nseed = 1\n",
+        &["--no-color=false", "--color-depth", "truecolor", "--width", "12", "--height", "6"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8(output.stdout).unwrap().contains("\x1b[38;2;"));
+}
@@ -0,0 +1,77 @@
+use mrs_matrix::AnimationState;
+use mrs_matrix::decode::DecodeEffect;
+use mrs_matrix::raindrop::RaindropConfig;
+use mrs_matrix::raindrop::color_algorithms::{ColorAlgorithm, ColorScheme};
+
+const TERMINAL_WIDTH: u16 = 20;
+const TERMINAL_HEIGHT: u16 = 9;
+
+// returns the chars of the middle row where a decode effect of the given length is drawn
+fn decode_row<T: ColorAlgorithm>(state: &AnimationState<T>, length: u16) -> String
+{
+    let first_column = (TERMINAL_WIDTH - length) / 2;
+    (first_column..first_column + length)
+        .map(|column| state.frame().get(column, TERMINAL_HEIGHT / 2).map_or(' ', |c| *c.content()))
+        .collect()
+}
+
+#[test]
+fn decoded_text_settles_into_place()
+{
+    let charset = vec!['X'];
+    let mut state = AnimationState::new(&charset, ColorScheme::Green, 1.0,
+        RaindropConfig::default(), Some(4), TERMINAL_WIDTH, TERMINAL_HEIGHT);
+    state.set_decode_effect(Some(DecodeEffect::new_seeded("Wake up", 0.1, 4)));
+
+    //before settling, unlocked cells flicker through the charset; after, the text stays put
+    let mut flickering_rows = 0;
+    for _ in 0..300 {
+        state.step();
+        let row = decode_row(&state, 7);
+        assert_eq!(row.chars().nth(4), Some(' '));
+        for (c, target) in row.chars().zip("Wake up".chars()) {
+            assert!(c == target || c == 'X', "{} is neither {} nor from the charset", c, target);
+        }
+        if row != "Wake up" {
+            flickering_rows += 1;
+        }
+    }
+    assert!(flickering_rows > 0);
+    assert_eq!(decode_row(&state, 7), "Wake up");
+}
+
+#[test]
+fn lock_rate_controls_settling()
+{
+    let charset = vec!['X'];
+
+    let mut never_settles = DecodeEffect::new_seeded("Neo", 0.0, 1);
+    let mut settles_at_once = DecodeEffect::new_seeded("Neo", 1.0, 1);
+    assert!(!settles_at_once.is_settled());
+    for _ in 0..100 {
+        never_settles.step(&charset);
+        settles_at_once.step(&charset);
+        assert!(!never_settles.is_settled());
+        assert!(settles_at_once.is_settled());
+    }
+
+    //text of nothing but spaces has nothing to settle
+    assert!(DecodeEffect::new("   ", 0.0).is_settled());
+}
+
+#[test]
+fn seeded_decode_effects_are_reproducible()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let settle = |seed| {
+        let mut state = AnimationState::new(&charset, ColorScheme::Green, 1.0,
+            RaindropConfig::default(), Some(1), TERMINAL_WIDTH, TERMINAL_HEIGHT);
+        state.set_decode_effect(Some(DecodeEffect::new_seeded("follow the", 0.05, seed)));
+        (0..30).map(|_| {
+            state.step();
+            decode_row(&state, 10)
+        }).collect::<Vec<_>>()
+    };
+    assert_eq!(settle(7), settle(7));
+    assert_ne!(settle(7), settle(8));
+}
@@ -0,0 +1,24 @@
+use std::env;
+use mrs_matrix::MatrixBuilder;
+use mrs_matrix::raindrop::{FollowerBold, RenderConfig};
+
+// this is the only test in its own binary, as changing the environment would affect any 
+// other test running alongside it
+#[test]
+fn dumb_terminals_are_drawn_without_styling()
+{
+    env::remove_var("NO_COLOR");
+    env::set_var("TERM", "dumb");
+
+    //bold followers are turned off along with color and bold leaders, leaving no escape codes
+    let render_config = RenderConfig{follower_bold: FollowerBold::All, ..RenderConfig::default()};
+    let frame = MatrixBuilder::new()
+        .charset(vec!['X'])
+        .seed(3)
+        .size(20, 10)
+        .render_config(render_config)
+        .render_oneshot()
+        .unwrap();
+    assert!(frame.contains('X'));
+    assert!(!frame.contains('\x1b'), "drew escape codes to a dumb terminal: {:?}", frame);
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "html")]
+
+use mrs_matrix::AnimationState;
+use mrs_matrix::export::render_frame_html;
+use mrs_matrix::raindrop::{RaindropConfig, RenderConfig};
+use mrs_matrix::raindrop::color_algorithms::ColorScheme;
+
+const TERMINAL_WIDTH: u16 = 20;
+const TERMINAL_HEIGHT: u16 = 10;
+
+// returns the HTML of a frame with rain on the whole screen
+fn render_html(charset: &Vec<char>, render_config: RenderConfig) -> String
+{
+    let mut animation_state = AnimationState::new(charset, ColorScheme::Green, 1.0,
+        RaindropConfig::default(), Some(9), TERMINAL_WIDTH, TERMINAL_HEIGHT);
+    animation_state.set_render_config(render_config);
+    for _ in 0..100 {
+        animation_state.step();
+    }
+    render_frame_html(animation_state.frame())
+}
+
+#[test]
+fn html_has_a_line_per_row_and_a_span_per_char()
+{
+    let html = render_html(&vec!['X'], RenderConfig::default());
+    assert!(html.starts_with("<pre"));
+    assert!(html.ends_with("</pre>\n"));
+
+    //the opening and closing tags are on their own lines
+    let lines: Vec<_> = html.lines().collect();
+    assert_eq!(lines.len(), usize::from(TERMINAL_HEIGHT) + 2);
+
+    //every char is wrapped in its own span, and every other cell is a space
+    for line in &lines[1..lines.len() - 1] {
+        let chars = line.matches("X</span>").count();
+        let spaces = line.split("</span>").map(|part| part.split("<span").next().unwrap())
+            .map(|between_spans| between_spans.chars().filter(|c| *c == ' ').count())
+            .sum::<usize>();
+        assert_eq!(chars + spaces, usize::from(TERMINAL_WIDTH));
+    }
+    assert!(html.contains("X</span>"));
+
+    //followers are green, and leaders are bold and white
+    assert!(html.contains("<span style=\"color:#ffffff;font-weight:bold\">X</span>"));
+    assert!(html.split("color:#").skip(1).any(|hex| {
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).unwrap();
+        channel(2) > channel(0) && channel(2) > channel(4)
+    }));
+}
+
+#[test]
+fn html_special_chars_are_escaped()
+{
+    let html = render_html(&vec!['<', '&'], RenderConfig::default());
+    assert!(html.contains("&lt;</span>"));
+    assert!(html.contains("&amp;</span>"));
+    assert!(!html.contains("><</span>"));
+}
+
+#[test]
+fn chars_without_color_have_no_style()
+{
+    let render_config = RenderConfig{use_color: false, bold_leader: false, ..RenderConfig::default()};
+    let html = render_html(&vec!['X'], render_config);
+    assert!(html.contains("<span>X</span>"));
+    assert!(!html.contains("<span style"));
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "gif")]
+
+use std::time::Duration;
+use gif::{ColorOutput, DecodeOptions};
+use mrs_matrix::{MatrixBuilder, MatrixError};
+use mrs_matrix::gif::{CELL_HEIGHT, CELL_WIDTH};
+
+const TERMINAL_WIDTH: u16 = 20;
+const TERMINAL_HEIGHT: u16 = 10;
+
+// returns the delay and RGBA pixels of every frame of a GIF
+fn decode(gif: &[u8]) -> Vec<(u16, Vec<u8>)>
+{
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.read_info(gif).unwrap();
+    assert_eq!(decoder.width(), TERMINAL_WIDTH * CELL_WIDTH);
+    assert_eq!(decoder.height(), TERMINAL_HEIGHT * CELL_HEIGHT);
+
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push((frame.delay, frame.buffer.to_vec()));
+    }
+    frames
+}
+
+fn builder(seed: u64) -> MatrixBuilder
+{
+    MatrixBuilder::new()
+        .seed(seed)
+        .size(TERMINAL_WIDTH, TERMINAL_HEIGHT)
+}
+
+#[test]
+fn seeded_gifs_are_reproducible()
+{
+    let render = |seed| {
+        let mut gif = Vec::new();
+        builder(seed).frames(40).render_gif(&mut gif).unwrap();
+        gif
+    };
+
+    let gif = render(3);
+    assert_eq!(gif, render(3));
+    assert_ne!(gif, render(4));
+
+    let frames = decode(&gif);
+    assert_eq!(frames.len(), 40);
+
+    //every frame lasts a frame at the default framerate of 25
+    assert!(frames.iter().all(|(delay, _)| *delay == 4));
+
+    //the rain is green, with a white leader
+    let (_, last_frame) = frames.last().unwrap();
+    let pixels: Vec<_> = last_frame.chunks(4).collect();
+    assert!(pixels.contains(&&[255, 255, 255, 255][..]));
+    assert!(pixels.iter().any(|pixel| pixel[1] > pixel[0] && pixel[1] > pixel[2]));
+}
+
+#[test]
+fn frame_delays_add_up_to_the_duration()
+{
+    //a third of a second can't be written in hundredths, but three of them add up to a second
+    let mut gif = Vec::new();
+    builder(1).framerate(30).duration(Duration::from_secs(1)).render_gif(&mut gif).unwrap();
+
+    let frames = decode(&gif);
+    assert_eq!(frames.len(), 30);
+    assert!(frames.iter().all(|(delay, _)| *delay == 3 || *delay == 4));
+    assert_eq!(frames.iter().map(|(delay, _)| delay).sum::<u16>(), 100);
+}
+
+#[test]
+fn gifs_need_a_length()
+{
+    let error = builder(1).render_gif(Vec::new()).unwrap_err();
+    assert!(matches!(error, MatrixError::Config(_)));
+}
@@ -0,0 +1,695 @@
+use crossterm::style::Color;
+use mrs_matrix::{render_frame, render_frame_ansi};
+use mrs_matrix::raindrop::{FollowerBold, Raindrop, RaindropConfig, RenderConfig, color_algorithms};
+use mrs_matrix::raindrop::color_algorithms::ColorContext;
+use mrs_matrix::raindrop::charsets::{CharSampler, Charset, Weighted};
+
+const TERMINAL_HEIGHT: u16 = 24;
+
+fn green() -> color_algorithms::LightnessDescending
+{
+    color_algorithms::LightnessDescending::new(118.0, 1.0).unwrap()
+}
+
+#[test]
+fn chars_come_from_charset()
+{
+    let charset = vec!['X'];
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    for _ in 0..100 {
+        assert_eq!(raindrop.gen_char(), 'X');
+    }
+
+    //advance far enough for the raindrop to fall through the whole terminal at least once,
+    //checking every char it displays (leader and follower) along the way
+    for _ in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            if let Some(c) = raindrop.get_char_at_row(row) {
+                assert_eq!(c, 'X');
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+}
+
+#[test]
+fn leader_char_only_changes_when_advanced()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 3);
+
+    let mut leaders_seen = 0;
+    let mut leader_changes = 0;
+    let mut previous_leader = None;
+    for _ in 0..200 {
+        if let Ok(leader_row) = u16::try_from(raindrop.row_index()) {
+            //reading the same frame twice gives the same leader
+            let leader = raindrop.get_char_at_row(leader_row);
+            assert_eq!(leader, raindrop.get_char_at_row(leader_row));
+            if leader.is_some() {
+                leaders_seen += 1;
+                if previous_leader.is_some() && leader != previous_leader {
+                    leader_changes += 1;
+                }
+            }
+            previous_leader = leader;
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(leaders_seen > 0);
+
+    //the leader is still re-rolled from frame to frame
+    assert!(leader_changes > 0);
+}
+
+#[test]
+fn leader_refresh_frames_holds_each_leader_char()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let config = RaindropConfig{leader_refresh_frames: 4, ..RaindropConfig::default()};
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, config, TERMINAL_HEIGHT, 4);
+
+    let mut leader_changes = 0;
+    let mut previous_leader = raindrop.leader_char();
+    for frame in 0..200 {
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+        let leader = raindrop.leader_char();
+        if leader != previous_leader {
+            //the leader may only change on every fourth advance
+            assert_eq!(frame % 4, 0);
+            leader_changes += 1;
+        }
+        previous_leader = leader;
+    }
+    assert!(leader_changes > 0);
+}
+
+#[test]
+fn static_leaders_keep_their_char_until_respawning()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let config = RaindropConfig{static_leader: true, ..RaindropConfig::default()};
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, config, TERMINAL_HEIGHT, 7);
+
+    let mut respawns = 0;
+    let mut leaders = vec![raindrop.leader_char()];
+    for _ in 0..500 {
+        let previous_row_index = raindrop.row_index();
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+        if raindrop.row_index() < previous_row_index {
+            respawns += 1;
+            leaders.push(raindrop.leader_char());
+        } else {
+            assert_eq!(raindrop.leader_char(), *leaders.last().unwrap());
+        }
+    }
+    assert!(respawns > 1);
+
+    //a new leader char is picked on respawning
+    assert!(leaders.windows(2).any(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn raindrops_draw_each_fall_from_one_of_their_charsets()
+{
+    let charset = vec!['a', 'b', 'c'];
+    let charsets = vec![CharSampler::new(charset.clone()), CharSampler::new(vec!['x', 'y', 'z'])];
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 8);
+    raindrop.set_charsets(&charsets);
+
+    let mut charsets_seen = [false; 2];
+    for _ in 0..1000 {
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+        let chars: Vec<char> = (0..TERMINAL_HEIGHT)
+            .filter_map(|row_index| raindrop.get_char_at_row(row_index))
+            .collect();
+        if chars.is_empty() {
+            continue;
+        }
+        let charset_index = charsets.iter().position(|charset| charset.chars().contains(&chars[0])).unwrap();
+        assert!(chars.iter().all(|c| charsets[charset_index].chars().contains(c)));
+        charsets_seen[charset_index] = true;
+    }
+    assert_eq!(charsets_seen, [true, true]);
+}
+
+#[test]
+fn raindrops_draw_chars_by_the_weights_of_their_charset()
+{
+    let charset = vec!['a', 'b'];
+    let weighted = [Weighted::new(vec![('a', 9), ('b', 1)]).unwrap().sampler()];
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 4);
+    raindrop.set_charsets(&weighted);
+
+    let chars: Vec<char> = (0..4000).map(|_| raindrop.gen_char()).collect();
+    let a_count = chars.iter().filter(|c| **c == 'a').count();
+    assert!((3400..3800).contains(&a_count), "drew 'a' {} times out of 4000", a_count);
+}
+
+#[test]
+fn respawning_draws_the_same_chars_for_the_same_seed()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 11);
+
+    //these were drawn before followers were refilled in place, rather than built up anew
+    let expected = ["ygkxydjxotx", "zqidttewjxiusgux", "nxumhuzpklcpyekjxx", "logcielufnx", "gpevohxjqkrsvjoydx"];
+    for expected_chars in expected {
+        raindrop.reinit_state(TERMINAL_HEIGHT);
+        raindrop.set_row_index(i32::from(TERMINAL_HEIGHT) - 1);
+        let chars: String = (0..TERMINAL_HEIGHT)
+            .filter_map(|row_index| raindrop.get_char_at_row(row_index))
+            .collect();
+        assert_eq!(chars, expected_chars);
+    }
+}
+
+#[test]
+fn recycled_siblings_match_spawned_siblings()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let config = RaindropConfig{speed_variation: true, ..RaindropConfig::default()};
+    let mut parent = Raindrop::new_seeded(&charset, 0.75, config, TERMINAL_HEIGHT, 9);
+    let mut recycling_parent = Raindrop::new_seeded(&charset, 0.75, config, TERMINAL_HEIGHT, 9);
+
+    //any finished raindrop can be recycled, whatever it was created with
+    let mut recycled = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 10);
+    for _ in 0..50 {
+        recycled.advance_animation(TERMINAL_HEIGHT);
+    }
+
+    let mut sibling = parent.spawn_sibling(TERMINAL_HEIGHT);
+    recycling_parent.spawn_sibling_into(&mut recycled, TERMINAL_HEIGHT);
+    for _ in 0..100 {
+        assert_eq!(recycled.row_index(), sibling.row_index());
+        assert_eq!(recycled.leader_char(), sibling.leader_char());
+        for row_index in 0..TERMINAL_HEIGHT {
+            assert_eq!(recycled.get_char_at_row(row_index), sibling.get_char_at_row(row_index));
+        }
+        sibling.advance_animation(TERMINAL_HEIGHT);
+        recycled.advance_animation(TERMINAL_HEIGHT);
+    }
+}
+
+#[test]
+fn accessors_match_the_chars_drawn()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 5);
+
+    for _ in 0..100 {
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+        assert_eq!(raindrop.tail_row_index(), raindrop.row_index() - raindrop.follower_len() as i32);
+        if let Ok(leader_row) = u16::try_from(raindrop.row_index()) {
+            assert_eq!(raindrop.get_char_at_row(leader_row), Some(raindrop.leader_char()));
+        }
+    }
+}
+
+#[test]
+fn raindrops_can_be_placed_on_a_row()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 6);
+    raindrop.advance_animation(TERMINAL_HEIGHT);
+
+    raindrop.set_row_index(10);
+    assert_eq!(raindrop.row_index(), 10);
+    assert_eq!(raindrop.get_char_at_row(10), Some(raindrop.leader_char()));
+    assert!(raindrop.get_char_at_row(9).is_some());
+    assert_eq!(raindrop.get_char_at_row(11), None);
+
+    //it carries on falling from the row it was placed on
+    raindrop.advance_animation(TERMINAL_HEIGHT);
+    assert_eq!(raindrop.row_index(), 11);
+
+    //rows far outside of the terminal are clamped, and a drop placed below the terminal respawns above it
+    raindrop.set_row_index(i32::MAX);
+    assert_eq!(raindrop.row_index(), i32::from(u16::MAX));
+    raindrop.set_row_index(i32::MIN);
+    assert_eq!(raindrop.row_index(), -i32::from(u16::MAX));
+    assert!(raindrop.tail_row_index() < raindrop.row_index());
+    raindrop.set_row_index(1000);
+    raindrop.advance_animation(TERMINAL_HEIGHT);
+    assert!(raindrop.row_index() < 0);
+}
+
+#[test]
+fn every_row_can_be_read_at_every_position()
+{
+    let charset = vec!['X'];
+    let mut raindrop = Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, 8);
+
+    //from above the top of the terminal to far below it, every row is either the leader,
+    //a follower, or empty; none of them should fail to find a follower index
+    while raindrop.row_index() < 300 {
+        for row in 0..=u16::MAX {
+            if let Some(c) = raindrop.get_char_at_row(row) {
+                assert_eq!(c, 'X');
+            }
+        }
+        raindrop.move_drop();
+    }
+}
+
+#[test]
+fn followers_are_styled_by_color_algorithm()
+{
+    let charset = vec!['X'];
+    let color_algorithm = color_algorithms::HueVariation::new(1.0, 0.5).unwrap();
+    let render_config = RenderConfig::default();
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    //collect the colors of every follower char shown while the raindrop falls
+    let mut follower_colors = Vec::new();
+    for _ in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            if let Some(styled_char) = raindrop.get_styled_char_at_row(row, &color_algorithm, &render_config, &ColorContext::default()) {
+                match styled_char.style().foreground_color {
+                    //the leader is bright white by default
+                    Some(Color::AnsiValue(15)) => (),
+                    Some(color) => follower_colors.push(color),
+                    None => panic!("follower char has no color")
+                }
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+
+    //HueVariation sweeps the whole color wheel, so some followers must be something other than green
+    assert!(follower_colors.iter().any(|color| match *color {
+        Color::Rgb{r, g, b} => g < r.max(b),
+        _ => false
+    }));
+}
+
+#[test]
+fn leader_bold_can_be_turned_off()
+{
+    let charset = vec!['X'];
+    let render_config = RenderConfig{bold_leader: false, ..RenderConfig::default()};
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    let mut leaders_seen = 0;
+    for _ in 0..200 {
+        if let Ok(leader_row) = u16::try_from(raindrop.row_index()) {
+            if let Some(styled_char) = raindrop.get_styled_char_at_row(leader_row, &green(), &render_config, &ColorContext::default()) {
+                assert_eq!(styled_char.style().foreground_color, Some(Color::AnsiValue(15)));
+                assert!(styled_char.style().attributes.is_empty());
+                leaders_seen += 1;
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(leaders_seen > 0);
+}
+
+#[test]
+fn leader_color_is_configurable()
+{
+    let charset = vec!['X'];
+    let render_config = RenderConfig{
+        leader_color: coolor::Color::Rgb(coolor::Rgb::new(200, 255, 200)),
+        ..RenderConfig::default()
+    };
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    let mut leaders_seen = 0;
+    for _ in 0..200 {
+        if let Ok(leader_row) = u16::try_from(raindrop.row_index()) {
+            if let Some(styled_char) = raindrop.get_styled_char_at_row(leader_row, &green(), &render_config, &ColorContext::default()) {
+                assert_eq!(styled_char.style().foreground_color, Some(Color::Rgb{r: 200, g: 255, b: 200}));
+                leaders_seen += 1;
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(leaders_seen > 0);
+}
+
+#[test]
+fn brightness_dims_every_char_but_never_hides_leaders()
+{
+    let charset = vec!['X'];
+    let solid = color_algorithms::Solid::new(coolor::Hsl::new(120.0, 1.0, 0.5)).unwrap();
+    let styled_color = |hsl: coolor::Hsl| Some(Color::from(coolor::Color::Hsl(hsl)));
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    let render_config = |brightness| RenderConfig{
+        leader_color: coolor::Color::Hsl(coolor::Hsl::new(0.0, 0.0, 1.0)),
+        brightness,
+        ..RenderConfig::default()
+    };
+    let (half, dark) = (render_config(0.5), render_config(0.0));
+
+    let mut chars_seen = 0;
+    for _ in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            let color_at = |render_config| raindrop
+                .get_styled_char_at_row(row, &solid, &render_config, &ColorContext::default())
+                .map(|styled_char| styled_char.style().foreground_color);
+            if let Some(half_color) = color_at(half) {
+                if i32::from(row) == raindrop.row_index() {
+                    //leaders are dimmed like followers, but only down to a fifth of their lightness
+                    assert_eq!(half_color, styled_color(coolor::Hsl::new(0.0, 0.0, 0.5)));
+                    assert_eq!(color_at(dark), Some(styled_color(coolor::Hsl::new(0.0, 0.0, 0.2))));
+                } else {
+                    assert_eq!(half_color, styled_color(coolor::Hsl::new(120.0, 1.0, 0.25)));
+                    assert_eq!(color_at(dark), Some(styled_color(coolor::Hsl::new(120.0, 1.0, 0.0))));
+                }
+                chars_seen += 1;
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(chars_seen > 0);
+}
+
+#[test]
+fn vignette_darkens_chars_away_from_the_center()
+{
+    let charset = vec!['X'];
+    let solid = color_algorithms::Solid::new(coolor::Hsl::new(120.0, 1.0, 0.5)).unwrap();
+    let render_config = RenderConfig{vignette: 1.0, ..RenderConfig::default()};
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    //the same char, as if it were drawn in the center of the screen, the middle of its left edge,
+    //or its bottom right corner
+    let at = |column_index, row_index| ColorContext{
+        column_index, term_cols: 81, row_index, term_rows: 25, ..ColorContext::default()
+    };
+    let solid_with_lightness = |l| Some(Color::from(coolor::Color::Hsl(coolor::Hsl::new(120.0, 1.0, l))));
+
+    let mut followers_seen = 0;
+    for _ in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            if i32::from(row) == raindrop.row_index() {
+                continue;
+            }
+            let color_at = |color_context| raindrop
+                .get_styled_char_at_row(row, &solid, &render_config, &color_context)
+                .map(|styled_char| styled_char.style().foreground_color);
+            if let Some(center_color) = color_at(at(40, 12)) {
+                //the vignette composes with the color algorithm, leaving the center untouched
+                assert_eq!(center_color, solid_with_lightness(0.5));
+                assert_eq!(color_at(at(0, 12)), Some(solid_with_lightness(0.25)));
+                assert_eq!(color_at(at(80, 24)), Some(solid_with_lightness(0.0)));
+                followers_seen += 1;
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(followers_seen > 0);
+}
+
+#[test]
+fn scanlines_dim_every_other_row()
+{
+    let charset = vec!['X'];
+    let solid = color_algorithms::Solid::new(coolor::Hsl::new(120.0, 1.0, 0.5)).unwrap();
+    let render_config = RenderConfig{scanlines: true, ..RenderConfig::default()};
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    let mut followers_seen = [0, 0];
+    for _ in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            if i32::from(row) == raindrop.row_index() {
+                continue;
+            }
+            let color_context = ColorContext{row_index: row, term_rows: TERMINAL_HEIGHT, ..ColorContext::default()};
+            if let Some(styled_char) = raindrop.get_styled_char_at_row(row, &solid, &render_config, &color_context) {
+                let lightness = if row % 2 == 0 {0.5} else {0.3};
+                assert_eq!(styled_char.style().foreground_color, 
+                    Some(Color::from(coolor::Color::Hsl(coolor::Hsl::new(120.0, 1.0, lightness)))));
+                followers_seen[usize::from(row % 2)] += 1;
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(followers_seen[0] > 0 && followers_seen[1] > 0);
+}
+
+#[test]
+fn glow_lightens_followers_near_the_leader()
+{
+    let charset = vec!['X'];
+    let solid = color_algorithms::Solid::new(coolor::Hsl::new(120.0, 1.0, 0.5)).unwrap();
+    let render_config = RenderConfig{glow: 2, ..RenderConfig::default()};
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    //lightening pure green raises its red channel (and blue) from zero, 
+    //by a third of the way to white for each step closer to the leader
+    let expected_red = |position_in_follower: i32| match position_in_follower {
+        0 => 170,
+        1 => 85,
+        _ => 0
+    };
+
+    let mut glowing_followers_seen = 0;
+    for _ in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            let position_in_follower = raindrop.row_index() - 1 - i32::from(row);
+            if position_in_follower < 0 {
+                continue;
+            }
+            if let Some(styled_char) = raindrop.get_styled_char_at_row(row, &solid, &render_config, 
+                &ColorContext::default()) 
+            {
+                match styled_char.style().foreground_color {
+                    Some(Color::Rgb{r, ..}) => assert!(
+                        (i32::from(r) - expected_red(position_in_follower)).abs() <= 1,
+                        "red of {} at {} chars behind the leader", r, position_in_follower),
+                    color => panic!("expected an rgb color, got {:?}", color)
+                }
+                if position_in_follower < 2 {
+                    glowing_followers_seen += 1;
+                }
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(glowing_followers_seen > 0);
+}
+
+#[test]
+fn followers_can_be_drawn_bold()
+{
+    use crossterm::style::Attribute;
+
+    let charset = vec!['X'];
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+    let is_bold = |styled_char: crossterm::style::StyledContent<char>| {
+        styled_char.style().attributes.has(Attribute::Bold)
+    };
+    let modes = [FollowerBold::None, FollowerBold::Random, FollowerBold::All];
+
+    //counts of followers that were bold and that weren't, for each mode
+    let mut counts = [[0, 0]; 3];
+    for frame in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            if i32::from(row) == raindrop.row_index() {
+                continue;
+            }
+            let color_context = ColorContext{phase: frame as f32, row_index: row, term_rows: TERMINAL_HEIGHT, 
+                ..ColorContext::default()};
+            for (mode_index, follower_bold) in modes.into_iter().enumerate() {
+                for use_color in [true, false] {
+                    let render_config = RenderConfig{follower_bold, use_color, ..RenderConfig::default()};
+                    let styled_char_at = || raindrop.get_styled_char_at_row(row, &green(), &render_config, 
+                        &color_context);
+                    if let Some(styled_char) = styled_char_at() {
+                        counts[mode_index][usize::from(is_bold(styled_char))] += 1;
+
+                        //the same char at the same time is always rolled the same way
+                        assert_eq!(styled_char_at().map(is_bold), Some(is_bold(styled_char)));
+                    }
+                }
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+
+    let [none, random, all] = counts;
+    assert!(none[0] > 0 && none[1] == 0);
+    assert!(all[0] == 0 && all[1] > 0);
+
+    //roughly half of the followers are bold at random
+    let random_total = random[0] + random[1];
+    assert!(random[1] * 3 > random_total && random[1] * 3 < random_total * 2, "{:?}", random);
+}
+
+#[test]
+fn chars_can_be_drawn_without_color()
+{
+    let charset = vec!['X'];
+    let render_config = RenderConfig{use_color: false, ..RenderConfig::default()};
+    let mut raindrop = Raindrop::new(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT);
+
+    let mut chars_seen = 0;
+    for _ in 0..200 {
+        for row in 0..TERMINAL_HEIGHT {
+            if let Some(styled_char) = raindrop.get_styled_char_at_row(row, &green(), &render_config, 
+                &ColorContext::default()) 
+            {
+                assert_eq!(styled_char.style().foreground_color, None);
+                chars_seen += 1;
+            }
+        }
+        raindrop.advance_animation(TERMINAL_HEIGHT);
+    }
+    assert!(chars_seen > 0);
+}
+
+#[test]
+fn seeded_raindrops_are_reproducible()
+{
+    const TERMINAL_WIDTH: u16 = 80;
+    let charset = vec!['a', 'b', 'c', 'd', 'e', 'f'];
+
+    //render a headless frame after a number of advances
+    let render_seeded_frame = |seed: u64| {
+        let mut raindrops: Vec<_> = (0..TERMINAL_WIDTH).map(|column| {
+            Raindrop::new_seeded(&charset, 0.75, RaindropConfig::default(), 
+                TERMINAL_HEIGHT, seed + u64::from(column))
+        }).collect();
+
+        for _ in 0..50 {
+            for raindrop in raindrops.iter_mut() {
+                raindrop.advance_animation(TERMINAL_HEIGHT);
+            }
+        }
+
+        render_frame(&raindrops, TERMINAL_WIDTH, TERMINAL_HEIGHT, &green(), &RenderConfig::default())
+    };
+
+    assert_eq!(render_seeded_frame(1234), render_seeded_frame(1234));
+    assert_ne!(render_seeded_frame(1234), render_seeded_frame(4321));
+}
+
+#[test]
+fn ansi_frames_are_styled_lines_without_cursor_movement()
+{
+    const TERMINAL_WIDTH: u16 = 30;
+    let charset = vec!['X'];
+    let mut raindrops: Vec<_> = (0..TERMINAL_WIDTH).map(|column| {
+        Raindrop::new_seeded(&charset, 1.0, RaindropConfig::default(), TERMINAL_HEIGHT, u64::from(column))
+    }).collect();
+    for _ in 0..50 {
+        for raindrop in raindrops.iter_mut() {
+            raindrop.advance_animation(TERMINAL_HEIGHT);
+        }
+    }
+
+    let frame = render_frame_ansi(&raindrops, TERMINAL_WIDTH, TERMINAL_HEIGHT, &green(), &RenderConfig::default());
+    assert_eq!(frame.lines().count(), usize::from(TERMINAL_HEIGHT));
+    assert!(frame.contains('X'));
+
+    for line in frame.lines() {
+        let escape_codes: Vec<_> = line.split("\x1b[").skip(1)
+            .map(|code| &code[..=code.find(|c: char| c.is_ascii_alphabetic()).unwrap()])
+            .collect();
+
+        //the only escape codes are styles, which end in 'm'; cursor movements end in other letters
+        assert!(escape_codes.iter().all(|code| code.ends_with('m')));
+
+        //every line ends with its style reset (entirely, or just the foreground color), 
+        //so nothing carries over to the next one
+        if let Some(last_code) = escape_codes.last() {
+            assert!(["0m", "39m"].contains(last_code), "line ends with style {}", last_code);
+        }
+    }
+}
+
+#[test]
+fn speed_variation_slows_some_raindrops()
+{
+    let charset = vec!['X'];
+    let config = RaindropConfig{speed_variation: true, ..RaindropConfig::default()};
+
+    //use a terminal tall enough that no raindrop respawns while being measured
+    let terminal_height = 1000;
+    let frames = 30;
+
+    let mut distances = Vec::new();
+    for seed in 0..50 {
+        let mut raindrop = Raindrop::new_seeded(&charset, 1.0, config, terminal_height, seed);
+        let start_row = raindrop.row_index();
+        for _ in 0..frames {
+            raindrop.advance_animation(terminal_height);
+        }
+        distances.push(raindrop.row_index() - start_row);
+    }
+
+    //every raindrop moves one row every 1, 2, or 3 frames
+    for distance in &distances {
+        assert!([frames, frames / 2, frames / 3].contains(distance), "moved {} rows", distance);
+    }
+    assert!(distances.contains(&frames));
+    assert!(distances.iter().any(|distance| *distance < frames));
+}
+
+#[test]
+fn advance_chance_staggers_raindrops()
+{
+    let charset = vec!['X'];
+
+    //use a terminal tall enough that no raindrop respawns while being measured
+    let terminal_height = 1000;
+    let frames = 40;
+
+    let distances_moved = |advance_chance| -> Vec<i32> {
+        (0..50).map(|seed| {
+            let mut raindrop = Raindrop::new_seeded(&charset, advance_chance, 
+                RaindropConfig::default(), terminal_height, seed);
+            let start_row = raindrop.row_index();
+            for _ in 0..frames {
+                raindrop.advance_animation(terminal_height);
+            }
+            raindrop.row_index() - start_row
+        }).collect()
+    };
+
+    //with no chance of advancing, no raindrop ever moves
+    assert!(distances_moved(0.0).iter().all(|distance| *distance == 0));
+
+    //with a certain chance, every raindrop moves on every frame, in lockstep
+    assert!(distances_moved(1.0).iter().all(|distance| *distance == frames));
+
+    //in between, each raindrop rolls for itself, so they fall different distances
+    let distances = distances_moved(0.5);
+    assert!(distances.iter().all(|distance| (0..frames).contains(distance)));
+    assert!(distances.iter().any(|distance| *distance != distances[0]));
+}
+
+#[test]
+fn glitching_changes_follower_chars()
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let terminal_height = 1000;
+
+    //with an advance chance of zero, the raindrop only moves when told to,
+    //so any change to the chars it displays must come from glitching
+    let followers_after_frames = |glitch_rate| {
+        let config = RaindropConfig{glitch_rate, ..RaindropConfig::default()};
+        let mut raindrop = Raindrop::new_seeded(&charset, 0.0, config, terminal_height, 11);
+        while raindrop.row_index() < 100 {
+            raindrop.move_drop();
+        }
+
+        let read_followers = |raindrop: &mut Raindrop| -> Vec<Option<char>> {
+            (0..100).map(|row| raindrop.get_char_at_row(row)).collect()
+        };
+        let before = read_followers(&mut raindrop);
+        for _ in 0..20 {
+            raindrop.advance_animation(terminal_height);
+        }
+        (before, read_followers(&mut raindrop))
+    };
+
+    let (before, after) = followers_after_frames(0.0);
+    assert_eq!(before, after);
+
+    let (before, after) = followers_after_frames(1.0);
+    assert_ne!(before, after);
+}
@@ -0,0 +1,65 @@
+use proptest::prelude::*;
+use mrs_matrix::raindrop::{Raindrop, RaindropConfig};
+
+// returns the rows of a terminal of height terminal_height that raindrop draws a char on
+fn drawn_rows(raindrop: &Raindrop, terminal_height: u16) -> Vec<u16>
+{
+    (0..terminal_height).filter(|row_index| raindrop.get_char_at_row(*row_index).is_some()).collect()
+}
+
+prop_compose! {
+    fn raindrop_configs()(follower_min_length in 1..20_u16, follower_max_length_offset in 0..20_u16, 
+        speed_variation: bool, glitch_rate in 0.0..=1.0_f32, density in 0.0..=1.0_f32) -> RaindropConfig
+    {
+        RaindropConfig{follower_min_length, follower_max_length_offset, speed_variation, glitch_rate, 
+            density, ..RaindropConfig::default()}
+    }
+}
+
+proptest! {
+    #[test]
+    fn raindrops_are_drawn_as_one_unbroken_run(terminal_height in 1..100_u16, seed: u64, 
+        advance_chance in 0.0..=1.0_f64, config in raindrop_configs(), frames in 0..200_usize)
+    {
+        let charset = vec!['a', 'b', 'c'];
+        let mut raindrop = Raindrop::new_seeded(&charset, advance_chance, config, terminal_height, seed);
+
+        for _ in 0..frames {
+            let rows = drawn_rows(&raindrop, terminal_height);
+
+            //the leader and follower are drawn on consecutive rows, with no gaps between them
+            if let (Some(first_row), Some(last_row)) = (rows.first(), rows.last()) {
+                prop_assert_eq!(usize::from(last_row - first_row) + 1, rows.len());
+            }
+
+            //nothing is drawn above the tail or below the leader
+            for row_index in 0..terminal_height {
+                let row = i32::from(row_index);
+                if row < raindrop.tail_row_index() || row > raindrop.row_index() {
+                    prop_assert_eq!(raindrop.get_char_at_row(row_index), None);
+                }
+            }
+
+            //a raindrop is visible exactly when it draws a char on some row
+            prop_assert_eq!(raindrop.is_visible(terminal_height), !rows.is_empty());
+
+            raindrop.advance_animation(terminal_height);
+        }
+    }
+
+    #[test]
+    fn raindrops_draw_their_whole_follower_once_on_screen(terminal_height in 1..100_u16, seed: u64, 
+        config in raindrop_configs())
+    {
+        let charset = vec!['a', 'b', 'c'];
+        let mut raindrop = Raindrop::new_seeded(&charset, 1.0, config, terminal_height, seed);
+
+        //every row between the tail and the leader holds a char, wherever the raindrop is placed
+        for row in -5..i32::from(terminal_height) + 5 {
+            raindrop.set_row_index(row);
+            let expected_rows = (raindrop.tail_row_index().max(0)..=row.min(i32::from(terminal_height) - 1))
+                .count();
+            prop_assert_eq!(drawn_rows(&raindrop, terminal_height).len(), expected_rows);
+        }
+    }
+}
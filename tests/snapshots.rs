@@ -0,0 +1,68 @@
+use mrs_matrix::Frame;
+use mrs_matrix::animation::AnimationState;
+use mrs_matrix::raindrop::RaindropConfig;
+use mrs_matrix::raindrop::color_algorithms::{ColorAlgorithm, ColorScheme, HueVariation};
+
+const TERM_COLS: u16 = 24;
+const TERM_ROWS: u16 = 10;
+const SEED: u64 = 7;
+const FRAMES: usize = 4;
+
+// the rain starts above the screen, so this many frames are stepped through before capturing
+const WARM_UP_FRAMES: usize = 20;
+
+// draws a few frames of a seeded animation with draw_frame, each after a header naming
+// it, so a changed snapshot shows which frame went wrong. colors are left at true color, so the
+// rendered frames hold exactly what a terminal would be sent
+fn capture_frames<T, F>(color_algorithm: T, draw_frame: F) -> String
+where T: ColorAlgorithm, F: Fn(&Frame) -> String
+{
+    let charset: Vec<char> = ('a'..='z').collect();
+    let mut state = AnimationState::new(
+        &charset, color_algorithm, 0.75, RaindropConfig::default(), Some(SEED), TERM_COLS, TERM_ROWS);
+    for _ in 0..WARM_UP_FRAMES {
+        state.step();
+    }
+    let mut output = String::new();
+    for frame_number in 0..FRAMES {
+        state.step();
+        output.push_str(&format!("--- frame {} ---\n", frame_number));
+        output.push_str(&draw_frame(state.frame()));
+    }
+    output
+}
+
+// returns just the chars of frame, one trimmed line per row, which is far easier to read in a
+// snapshot than the rendered escape codes
+fn frame_chars(frame: &Frame) -> String
+{
+    let (term_cols, term_rows) = frame.size();
+    let mut output = String::new();
+    for row_index in 0..term_rows {
+        let row: String = (0..term_cols)
+            .map(|column_index| frame.get(column_index, row_index).map_or(' ', |cell| *cell.content()))
+            .collect();
+        output.push_str(row.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+#[test]
+fn seeded_rain_draws_the_same_chars()
+{
+    insta::assert_snapshot!(capture_frames(ColorScheme::Green, frame_chars));
+}
+
+#[test]
+fn green_lightness_descending_renders_the_same_frames()
+{
+    let green = ColorScheme::Green.lightness_descending().unwrap();
+    insta::assert_snapshot!(capture_frames(green, Frame::render_lines));
+}
+
+#[test]
+fn hue_variation_renders_the_same_frames()
+{
+    insta::assert_snapshot!(capture_frames(HueVariation::default(), Frame::render_lines));
+}